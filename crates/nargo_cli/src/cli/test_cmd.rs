@@ -85,7 +85,7 @@ fn run_test<B: Backend>(
     driver: &Driver,
     config: &CompileOptions,
 ) -> Result<(), CliError<B>> {
-    let program = driver
+    let (program, _) = driver
         .compile_no_check(config, main)
         .map_err(|_| CliError::Generic(format!("Test '{test_name}' failed to compile")))?;
 
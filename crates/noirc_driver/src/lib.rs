@@ -9,7 +9,10 @@ use clap::Args;
 use fm::{FileId, FileManager, FileType};
 use noirc_abi::FunctionSignature;
 use noirc_errors::{CustomDiagnostic, FileDiagnostic};
-use noirc_evaluator::{create_circuit, ssa_refactor::experimental_create_circuit};
+use noirc_evaluator::{
+    create_circuit,
+    ssa_refactor::{experimental_create_circuit, BlackBoxHost},
+};
 use noirc_frontend::graph::{CrateId, CrateName, CrateType, LOCAL_CRATE};
 use noirc_frontend::hir::def_map::{Contract, CrateDefMap};
 use noirc_frontend::hir::Context;
@@ -28,6 +31,7 @@ pub struct Driver {
     context: Context,
     language: Language,
     is_opcode_supported: Box<dyn Fn(&Opcode) -> bool>,
+    black_box_host: Option<Box<dyn BlackBoxHost>>,
 }
 
 #[derive(Args, Clone, Debug, Serialize, Deserialize)]
@@ -73,7 +77,18 @@ pub type ErrorsAndWarnings = Vec<FileDiagnostic>;
 
 impl Driver {
     pub fn new(language: &Language, is_opcode_supported: Box<dyn Fn(&Opcode) -> bool>) -> Self {
-        Driver { context: Context::default(), language: language.clone(), is_opcode_supported }
+        Driver {
+            context: Context::default(),
+            language: language.clone(),
+            is_opcode_supported,
+            black_box_host: None,
+        }
+    }
+
+    /// Sets the implementation of black box functions used by the experimental SSA pipeline
+    /// to evaluate calls with constant inputs during compilation.
+    pub fn set_black_box_host(&mut self, black_box_host: Box<dyn BlackBoxHost>) {
+        self.black_box_host = Some(black_box_host);
     }
 
     // TODO(#1599): Move control of the FileManager into nargo
@@ -198,7 +213,7 @@ impl Driver {
         &mut self,
         options: &CompileOptions,
     ) -> Result<(CompiledProgram, Warnings), ErrorsAndWarnings> {
-        let warnings = self.check_crate(options.deny_warnings)?;
+        let mut warnings = self.check_crate(options.deny_warnings)?;
 
         let main = match self.main_function() {
             Some(m) => m,
@@ -211,7 +226,11 @@ impl Driver {
             }
         };
 
-        let compiled_program = self.compile_no_check(options, main)?;
+        let (compiled_program, mut compilation_warnings) = self.compile_no_check(options, main)?;
+        warnings.append(&mut compilation_warnings);
+        if Self::has_errors(&warnings, options.deny_warnings) {
+            return Err(warnings);
+        }
 
         if options.print_acir {
             println!("Compiled ACIR for main:");
@@ -234,7 +253,10 @@ impl Driver {
 
         for contract in contracts {
             match self.compile_contract(contract, options) {
-                Ok(contract) => compiled_contracts.push(contract),
+                Ok((contract, mut more_warnings)) => {
+                    compiled_contracts.push(contract);
+                    errors.append(&mut more_warnings);
+                }
                 Err(mut more_errors) => errors.append(&mut more_errors),
             }
         }
@@ -272,13 +294,17 @@ impl Driver {
         &self,
         contract: Contract,
         options: &CompileOptions,
-    ) -> Result<CompiledContract, Vec<FileDiagnostic>> {
+    ) -> Result<(CompiledContract, Warnings), Vec<FileDiagnostic>> {
         let mut functions = Vec::new();
         let mut errs = Vec::new();
+        let mut warnings = Vec::new();
         for function_id in &contract.functions {
             let name = self.function_name(*function_id).to_owned();
             let function = match self.compile_no_check(options, *function_id) {
-                Ok(function) => function,
+                Ok((function, mut function_warnings)) => {
+                    warnings.append(&mut function_warnings);
+                    function
+                }
                 Err(err) => {
                     errs.push(err);
                     continue;
@@ -300,7 +326,7 @@ impl Driver {
         }
 
         if errs.is_empty() {
-            Ok(CompiledContract { name: contract.name, functions })
+            Ok((CompiledContract { name: contract.name, functions }, warnings))
         } else {
             Err(errs)
         }
@@ -326,13 +352,24 @@ impl Driver {
     /// Compile the current crate. Assumes self.check_crate is called beforehand!
     ///
     /// This function also assumes all errors in experimental_create_circuit and create_circuit
-    /// are not warnings.
-    #[allow(deprecated)]
+    /// are not warnings. Any warnings found while compiling are returned with the program.
     pub fn compile_no_check(
         &self,
         options: &CompileOptions,
         main_function: FuncId,
-    ) -> Result<CompiledProgram, FileDiagnostic> {
+    ) -> Result<(CompiledProgram, Warnings), FileDiagnostic> {
+        self.compile_no_check_with_ssa_callback(options, main_function, None)
+    }
+
+    /// Like [`Driver::compile_no_check`], but with the experimental SSA pipeline calls
+    /// `ssa_pass_callback` with the label and printed SSA after each of its passes.
+    #[allow(deprecated)]
+    pub fn compile_no_check_with_ssa_callback(
+        &self,
+        options: &CompileOptions,
+        main_function: FuncId,
+        ssa_pass_callback: Option<&mut dyn FnMut(&str, &str)>,
+    ) -> Result<(CompiledProgram, Warnings), FileDiagnostic> {
         let program = monomorphize(main_function, &self.context.def_interner);
 
         let np_language = self.language.clone();
@@ -344,7 +381,21 @@ impl Driver {
                 &self.is_opcode_supported,
                 options.show_ssa,
                 options.show_output,
+                ssa_pass_callback,
+                self.black_box_host.as_deref(),
             )
+            .map(|(circuit, abi, warnings)| {
+                // Warnings without a location are reported in the file of the compiled function
+                let file_id = self.context.def_interner.function_meta(&main_function).location.file;
+                let warnings = warnings
+                    .into_iter()
+                    .map(|warning| FileDiagnostic {
+                        file_id: warning.location.map_or(file_id, |location| location.file),
+                        diagnostic: warning.into(),
+                    })
+                    .collect();
+                (circuit, abi, warnings)
+            })
         } else {
             create_circuit(
                 program,
@@ -353,10 +404,11 @@ impl Driver {
                 options.show_ssa,
                 options.show_output,
             )
+            .map(|(circuit, abi)| (circuit, abi, Vec::new()))
         };
 
         match circuit_abi {
-            Ok((circuit, abi)) => Ok(CompiledProgram { circuit, abi }),
+            Ok((circuit, abi, warnings)) => Ok((CompiledProgram { circuit, abi }, warnings)),
             Err(err) => {
                 // The FileId here will be the file id of the file with the main file
                 // Errors will be shown at the call site without a stacktrace
@@ -164,6 +164,25 @@ impl<'block> BrilligBlock<'block> {
                 let condition = self.convert_ssa_value(*value, dfg);
                 self.brillig_context.constrain_instruction(condition);
             }
+            Instruction::ConstrainEqual(lhs, rhs) => {
+                let equal = Binary { lhs: *lhs, rhs: *rhs, operator: BinaryOp::Eq };
+                let is_equal = self.brillig_context.allocate_register();
+                self.convert_ssa_binary(&equal, dfg, is_equal);
+                self.brillig_context.constrain_instruction(is_equal);
+                self.brillig_context.deallocate_register(is_equal);
+            }
+            Instruction::ConstrainNotEqual(lhs, rhs) => {
+                // Brillig has no dedicated instruction, so this constrains !(lhs == rhs)
+                let equal = Binary { lhs: *lhs, rhs: *rhs, operator: BinaryOp::Eq };
+                let is_equal = self.brillig_context.allocate_register();
+                self.convert_ssa_binary(&equal, dfg, is_equal);
+
+                let is_not_equal = self.brillig_context.allocate_register();
+                self.brillig_context.not_instruction(is_equal, 1, is_not_equal);
+                self.brillig_context.constrain_instruction(is_not_equal);
+                self.brillig_context.deallocate_register(is_equal);
+                self.brillig_context.deallocate_register(is_not_equal);
+            }
             Instruction::Allocate => {
                 let value: crate::ssa_refactor::ir::map::Id<Value> =
                     dfg.instruction_results(instruction_id)[0];
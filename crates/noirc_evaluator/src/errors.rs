@@ -1,4 +1,4 @@
-use noirc_errors::{CustomDiagnostic as Diagnostic, FileDiagnostic, Location};
+use noirc_errors::{CustomDiagnostic as Diagnostic, DiagnosticKind, FileDiagnostic, Location};
 use thiserror::Error;
 
 #[derive(Debug)]
@@ -45,6 +45,29 @@ impl From<RuntimeError> for FileDiagnostic {
     }
 }
 
+/// A problem found while compiling a program which, unlike a `RuntimeError`,
+/// does not prevent the program from being compiled.
+#[derive(Debug)]
+pub struct RuntimeWarning {
+    pub location: Option<Location>,
+    pub message: String,
+}
+
+impl From<RuntimeWarning> for Diagnostic {
+    fn from(warning: RuntimeWarning) -> Diagnostic {
+        match warning.location {
+            Some(location) => {
+                Diagnostic::simple_warning(warning.message, String::new(), location.span)
+            }
+            None => {
+                let mut diagnostic = Diagnostic::from_message(&warning.message);
+                diagnostic.kind = DiagnosticKind::Warning;
+                diagnostic
+            }
+        }
+    }
+}
+
 #[derive(Error, Debug)]
 pub enum RuntimeErrorKind {
     // Array errors
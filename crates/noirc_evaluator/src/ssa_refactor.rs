@@ -7,17 +7,20 @@
 //! This module heavily borrows from Cranelift
 #![allow(dead_code)]
 
-use crate::errors::RuntimeError;
+use crate::errors::{RuntimeError, RuntimeWarning};
 use acvm::{
     acir::circuit::{Circuit, Opcode as AcirOpcode, PublicInputs},
     Language,
 };
+use iter_extended::vecmap;
 use noirc_abi::Abi;
 
 use noirc_frontend::monomorphization::ast::Program;
 
 use self::{abi_gen::gen_abi, acir_gen::GeneratedAcir, ssa_gen::Ssa};
 
+pub use self::opt::BlackBoxHost;
+
 mod abi_gen;
 mod acir_gen;
 pub mod ir;
@@ -27,45 +30,116 @@ pub mod ssa_gen;
 
 /// Optimize the given program by converting it into SSA
 /// form and performing optimizations there. When finished,
-/// convert the final SSA into ACIR and return it.
+/// convert the final SSA into ACIR and return it, along with
+/// any warnings found along the way.
+///
+/// If given, `ssa_pass_callback` is called with the label and printed SSA after each pass,
+/// and `black_box_host` evaluates black box calls whose inputs are all constant.
 pub(crate) fn optimize_into_acir(
     program: Program,
     allow_log_ops: bool,
     print_ssa_passes: bool,
-) -> GeneratedAcir {
+    ssa_pass_callback: Option<&mut dyn FnMut(&str, &str)>,
+    black_box_host: Option<&dyn BlackBoxHost>,
+) -> Result<(GeneratedAcir, Vec<RuntimeWarning>), RuntimeError> {
     let abi_distinctness = program.return_distinctness;
-    let ssa = ssa_gen::generate_ssa(program).print(print_ssa_passes, "Initial SSA:");
+    let mut printer = SsaPassPrinter { print_ssa_passes, callback: ssa_pass_callback };
+
+    let ssa = ssa_gen::generate_ssa(program)?
+        .print(&mut printer, "Initial SSA:")
+        .dead_instruction_elimination()
+        .print(&mut printer, "After Removing Dead Generated Instructions:")
+        .remove_unused_block_parameters()
+        .print(&mut printer, "After Removing Unused Block Parameters:")
+        .strength_reduction()
+        .print(&mut printer, "After Strength Reduction:")
+        .convert_tail_recursion()
+        .print(&mut printer, "After Converting Tail Recursion:")
+        .hoist_common_branch_prefixes()
+        .print(&mut printer, "After Hoisting Common Branch Prefixes:")
+        .specialize_constant_arguments()
+        .print(&mut printer, "After Specializing Constant Arguments:");
+    let warnings = vecmap(ssa.find_infinite_loops(), RuntimeWarning::from);
     let brillig = ssa.to_brillig();
-    ssa.inline_functions()
-        .print(print_ssa_passes, "After Inlining:")
+    let ssa = optimize_acir_functions(ssa, &mut printer, black_box_host)
+        .check_for_false_constraints()?
+        .finalize()?;
+    Ok((ssa.into_acir(brillig, abi_distinctness, allow_log_ops), warnings))
+}
+
+/// Runs each optimization pass needed to turn the program into a single
+/// function with a single block, ready to be converted into ACIR.
+///
+/// Black box calls on constants are only folded if a host implementing them is given.
+fn optimize_acir_functions(
+    ssa: Ssa,
+    printer: &mut SsaPassPrinter,
+    black_box_host: Option<&dyn BlackBoxHost>,
+) -> Ssa {
+    let ssa = ssa
+        .inline_functions()
+        .print(printer, "After Inlining:")
+        .hoist_constant_allocations()
+        .print(printer, "After Hoisting Constant Allocations:")
         .unroll_loops()
-        .print(print_ssa_passes, "After Unrolling:")
+        .print(printer, "After Unrolling:")
+        .deduplicate_blocks()
+        .print(printer, "After Deduplicating Blocks:")
         .simplify_cfg()
-        .print(print_ssa_passes, "After Simplifying:")
+        .print(printer, "After Simplifying:")
         .flatten_cfg()
-        .print(print_ssa_passes, "After Flattening:")
+        .print(printer, "After Flattening:")
         .mem2reg()
-        .print(print_ssa_passes, "After Mem2Reg:")
+        .print(printer, "After Mem2Reg:")
+        .dead_store_elimination()
+        .print(printer, "After Dead Store Elimination:")
         .fold_constants()
-        .print(print_ssa_passes, "After Constant Folding:")
+        .print(printer, "After Constant Folding:");
+
+    let ssa = match black_box_host {
+        Some(host) => ssa
+            .fold_constant_black_box_calls(host)
+            .print(printer, "After Folding Constant Black Box Calls:"),
+        None => ssa,
+    };
+
+    ssa.deduplicate_constant_arrays()
+        .print(printer, "After Deduplicating Constant Arrays:")
+        .combine_truncations()
+        .print(printer, "After Combining Truncations:")
+        .remove_redundant_constraints()
+        .print(printer, "After Removing Redundant Constraints:")
+        .remove_redundant_booleanity_constraints()
+        .print(printer, "After Removing Redundant Booleanity Constraints:")
         .dead_instruction_elimination()
-        .print(print_ssa_passes, "After Dead Instruction Elimination:")
-        .into_acir(brillig, abi_distinctness, allow_log_ops)
+        .print(printer, "After Dead Instruction Elimination:")
 }
 
 /// Compiles the Program into ACIR and applies optimizations to the arithmetic gates
 /// This is analogous to `ssa:create_circuit` and this method is called when one wants
 /// to use the new ssa module to process Noir code.
+///
+/// Any warnings found while compiling the program are returned alongside the circuit.
+/// The `ssa_pass_callback`, if any, receives the label and printed SSA after each SSA pass.
+/// If a `black_box_host` is given, black box calls on constants are evaluated with it.
 pub fn experimental_create_circuit(
     program: Program,
     np_language: Language,
     is_opcode_supported: &impl Fn(&AcirOpcode) -> bool,
     enable_logging: bool,
     show_output: bool,
-) -> Result<(Circuit, Abi), RuntimeError> {
+    ssa_pass_callback: Option<&mut dyn FnMut(&str, &str)>,
+    black_box_host: Option<&dyn BlackBoxHost>,
+) -> Result<(Circuit, Abi, Vec<RuntimeWarning>), RuntimeError> {
     let func_sig = program.main_function_signature.clone();
-    let GeneratedAcir { current_witness_index, opcodes, return_witnesses } =
-        optimize_into_acir(program, show_output, enable_logging);
+    let (GeneratedAcir { current_witness_index, opcodes, return_witnesses }, warnings) =
+        optimize_into_acir(
+            program,
+            show_output,
+            enable_logging,
+            ssa_pass_callback,
+            black_box_host,
+        )?;
 
     let abi = gen_abi(func_sig, return_witnesses.clone());
     let public_abi = abi.clone().public_abi();
@@ -93,14 +167,240 @@ pub fn experimental_create_circuit(
         .map_err(|_| RuntimeErrorKind::Spanless(String::from("produced an acvm compile error")))?
     };
 
-    Ok((optimized_circuit, abi))
+    Ok((optimized_circuit, abi, warnings))
 }
 
-impl Ssa {
-    fn print(self, print_ssa_passes: bool, msg: &str) -> Ssa {
-        if print_ssa_passes {
-            println!("{msg}\n{self}");
+/// Decides what is done with the SSA after each pass, for debugging the pipeline.
+struct SsaPassPrinter<'a> {
+    /// If true, the SSA is printed to stdout after each pass
+    print_ssa_passes: bool,
+
+    /// Called with the message of each pass and the printed SSA after it.
+    /// Useful for finding which pass introduced a bug.
+    callback: Option<&'a mut dyn FnMut(&str, &str)>,
+}
+
+impl SsaPassPrinter<'_> {
+    fn after_pass(&mut self, msg: &str, ssa: &Ssa) {
+        if !self.print_ssa_passes && self.callback.is_none() {
+            return;
+        }
+
+        let printed = ssa.to_string();
+        if self.print_ssa_passes {
+            println!("{msg}\n{printed}");
         }
+        if let Some(callback) = &mut self.callback {
+            callback(msg, &printed);
+        }
+    }
+}
+
+impl Ssa {
+    fn print(self, printer: &mut SsaPassPrinter, msg: &str) -> Ssa {
+        printer.after_pass(msg, &self);
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use acvm::{acir::BlackBoxFunc, FieldElement};
+    use iter_extended::vecmap;
+    use noirc_abi::AbiDistinctness;
+    use noirc_frontend::{
+        monomorphization::ast::{self, Program},
+        BinaryOpKind,
+    };
+
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{Intrinsic, TerminatorInstruction},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+        ssa_gen::test_utils::{
+            array_literal, binary, field, function, generate_functions, local_ident, location,
+            mutable_ident, parameter,
+        },
+    };
+
+    use super::{optimize_acir_functions, optimize_into_acir, BlackBoxHost, SsaPassPrinter};
+
+    /// A host implementing only pedersen, as the pair of its first two inputs.
+    struct TestHost;
+
+    impl BlackBoxHost for TestHost {
+        fn evaluate(
+            &self,
+            func: BlackBoxFunc,
+            inputs: &[FieldElement],
+        ) -> Option<Vec<FieldElement>> {
+            match func {
+                BlackBoxFunc::Pedersen => Some(inputs[..2].to_vec()),
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn callback_receives_each_pass() {
+        // fn main(x: Field) -> Field {
+        //     x
+        // }
+        let x = ast::LocalId(0);
+        let parameters = vec![parameter(x, "x", ast::Type::Field)];
+        let body = local_ident(x, "x", ast::Type::Field);
+        let main = function(0, "main", parameters, body, ast::Type::Field);
+        let program = Program::new(vec![main], (Vec::new(), None), AbiDistinctness::Distinct);
+
+        let mut snapshots = Vec::new();
+        let mut callback = |msg: &str, ssa: &str| snapshots.push((msg.to_owned(), ssa.to_owned()));
+        optimize_into_acir(program, false, false, Some(&mut callback), None).unwrap();
+
+        // One snapshot per pass, in the order the pipeline runs them
+        let labels = vecmap(&snapshots, |(msg, _)| msg.as_str());
+        assert_eq!(
+            labels,
+            vec![
+                "Initial SSA:",
+                "After Removing Dead Generated Instructions:",
+                "After Removing Unused Block Parameters:",
+                "After Strength Reduction:",
+                "After Converting Tail Recursion:",
+                "After Hoisting Common Branch Prefixes:",
+                "After Specializing Constant Arguments:",
+                "After Inlining:",
+                "After Hoisting Constant Allocations:",
+                "After Unrolling:",
+                "After Deduplicating Blocks:",
+                "After Simplifying:",
+                "After Flattening:",
+                "After Mem2Reg:",
+                "After Dead Store Elimination:",
+                "After Constant Folding:",
+                "After Deduplicating Constant Arrays:",
+                "After Combining Truncations:",
+                "After Removing Redundant Constraints:",
+                "After Removing Redundant Booleanity Constraints:",
+                "After Dead Instruction Elimination:",
+            ]
+        );
+        for (_, ssa) in snapshots {
+            assert!(ssa.contains("fn main f0"));
+        }
+    }
+
+    #[test]
+    fn loop_accumulating_constants_folds_to_a_constant() {
+        // fn main() -> Field {
+        //     let array = [1, 2, 3, 4];
+        //     let mut sum = 0;
+        //     for i in 0..4 {
+        //         sum = sum + array[i];
+        //     }
+        //     sum
+        // }
+        let array_type = ast::Type::Array(4, Box::new(ast::Type::Field));
+        let (array, sum, i) = (ast::LocalId(0), ast::LocalId(1), ast::LocalId(2));
+
+        let element = ast::Expression::Index(ast::Index {
+            collection: Box::new(local_ident(array, "array", array_type)),
+            index: Box::new(local_ident(i, "i", ast::Type::Field)),
+            element_type: ast::Type::Field,
+            location: location(),
+        });
+        let sum_ident = mutable_ident(sum, "sum", ast::Type::Field);
+        let accumulate = ast::Expression::Assign(ast::Assign {
+            lvalue: ast::LValue::Ident(sum_ident.clone()),
+            expression: Box::new(binary(
+                ast::Expression::Ident(sum_ident.clone()),
+                BinaryOpKind::Add,
+                element,
+            )),
+        });
+
+        let body = ast::Expression::Block(vec![
+            ast::Expression::Let(ast::Let {
+                id: array,
+                mutable: false,
+                name: "array".into(),
+                expression: Box::new(array_literal(vecmap(1..=4, field), ast::Type::Field)),
+            }),
+            ast::Expression::Let(ast::Let {
+                id: sum,
+                mutable: true,
+                name: "sum".into(),
+                expression: Box::new(field(0)),
+            }),
+            ast::Expression::For(ast::For {
+                index_variable: i,
+                index_name: "i".into(),
+                index_type: ast::Type::Field,
+                start_range: Box::new(field(0)),
+                end_range: Box::new(field(4)),
+                block: Box::new(ast::Expression::Semi(Box::new(accumulate))),
+            }),
+            ast::Expression::Ident(sum_ident),
+        ]);
+        let ssa = generate_functions(vec![function(0, "main", Vec::new(), body, ast::Type::Field)]);
+        let mut printer = SsaPassPrinter { print_ssa_passes: false, callback: None };
+        let ssa = optimize_acir_functions(ssa, &mut printer, None);
+
+        let main = ssa.main();
+        assert_eq!(main.reachable_blocks().len(), 1);
+
+        let entry = &main.dfg[main.entry_block()];
+        assert!(entry.instructions().is_empty());
+        let return_values = match entry.unwrap_terminator() {
+            TerminatorInstruction::Return { return_values } => return_values,
+            other => panic!("Expected a return, found {other:?}"),
+        };
+        assert_eq!(return_values.len(), 1);
+        assert_eq!(
+            main.dfg.get_numeric_constant(return_values[0]),
+            Some(FieldElement::from(10u128))
+        );
+    }
+
+    #[test]
+    fn black_box_calls_are_folded_with_a_host() {
+        // fn main f0 {
+        //   b0():
+        //     v3 = call pedersen([Field 2, Field 3], u32 0)
+        //     return v3
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let two = builder.field_constant(2u128);
+        let three = builder.field_constant(3u128);
+        let inputs = builder.array_constant(im::vector![two, three], Rc::new(vec![Type::field()]));
+        let domain_separator = builder.numeric_constant(0u128, Type::unsigned(32));
+
+        let pedersen = builder.import_intrinsic_id(Intrinsic::BlackBox(BlackBoxFunc::Pedersen));
+        let result_type = Type::Array(Rc::new(vec![Type::field()]), 2);
+        let result =
+            builder.insert_call(pedersen, vec![inputs, domain_separator], vec![result_type])[0];
+        builder.terminate_with_return(vec![result]);
+        let ssa = builder.finish();
+
+        let mut printer = SsaPassPrinter { print_ssa_passes: false, callback: None };
+        let ssa = optimize_acir_functions(ssa, &mut printer, Some(&TestHost));
+
+        let main = ssa.main();
+        let entry = &main.dfg[main.entry_block()];
+        assert!(entry.instructions().is_empty());
+        let return_values = match entry.unwrap_terminator() {
+            TerminatorInstruction::Return { return_values } => return_values,
+            other => panic!("Expected a return, found {other:?}"),
+        };
+        let (array, _) =
+            main.dfg.get_array_constant(return_values[0]).expect("Expected a constant array");
+        let outputs = vecmap(array, |element| main.dfg.get_numeric_constant(element));
+        assert_eq!(outputs, vec![Some(2u128.into()), Some(3u128.into())]);
+    }
+}
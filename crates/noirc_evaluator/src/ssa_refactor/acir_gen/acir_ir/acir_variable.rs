@@ -104,6 +104,13 @@ pub(crate) struct AcirContext {
     /// then the `acir_ir` will be populated to assert this
     /// addition.
     acir_ir: GeneratedAcir,
+
+    /// Maps the operands and bit size of each euclidean division performed so far to its
+    /// resulting quotient and remainder.
+    ///
+    /// Both `/` and `%` are computed via the same divmod, so caching it here ensures
+    /// computing both on the same operands only emits a single set of division constraints.
+    euclidean_divisions: HashMap<(AcirVar, AcirVar, u32), (AcirVar, AcirVar)>,
 }
 
 impl AcirContext {
@@ -274,6 +281,32 @@ impl AcirContext {
         }
     }
 
+    /// Constrains the `lhs` and `rhs` to differ.
+    ///
+    /// This witnesses the inverse of their difference, which only exists if it is non-zero.
+    pub(crate) fn assert_neq_var(
+        &mut self,
+        lhs: AcirVar,
+        rhs: AcirVar,
+    ) -> Result<(), AcirGenError> {
+        let lhs_data = &self.vars[&lhs];
+        let rhs_data = &self.vars[&rhs];
+        if let (AcirVarData::Const(lhs_const), AcirVarData::Const(rhs_const)) = (lhs_data, rhs_data)
+        {
+            if lhs_const != rhs_const {
+                // Constraint is always true and need not be added
+                Ok(())
+            } else {
+                // Constraint is always false - this program is unprovable
+                Err(AcirGenError::BadConstantInequality { lhs: *lhs_const, rhs: *rhs_const })
+            }
+        } else {
+            let difference = self.sub_var(lhs, rhs)?;
+            self.inv_var(difference)?;
+            Ok(())
+        }
+    }
+
     /// Adds a new Variable to context whose value will
     /// be constrained to be the division of `lhs` and `rhs`
     pub(crate) fn div_var(
@@ -408,12 +441,19 @@ impl AcirContext {
     }
 
     /// Returns the quotient and remainder such that lhs = rhs * quotient + remainder
-    fn euclidean_division_var(
+    ///
+    /// The result is cached so that repeated divisions of the same operands reuse
+    /// the quotient and remainder rather than constraining them again.
+    pub(crate) fn euclidean_division_var(
         &mut self,
         lhs: AcirVar,
         rhs: AcirVar,
         bit_size: u32,
     ) -> Result<(AcirVar, AcirVar), AcirGenError> {
+        if let Some(result) = self.euclidean_divisions.get(&(lhs, rhs, bit_size)) {
+            return Ok(*result);
+        }
+
         let predicate = Expression::one();
 
         let lhs_data = &self.vars[&lhs];
@@ -428,6 +468,7 @@ impl AcirContext {
         let quotient_var = self.add_data(AcirVarData::Witness(quotient));
         let remainder_var = self.add_data(AcirVarData::Witness(remainder));
 
+        self.euclidean_divisions.insert((lhs, rhs, bit_size), (quotient_var, remainder_var));
         Ok((quotient_var, remainder_var))
     }
 
@@ -6,6 +6,7 @@ pub(crate) enum AcirGenError {
     IndexOutOfBounds { index: usize, array_size: usize },
     UnsupportedIntegerSize { num_bits: u32, max_num_bits: u32 },
     BadConstantEquality { lhs: FieldElement, rhs: FieldElement },
+    BadConstantInequality { lhs: FieldElement, rhs: FieldElement },
 }
 
 impl AcirGenError {
@@ -25,6 +26,9 @@ impl AcirGenError {
             AcirGenError::BadConstantEquality { lhs, rhs } => {
                 format!("{lhs} and {rhs} constrained to be equal though they never can be")
             }
+            AcirGenError::BadConstantInequality { lhs, rhs } => {
+                format!("{lhs} and {rhs} constrained to differ though they are always equal")
+            }
         }
     }
 }
@@ -11,13 +11,13 @@ use self::acir_ir::{
 use super::{
     ir::{
         dfg::DataFlowGraph,
-        function::RuntimeType,
         instruction::{
             Binary, BinaryOp, Instruction, InstructionId, Intrinsic, TerminatorInstruction,
         },
         map::Id,
         types::{NumericType, Type},
         value::{Value, ValueId},
+        value_range::ValueRanges,
     },
     ssa_gen::Ssa,
 };
@@ -47,6 +47,10 @@ struct Context {
 
     /// Manages and builds the `AcirVar`s to which the converted SSA values refer.
     acir_context: AcirContext,
+
+    /// The known ranges of values within the main function, used to skip truncating
+    /// any value which is already known to fit within the target bit size.
+    value_ranges: ValueRanges,
 }
 
 #[derive(Debug, Clone)]
@@ -109,6 +113,7 @@ impl Context {
         let main_func = ssa.main();
         let dfg = &main_func.dfg;
         let entry_block = &dfg[main_func.entry_block()];
+        self.value_ranges = ValueRanges::with_function(main_func);
 
         self.convert_ssa_block_params(entry_block.parameters(), dfg);
 
@@ -198,6 +203,20 @@ impl Context {
                     .assert_eq_one(constrain_condition)
                     .expect("add Result types to all methods so errors bubble up");
             }
+            Instruction::ConstrainEqual(lhs, rhs) => {
+                let lhs = self.convert_numeric_value(*lhs, dfg);
+                let rhs = self.convert_numeric_value(*rhs, dfg);
+                self.acir_context
+                    .assert_eq_var(lhs, rhs)
+                    .expect("add Result types to all methods so errors bubble up");
+            }
+            Instruction::ConstrainNotEqual(lhs, rhs) => {
+                let lhs = self.convert_numeric_value(*lhs, dfg);
+                let rhs = self.convert_numeric_value(*rhs, dfg);
+                self.acir_context
+                    .assert_neq_var(lhs, rhs)
+                    .expect("add Result types to all methods so errors bubble up");
+            }
             Instruction::Cast(value_id, typ) => {
                 let result_acir_var = self
                     .convert_ssa_cast(value_id, typ, dfg)
@@ -209,34 +228,9 @@ impl Context {
                 match &dfg[*func] {
                     Value::Function(id) => {
                         let func = &ssa.functions[id];
-                        match func.runtime() {
-                            RuntimeType::Acir => unimplemented!(
-                                "expected an intrinsic/brillig call, but found {func:?}. All ACIR methods should be inlined"
-                            ),
-                            RuntimeType::Brillig => {
-                                let inputs = vecmap(arguments, |arg| self.convert_value(*arg, dfg));
-
-                                // Create the entry point artifact
-                                let mut entry_point = BrilligArtifact::to_entry_point_artifact(&brillig[*id]);
-                                // Link the entry point with all dependencies
-                                while let Some(unresolved_fn_label) = entry_point.first_unresolved_function_call() {
-                                    let artifact = &brillig.find_by_function_label(unresolved_fn_label.clone()).expect("Cannot find linked fn {unresolved_fn_label}");
-                                    entry_point.link_with(unresolved_fn_label, artifact);
-                                }
-                                // Generate the final bytecode
-                                let code = entry_point.finish();
-
-                                let outputs: Vec<AcirType> = vecmap(result_ids, |result_id| dfg.type_of_value(*result_id).into());
-
-                                let output_values = self.acir_context.brillig(code, inputs, outputs);
-                                // Compiler sanity check
-                                assert_eq!(result_ids.len(), output_values.len(), "ICE: The number of Brillig output values should match the result ids in SSA");
-
-                                for result in result_ids.iter().zip(output_values) {
-                                    self.ssa_values.insert(*result.0, result.1);
-                                }
-                            }
-                        }
+                        unimplemented!(
+                            "expected an intrinsic call, but found {func:?}. All ACIR methods should be inlined and brillig calls converted into hints"
+                        )
                     }
                     Value::Intrinsic(intrinsic) => {
                         let outputs = self.convert_ssa_intrinsic_call(
@@ -261,6 +255,41 @@ impl Context {
                     _ => unreachable!("expected calling a function"),
                 }
             }
+            Instruction::Hint { func, arguments } => {
+                let result_ids = dfg.instruction_results(instruction_id);
+                let id = match &dfg[*func] {
+                    Value::Function(id) => *id,
+                    _ => unreachable!("expected a hint to call a brillig function"),
+                };
+                let inputs = vecmap(arguments, |arg| self.convert_value(*arg, dfg));
+
+                // Create the entry point artifact
+                let mut entry_point = BrilligArtifact::to_entry_point_artifact(&brillig[id]);
+                // Link the entry point with all dependencies
+                while let Some(unresolved_fn_label) = entry_point.first_unresolved_function_call() {
+                    let artifact = &brillig
+                        .find_by_function_label(unresolved_fn_label.clone())
+                        .expect("Cannot find linked fn {unresolved_fn_label}");
+                    entry_point.link_with(unresolved_fn_label, artifact);
+                }
+                // Generate the final bytecode
+                let code = entry_point.finish();
+
+                let outputs: Vec<AcirType> =
+                    vecmap(result_ids, |result_id| dfg.type_of_value(*result_id).into());
+
+                let output_values = self.acir_context.brillig(code, inputs, outputs);
+                // Compiler sanity check
+                assert_eq!(
+                    result_ids.len(),
+                    output_values.len(),
+                    "ICE: The number of Brillig output values should match the result ids in SSA"
+                );
+
+                for result in result_ids.iter().zip(output_values) {
+                    self.ssa_values.insert(*result.0, result.1);
+                }
+            }
             Instruction::Not(value_id) => {
                 let (acir_var, typ) = match self.convert_value(*value_id, dfg) {
                     AcirValue::Var(acir_var, typ) => (acir_var, typ),
@@ -566,7 +595,9 @@ impl Context {
                     todo!("Cast from unsigned to signed")
                 }
                 let max_bit_size = incoming_type.bit_size();
-                if max_bit_size <= *bit_size {
+                if max_bit_size <= *bit_size
+                    || self.value_ranges.fits_in_bits(dfg, *value_id, *bit_size)
+                {
                     // Incoming variable already fits into target bit size -  this is a no-op
                     return Ok(variable);
                 }
@@ -585,6 +616,10 @@ impl Context {
         dfg: &DataFlowGraph,
     ) -> Result<AcirVar, AcirGenError> {
         let mut var = self.convert_numeric_value(value_id, dfg);
+        if self.value_ranges.fits_in_bits(dfg, value_id, bit_size) {
+            return Ok(var);
+        }
+
         let truncation_target = match &dfg[value_id] {
             Value::Instruction { instruction, .. } => &dfg[*instruction],
             _ => unreachable!("ICE: Truncates are only ever applied to the result of a binary op"),
@@ -676,6 +711,28 @@ impl Context {
 
                 Self::convert_vars_to_values(out_vars, dfg, result_ids)
             }
+            Intrinsic::DivMod => {
+                let lhs = self.convert_value(arguments[0], dfg).into_var();
+                let rhs = self.convert_value(arguments[1], dfg).into_var();
+                let bit_size = AcirType::from(dfg.type_of_value(result_ids[0])).bit_size();
+
+                let (quotient, remainder) = self
+                    .acir_context
+                    .euclidean_division_var(lhs, rhs, bit_size)
+                    .expect("add Result types to all methods so errors bubble up");
+
+                Self::convert_vars_to_values(vec![quotient, remainder], dfg, result_ids)
+            }
+            Intrinsic::ArrayGetOr
+            | Intrinsic::SaturatingAdd
+            | Intrinsic::SaturatingSub
+            | Intrinsic::BitLength
+            | Intrinsic::Abs
+            | Intrinsic::ConditionalSwap => {
+                unreachable!(
+                    "ICE: {intrinsic} is expanded into other instructions during SSA generation"
+                )
+            }
         }
     }
 
@@ -767,7 +824,7 @@ mod tests {
 
     use acvm::{
         acir::{
-            circuit::Opcode,
+            circuit::{directives::Directive, Opcode},
             native_types::{Expression, Witness},
         },
         FieldElement,
@@ -776,7 +833,12 @@ mod tests {
     use crate::{
         brillig::Brillig,
         ssa_refactor::{
-            ir::{function::RuntimeType, map::Id, types::Type},
+            ir::{
+                function::RuntimeType,
+                instruction::{BinaryOp, Intrinsic},
+                map::Id,
+                types::Type,
+            },
             ssa_builder::FunctionBuilder,
         },
     };
@@ -809,4 +871,141 @@ mod tests {
         assert_eq!(acir.opcodes, expected_opcodes);
         assert_eq!(acir.return_witnesses, vec![Witness(1)]);
     }
+
+    /// Converts `fn main(v0: u32, v1: u32)` which returns `v0 / v1` and, if `with_modulo`
+    /// is set, also `v0 % v1` into ACIR, returning the number of opcodes generated.
+    fn count_division_opcodes(with_modulo: bool) -> usize {
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(32));
+        let v1 = builder.add_parameter(Type::unsigned(32));
+
+        let mut return_values = vec![builder.insert_binary(v0, BinaryOp::Div, v1)];
+        if with_modulo {
+            return_values.push(builder.insert_binary(v0, BinaryOp::Mod, v1));
+        }
+        builder.terminate_with_return(return_values);
+
+        let ssa = builder.finish();
+        let context = Context::default();
+        context.convert_ssa(ssa, Brillig::default(), false).opcodes.len()
+    }
+
+    #[test]
+    fn division_and_modulo_share_constraints() {
+        // fn main {
+        //   b0(v0: u32, v1: u32):
+        //     v2 = div v0, v1
+        //     v3 = mod v0, v1
+        //     return v2, v3
+        // }
+        //
+        // The quotient and remainder are both witnesses of the same divmod, so
+        // returning the remainder as well must not add any new constraints.
+        assert_eq!(count_division_opcodes(true), count_division_opcodes(false));
+    }
+
+    #[test]
+    fn field_division_constrains_inverse() {
+        // fn main {
+        //   b0(v0: Field, v1: Field):
+        //     v2 = div v0, v1
+        //     return v2
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let v2 = builder.insert_binary(v0, BinaryOp::Div, v1);
+        builder.terminate_with_return(vec![v2]);
+
+        let ssa = builder.finish();
+        let context = Context::default();
+        let acir = context.convert_ssa(ssa, Brillig::default(), false);
+
+        // The inverse of v1 is witnessed by a brillig directive, followed by
+        // a constraint that `v1 * inverse == 1`.
+        let (directive_index, _) = acir
+            .opcodes
+            .iter()
+            .enumerate()
+            .find(|(_, opcode)| matches!(opcode, Opcode::Brillig(_)))
+            .expect("Expected the inverse to be computed by a brillig directive");
+        assert!(matches!(acir.opcodes[directive_index + 1], Opcode::Arithmetic(_)));
+    }
+
+    /// Converts `fn main(v0)` which returns `v0` cast to a Field and then to a u8 into ACIR,
+    /// returning whether a truncation was generated for the cast to u8.
+    fn u8_cast_is_truncated(input_type: Type) -> bool {
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(input_type);
+        let v1 = builder.insert_cast(v0, Type::field());
+        let v2 = builder.insert_cast(v1, Type::unsigned(8));
+        builder.terminate_with_return(vec![v2]);
+
+        let ssa = builder.finish();
+        let context = Context::default();
+        let acir = context.convert_ssa(ssa, Brillig::default(), false);
+        acir.opcodes
+            .iter()
+            .any(|opcode| matches!(opcode, Opcode::Directive(Directive::Quotient(_))))
+    }
+
+    #[test]
+    fn cast_of_value_in_range_is_not_truncated() {
+        // fn main {
+        //   b0(v0: u8):
+        //     v1 = cast v0 as Field
+        //     v2 = cast v1 as u8
+        //     return v2
+        // }
+        //
+        // v1 is known to be within 0..256 since it is derived from a u8 input,
+        // so the cast back to u8 needs no truncation.
+        assert!(!u8_cast_is_truncated(Type::unsigned(8)));
+        assert!(u8_cast_is_truncated(Type::unsigned(16)));
+    }
+
+    #[test]
+    fn sort_witnesses_a_sorted_permutation() {
+        // fn main {
+        //   b0(v0: u32, v1: u32, v2: u32):
+        //     v4 = call arraysort([v0, v1, v2])
+        //     return v4
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let elements = (0..3).map(|_| builder.add_parameter(Type::unsigned(32))).collect();
+        let element_type = Rc::new(vec![Type::unsigned(32)]);
+        let array = builder.array_constant(elements, element_type.clone());
+        let sort = builder.import_intrinsic_id(Intrinsic::Sort);
+        let array_type = Type::Array(element_type, 3);
+        let sorted = builder.insert_call(sort, vec![array], vec![array_type])[0];
+        builder.terminate_with_return(vec![sorted]);
+
+        let ssa = builder.finish();
+        let context = Context::default();
+        let acir = context.convert_ssa(ssa, Brillig::default(), false);
+
+        // The sorted outputs are fresh witnesses, solved by a permutation directive over the
+        // inputs and constrained to be both a permutation of them and in ascending order.
+        let inputs = [Witness(1), Witness(2), Witness(3)];
+        assert_eq!(acir.return_witnesses.len(), 3);
+        assert!(acir.return_witnesses.iter().all(|witness| !inputs.contains(witness)));
+
+        let count = |is_directive: fn(&Directive) -> bool| {
+            let opcodes = acir.opcodes.iter();
+            opcodes
+                .filter(|opcode| matches!(opcode, Opcode::Directive(d) if is_directive(d)))
+                .count()
+        };
+        let is_permutation =
+            |directive: &Directive| matches!(directive, Directive::PermutationSort { .. });
+        assert_eq!(count(is_permutation), 1);
+
+        // Each adjacent pair of outputs is compared, witnessing the quotient of their difference
+        let is_quotient = |directive: &Directive| matches!(directive, Directive::Quotient(_));
+        assert_eq!(count(is_quotient), 2);
+    }
 }
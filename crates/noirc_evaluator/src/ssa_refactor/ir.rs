@@ -1,12 +1,18 @@
 pub(crate) mod basic_block;
+pub(crate) mod call_graph;
 pub(crate) mod cfg;
 pub(crate) mod dfg;
 pub(crate) mod dom;
 pub(crate) mod function;
 pub(crate) mod function_inserter;
 pub(crate) mod instruction;
+#[cfg(test)]
+pub(crate) mod interpreter;
 pub(crate) mod map;
 pub(crate) mod post_order;
 pub(crate) mod printer;
+pub(crate) mod taint;
 pub(crate) mod types;
+pub(crate) mod unused_parameters;
 pub(crate) mod value;
+pub(crate) mod value_range;
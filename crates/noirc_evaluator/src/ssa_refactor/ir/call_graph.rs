@@ -0,0 +1,179 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::ssa_refactor::ssa_gen::Ssa;
+
+use super::{
+    function::{Function, FunctionId},
+    instruction::Instruction,
+    value::Value,
+};
+
+/// The call graph of a program maps each function to the set of functions it directly calls.
+///
+/// Only calls to known functions are included. Calls to intrinsics or foreign functions
+/// are not part of the call graph.
+pub(crate) struct CallGraph {
+    callees: BTreeMap<FunctionId, BTreeSet<FunctionId>>,
+}
+
+impl CallGraph {
+    /// Compute the call graph of each function in the given program.
+    pub(crate) fn with_ssa(ssa: &Ssa) -> Self {
+        let callees = ssa
+            .functions
+            .iter()
+            .map(|(id, function)| (*id, Self::compute_callees(function)))
+            .collect();
+
+        Self { callees }
+    }
+
+    /// Collects every function directly called within the reachable blocks of the given function.
+    fn compute_callees(function: &Function) -> BTreeSet<FunctionId> {
+        let mut callees = BTreeSet::new();
+
+        for block in function.reachable_blocks() {
+            for instruction in function.dfg[block].instructions() {
+                if let Instruction::Call { func, .. } | Instruction::Hint { func, .. } =
+                    &function.dfg[*instruction]
+                {
+                    if let Value::Function(callee) = &function.dfg[function.dfg.resolve(*func)] {
+                        callees.insert(*callee);
+                    }
+                }
+            }
+        }
+
+        callees
+    }
+
+    /// Returns each function directly called by the given function.
+    pub(crate) fn callees(&self, function: FunctionId) -> impl Iterator<Item = FunctionId> + '_ {
+        self.callees.get(&function).into_iter().flatten().copied()
+    }
+}
+
+/// The error returned by `max_call_depth` if the program contains recursion.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct RecursiveCall {
+    /// A function which is part of the call cycle.
+    pub(crate) function: FunctionId,
+}
+
+/// Returns the number of functions in the deepest call chain starting from the program's
+/// main function, counting main itself. A main function which calls no other functions
+/// has a call depth of 1.
+///
+/// Since the depth of a recursive call chain is unbounded, this returns an error if any
+/// function reachable from main is recursive.
+pub(crate) fn max_call_depth(ssa: &Ssa) -> Result<usize, RecursiveCall> {
+    let call_graph = CallGraph::with_ssa(ssa);
+    let mut depths = HashMap::new();
+    call_depth(&call_graph, ssa.main_id, &mut depths, &mut Vec::new())
+}
+
+/// Computes the call depth of the given function, caching the result in `depths`.
+///
+/// `call_stack` contains each function currently being visited, and is used to detect cycles.
+fn call_depth(
+    call_graph: &CallGraph,
+    function: FunctionId,
+    depths: &mut HashMap<FunctionId, usize>,
+    call_stack: &mut Vec<FunctionId>,
+) -> Result<usize, RecursiveCall> {
+    if let Some(depth) = depths.get(&function) {
+        return Ok(*depth);
+    }
+
+    if call_stack.contains(&function) {
+        return Err(RecursiveCall { function });
+    }
+
+    call_stack.push(function);
+    let mut max_callee_depth = 0;
+    for callee in call_graph.callees(function) {
+        let callee_depth = call_depth(call_graph, callee, depths, call_stack)?;
+        max_callee_depth = max_callee_depth.max(callee_depth);
+    }
+    call_stack.pop();
+
+    let depth = max_callee_depth + 1;
+    depths.insert(function, depth);
+    Ok(depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{function::RuntimeType, map::Id},
+        ssa_builder::FunctionBuilder,
+    };
+
+    use super::{max_call_depth, RecursiveCall};
+
+    #[test]
+    fn three_level_call_chain() {
+        // fn main f0 {
+        //   b0():
+        //     call f1()
+        //     return
+        // }
+        // fn f1 f1 {
+        //   b0():
+        //     call f2()
+        //     return
+        // }
+        // fn f2 f2 {
+        //   b0():
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let f1_id = Id::test_new(1);
+        let f2_id = Id::test_new(2);
+
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let f1 = builder.import_function(f1_id);
+        builder.insert_call(f1, vec![], vec![]);
+        builder.terminate_with_return(vec![]);
+
+        builder.new_function("f1".into(), f1_id);
+        let f2 = builder.import_function(f2_id);
+        builder.insert_call(f2, vec![], vec![]);
+        builder.terminate_with_return(vec![]);
+
+        builder.new_function("f2".into(), f2_id);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish();
+        assert_eq!(max_call_depth(&ssa), Ok(3));
+    }
+
+    #[test]
+    fn recursion_is_an_error() {
+        // fn main f0 {
+        //   b0():
+        //     call f1()
+        //     return
+        // }
+        // fn f1 f1 {
+        //   b0():
+        //     call f1()
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let f1_id = Id::test_new(1);
+
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let f1 = builder.import_function(f1_id);
+        builder.insert_call(f1, vec![], vec![]);
+        builder.terminate_with_return(vec![]);
+
+        builder.new_function("f1".into(), f1_id);
+        let f1 = builder.import_function(f1_id);
+        builder.insert_call(f1, vec![], vec![]);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish();
+        assert_eq!(max_call_depth(&ssa), Err(RecursiveCall { function: f1_id }));
+    }
+}
@@ -15,12 +15,13 @@ use super::{
 
 use acvm::FieldElement;
 use iter_extended::vecmap;
+use noirc_errors::Location;
 
 /// The DataFlowGraph contains most of the actual data in a function including
 /// its blocks, instructions, and values. This struct is largely responsible for
 /// owning most data in a function and handing out Ids to this data that can be
 /// shared without worrying about ownership.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub(crate) struct DataFlowGraph {
     /// All of the instructions in a function
     instructions: DenseMap<Instruction>,
@@ -69,6 +70,16 @@ pub(crate) struct DataFlowGraph {
     /// for that of another. This information is purely used for printing the SSA, and has no
     /// material effect on the SSA itself.
     replaced_value_ids: HashMap<ValueId, ValueId>,
+
+    /// The source location of each instruction which was generated from code that may fail,
+    /// such as the constrain instruction of an `assert`. Instructions with no location recorded
+    /// for them are missing from this map.
+    locations: HashMap<InstructionId, Location>,
+
+    /// Incremented each time a block is mutably accessed in a way that may change its
+    /// terminator, and therefore the edges of the control flow graph. This is used by
+    /// `Function` to know when its cached control flow graph must be recomputed.
+    cfg_version: usize,
 }
 
 impl DataFlowGraph {
@@ -137,6 +148,18 @@ impl DataFlowGraph {
         instruction: Instruction,
         block: BasicBlockId,
         ctrl_typevars: Option<Vec<Type>>,
+    ) -> InsertInstructionResult {
+        self.insert_instruction_with_location(instruction, block, ctrl_typevars, None)
+    }
+
+    /// Inserts a new instruction at the end of the given block and returns its results,
+    /// recording the given source location for the instruction if it is not simplified away.
+    pub(crate) fn insert_instruction_with_location(
+        &mut self,
+        instruction: Instruction,
+        block: BasicBlockId,
+        ctrl_typevars: Option<Vec<Type>>,
+        location: Option<Location>,
     ) -> InsertInstructionResult {
         use InsertInstructionResult::*;
         match instruction.simplify(self) {
@@ -145,11 +168,19 @@ impl DataFlowGraph {
             SimplifyResult::None => {
                 let id = self.make_instruction(instruction, ctrl_typevars);
                 self.blocks[block].insert_instruction(id);
+                if let Some(location) = location {
+                    self.locations.insert(id, location);
+                }
                 InsertInstructionResult::Results(self.instruction_results(id))
             }
         }
     }
 
+    /// Returns the source location recorded for the given instruction, if any.
+    pub(crate) fn get_location(&self, instruction: InstructionId) -> Option<Location> {
+        self.locations.get(&instruction).copied()
+    }
+
     /// Insert a value into the dfg's storage and return an id to reference it.
     /// Until the value is used in an instruction it is unreachable.
     pub(crate) fn make_value(&mut self, value: Value) -> ValueId {
@@ -191,7 +222,7 @@ impl DataFlowGraph {
         id
     }
 
-    /// Create a new constant array value from the given elements
+    /// Create a new array value from the given elements.
     pub(crate) fn make_array(
         &mut self,
         array: im::Vector<ValueId>,
@@ -208,6 +239,44 @@ impl DataFlowGraph {
         self.values.insert(Value::Function(function))
     }
 
+    /// Replaces each reference to the function `old` within this function
+    /// with a reference to the function `new`.
+    pub(crate) fn replace_function(&mut self, old: FunctionId, new: FunctionId) {
+        let references: Vec<ValueId> = self
+            .values
+            .iter()
+            .filter(|(_, value)| matches!(value, Value::Function(id) if *id == old))
+            .map(|(value_id, _)| value_id)
+            .collect();
+
+        for value_id in references {
+            self.values[value_id] = Value::Function(new);
+        }
+
+        if let Some(value_id) = self.functions.remove(&old) {
+            self.functions.entry(new).or_insert(value_id);
+        }
+    }
+
+    /// Replaces each reference to a foreign function within this function whose name is in
+    /// `definitions` with a reference to the function defined under that name.
+    pub(crate) fn link_foreign_functions(&mut self, definitions: &HashMap<String, FunctionId>) {
+        let references: Vec<(ValueId, FunctionId)> = self
+            .values
+            .iter()
+            .filter_map(|(value_id, value)| match value {
+                Value::ForeignFunction(name) => Some((value_id, *definitions.get(name)?)),
+                _ => None,
+            })
+            .collect();
+
+        for (value_id, function) in references {
+            self.values[value_id] = Value::Function(function);
+            self.functions.entry(function).or_insert(value_id);
+        }
+        self.foreign_functions.retain(|name, _| !definitions.contains_key(name));
+    }
+
     /// Gets or creates a ValueId for the given FunctionId.
     pub(crate) fn import_foreign_function(&mut self, function: &str) -> ValueId {
         if let Some(existing) = self.foreign_functions.get(function) {
@@ -340,20 +409,51 @@ impl DataFlowGraph {
         }
     }
 
+    /// Returns true if this value is a numeric constant or an array whose elements are all
+    /// constants, recursively.
+    pub(crate) fn is_constant(&self, value: ValueId) -> bool {
+        match self.get_array_constant(value) {
+            Some((array, _)) => array.iter().all(|element| self.is_constant(*element)),
+            None => self.get_numeric_constant(value).is_some(),
+        }
+    }
+
+    /// Returns true if this value is an array whose elements are all numeric constants.
+    ///
+    /// Such an array is known entirely at compile time, so backends may place it in read-only
+    /// memory rather than building it up element by element.
+    pub(crate) fn is_constant_array(&self, value: ValueId) -> bool {
+        match self.get_array_constant(value) {
+            Some((array, _)) => {
+                array.iter().all(|element| self.get_numeric_constant(*element).is_some())
+            }
+            None => false,
+        }
+    }
+
     /// Sets the terminator instruction for the given basic block
     pub(crate) fn set_block_terminator(
         &mut self,
         block: BasicBlockId,
         terminator: TerminatorInstruction,
     ) {
+        self.cfg_version += 1;
         self.blocks[block].set_terminator(terminator);
     }
 
+    /// Returns a counter which changes whenever any block's terminator may have changed.
+    /// If two calls return the same version, the control flow graph is guaranteed to be
+    /// unchanged between them.
+    pub(crate) fn cfg_version(&self) -> usize {
+        self.cfg_version
+    }
+
     /// Moves the entirety of the given block's contents into the destination block.
     /// The source block afterward will be left in a valid but emptied state. The
     /// destination block will also have its terminator overwritten with that of the
     /// source block.
     pub(crate) fn inline_block(&mut self, source: BasicBlockId, destination: BasicBlockId) {
+        self.cfg_version += 1;
         let source = &mut self.blocks[source];
         let mut instructions = std::mem::take(source.instructions_mut());
         let terminator = source.take_terminator();
@@ -371,6 +471,14 @@ impl std::ops::Index<InstructionId> for DataFlowGraph {
     }
 }
 
+impl std::ops::IndexMut<InstructionId> for DataFlowGraph {
+    /// Get a mutable reference to an instruction. Any replacement instruction must
+    /// have results of the same types as the original, which are kept unchanged.
+    fn index_mut(&mut self, id: InstructionId) -> &mut Instruction {
+        &mut self.instructions[id]
+    }
+}
+
 impl std::ops::Index<ValueId> for DataFlowGraph {
     type Output = Value;
     fn index(&self, id: ValueId) -> &Self::Output {
@@ -387,7 +495,11 @@ impl std::ops::Index<BasicBlockId> for DataFlowGraph {
 
 impl std::ops::IndexMut<BasicBlockId> for DataFlowGraph {
     /// Get a mutable reference to a function's basic block for the given id.
+    ///
+    /// Since the block's terminator may be changed through this reference, this
+    /// invalidates any control flow graph cached for the function.
     fn index_mut(&mut self, id: BasicBlockId) -> &mut BasicBlock {
+        self.cfg_version += 1;
         &mut self.blocks[id]
     }
 }
@@ -437,8 +549,10 @@ impl<'dfg> InsertInstructionResult<'dfg> {
 
 #[cfg(test)]
 mod tests {
+    use std::rc::Rc;
+
     use super::DataFlowGraph;
-    use crate::ssa_refactor::ir::instruction::Instruction;
+    use crate::ssa_refactor::ir::{instruction::Instruction, types::Type};
 
     #[test]
     fn make_instruction() {
@@ -449,4 +563,20 @@ mod tests {
         let results = dfg.instruction_results(ins_id);
         assert_eq!(results.len(), 1);
     }
+
+    #[test]
+    fn arrays_of_numeric_constants_are_constant() {
+        let mut dfg = DataFlowGraph::default();
+        let one = dfg.make_constant(1u128.into(), Type::field());
+        let two = dfg.make_constant(2u128.into(), Type::field());
+        let element_type = Rc::new(vec![Type::field()]);
+
+        let constant_array = dfg.make_array(im::vector![one, two], element_type.clone());
+        assert!(dfg.is_constant_array(constant_array));
+
+        let allocate = dfg.make_instruction(Instruction::Allocate, None);
+        let unknown = dfg.instruction_results(allocate)[0];
+        let mixed_array = dfg.make_array(im::vector![one, unknown], element_type);
+        assert!(!dfg.is_constant_array(mixed_array));
+    }
 }
@@ -1,7 +1,11 @@
-use std::collections::HashSet;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
 
-use super::basic_block::BasicBlockId;
-use super::dfg::DataFlowGraph;
+use super::basic_block::{BasicBlock, BasicBlockId};
+use super::dfg::{DataFlowGraph, InsertInstructionResult};
+use super::dom::DominatorTree;
+use super::instruction::{Instruction, SimplifyResult, TerminatorInstruction};
 use super::map::Id;
 use super::types::Type;
 use super::value::ValueId;
@@ -34,6 +38,10 @@ pub(crate) struct Function {
     /// The DataFlowGraph holds the majority of data pertaining to the function
     /// including its blocks, instructions, and values.
     pub(crate) dfg: DataFlowGraph,
+
+    /// The predecessors of each reachable block most recently computed for this function,
+    /// along with the `DataFlowGraph::cfg_version` they were computed at. Used by `predecessors`.
+    predecessors_cache: RefCell<Option<(usize, Rc<Predecessors>)>>,
 }
 
 impl Function {
@@ -43,7 +51,14 @@ impl Function {
     pub(crate) fn new(name: String, id: FunctionId) -> Self {
         let mut dfg = DataFlowGraph::default();
         let entry_block = dfg.make_block();
-        Self { name, id, entry_block, dfg, runtime: RuntimeType::Acir }
+        Self {
+            name,
+            id,
+            entry_block,
+            dfg,
+            runtime: RuntimeType::Acir,
+            predecessors_cache: RefCell::default(),
+        }
     }
 
     /// The name of the function.
@@ -57,6 +72,29 @@ impl Function {
         self.id
     }
 
+    /// Creates a copy of this function with the given id.
+    ///
+    /// References to this function within the copy, such as recursive calls, still refer to
+    /// this function rather than to the copy.
+    pub(crate) fn clone_with_id(&self, id: FunctionId) -> Self {
+        Self {
+            entry_block: self.entry_block,
+            name: self.name.clone(),
+            id,
+            runtime: self.runtime.clone(),
+            dfg: self.dfg.clone(),
+            predecessors_cache: RefCell::default(),
+        }
+    }
+
+    /// Set the id of the function.
+    ///
+    /// This does not update references to the function from other functions,
+    /// see `Ssa::rename_function` for that.
+    pub(crate) fn set_id(&mut self, id: FunctionId) {
+        self.id = id;
+    }
+
     /// Runtime type of the function.
     pub(crate) fn runtime(&self) -> RuntimeType {
         self.runtime.clone()
@@ -77,6 +115,80 @@ impl Function {
         self.entry_block
     }
 
+    /// Appends a new, empty block to this function and returns its id.
+    ///
+    /// Unlike `FunctionBuilder::insert_block`, this does not require the function
+    /// to be the one currently being built.
+    pub(crate) fn append_block(&mut self) -> BasicBlockId {
+        self.dfg.make_block()
+    }
+
+    /// Splits the given block after the instruction at `index`, returning the new block.
+    ///
+    /// Every instruction after `index`, along with the terminator, is moved into the new
+    /// block, and the original block is terminated with a jmp to it. This lets other blocks,
+    /// such as those of an inlined callee, be spliced in between the two halves.
+    pub(crate) fn split_block_at(&mut self, block: BasicBlockId, index: usize) -> BasicBlockId {
+        let new_block = self.dfg.make_block();
+
+        let instructions = self.dfg[block].instructions_mut().split_off(index + 1);
+        let terminator = self.dfg[block].take_terminator();
+        *self.dfg[new_block].instructions_mut() = instructions;
+        self.dfg.set_block_terminator(new_block, terminator);
+
+        let jmp = TerminatorInstruction::Jmp { destination: new_block, arguments: Vec::new() };
+        self.dfg.set_block_terminator(block, jmp);
+        new_block
+    }
+
+    /// Inserts the given instruction into `block` before the instruction at `index`, returning
+    /// its results. An `index` equal to the number of instructions in the block appends it.
+    ///
+    /// Unlike `FunctionBuilder::insert_instruction`, this does not require the function to be
+    /// the one currently being built, and allows the new instruction to be placed before the
+    /// existing instructions using its results. As with the builder, the instruction is
+    /// simplified first, and is only inserted if it does not simplify to an existing value.
+    pub(crate) fn insert_instruction(
+        &mut self,
+        block: BasicBlockId,
+        index: usize,
+        instruction: Instruction,
+        ctrl_typevars: Option<Vec<Type>>,
+    ) -> InsertInstructionResult {
+        match instruction.simplify(&mut self.dfg) {
+            SimplifyResult::SimplifiedTo(value) => InsertInstructionResult::SimplifiedTo(value),
+            SimplifyResult::Remove => InsertInstructionResult::InstructionRemoved,
+            SimplifyResult::None => {
+                let id = self.dfg.make_instruction(instruction, ctrl_typevars);
+                self.dfg[block].instructions_mut().insert(index, id);
+                InsertInstructionResult::Results(self.dfg.instruction_results(id))
+            }
+        }
+    }
+
+    /// Removes the given block from the function, clearing its parameters, instructions,
+    /// and terminator.
+    ///
+    /// Returns the list of other blocks still jumping to this block instead if there are any,
+    /// in which case the block is left unchanged. Note that the entry block cannot be removed.
+    pub(crate) fn remove_block(&mut self, block: BasicBlockId) -> Result<(), Vec<BasicBlockId>> {
+        assert_ne!(block, self.entry_block, "ICE: The entry block of a function cannot be removed");
+
+        let predecessors: Vec<_> = self
+            .dfg
+            .basic_blocks_iter()
+            .filter(|(id, other)| *id != block && other.successors().any(|next| next == block))
+            .map(|(id, _)| id)
+            .collect();
+
+        if !predecessors.is_empty() {
+            return Err(predecessors);
+        }
+
+        self.dfg[block] = BasicBlock::new();
+        Ok(())
+    }
+
     /// Returns the parameters of this function.
     /// The parameters will always match that of this function's entry block.
     pub(crate) fn parameters(&self) -> &[ValueId] {
@@ -99,8 +211,64 @@ impl Function {
         }
         blocks
     }
+
+    /// Returns the reachable blocks of this function in reverse post-order, as ordered by the
+    /// function's dominator tree.
+    ///
+    /// Each block is visited after every block which dominates it, so passes which carry
+    /// information down from dominating blocks can process the blocks in this order.
+    pub(crate) fn blocks_in_dominator_order(&self) -> impl Iterator<Item = BasicBlockId> {
+        let dominator_tree = DominatorTree::with_function(self);
+        let mut blocks: Vec<_> = self.reachable_blocks().into_iter().collect();
+        blocks.sort_by(|a, b| dominator_tree.reverse_post_order_cmp(*a, *b));
+        blocks.into_iter()
+    }
+
+    /// Returns the successors of the given block, each listed once, read from its terminator.
+    pub(crate) fn successors(&self, block: BasicBlockId) -> Vec<BasicBlockId> {
+        let mut successors: Vec<_> = self.dfg[block].successors().collect();
+        successors.dedup();
+        successors
+    }
+
+    /// Returns the reachable predecessors of the given block, each listed once.
+    ///
+    /// Unlike `ControlFlowGraph`, this places no limit on the number of predecessors a block
+    /// may have, so it can be used on any function, such as one with loops in Brillig. The
+    /// predecessors are cached and only recomputed after a block may have had its terminator
+    /// changed.
+    pub(crate) fn predecessors(&self, block: BasicBlockId) -> Vec<BasicBlockId> {
+        self.cached_predecessors().get(&block).cloned().unwrap_or_default()
+    }
+
+    /// Returns the cached predecessors of each reachable block of this function, recomputing
+    /// them first if any terminator may have changed since they were last computed.
+    fn cached_predecessors(&self) -> Rc<Predecessors> {
+        let version = self.dfg.cfg_version();
+        let mut cache = self.predecessors_cache.borrow_mut();
+
+        match cache.as_ref() {
+            Some((cached_version, predecessors)) if *cached_version == version => {
+                predecessors.clone()
+            }
+            _ => {
+                let mut predecessors = Predecessors::new();
+                for block in self.reachable_blocks() {
+                    for successor in self.successors(block) {
+                        predecessors.entry(successor).or_default().push(block);
+                    }
+                }
+                let predecessors = Rc::new(predecessors);
+                *cache = Some((version, predecessors.clone()));
+                predecessors
+            }
+        }
+    }
 }
 
+/// The predecessors of each block of a function which has any.
+type Predecessors = HashMap<BasicBlockId, Vec<BasicBlockId>>;
+
 /// FunctionId is a reference for a function
 ///
 /// This Id is how each function refers to other functions
@@ -126,3 +294,286 @@ fn sign_smoke() {
     signature.params.push(Type::Numeric(super::types::NumericType::NativeField));
     signature.returns.push(Type::Numeric(super::types::NumericType::Unsigned { bit_size: 32 }));
 }
+
+#[test]
+fn cached_cfg_adjacency() {
+    // fn func {
+    //   b0(v0: u1):
+    //     jmpif v0, then: b1, else: b2
+    //   b1():
+    //     jmp b3()
+    //   b2():
+    //     jmp b3()
+    //   b3():
+    //     return
+    // }
+    let mut func = Function::new("func".into(), Id::test_new(0));
+    let b0 = func.entry_block();
+    let v0 = func.dfg.add_block_parameter(b0, Type::bool());
+    let b1 = func.dfg.make_block();
+    let b2 = func.dfg.make_block();
+    let b3 = func.dfg.make_block();
+
+    let jmp_b3 = || TerminatorInstruction::Jmp { destination: b3, arguments: vec![] };
+    func.dfg.set_block_terminator(
+        b0,
+        TerminatorInstruction::JmpIf { condition: v0, then_destination: b1, else_destination: b2 },
+    );
+    func.dfg.set_block_terminator(b1, jmp_b3());
+    func.dfg.set_block_terminator(b2, jmp_b3());
+    func.dfg.set_block_terminator(b3, TerminatorInstruction::Return { return_values: vec![] });
+
+    let mut merge_predecessors = func.predecessors(b3);
+    merge_predecessors.sort();
+    assert_eq!(merge_predecessors, vec![b1, b2]);
+    assert_eq!(func.successors(b0).len(), 2);
+
+    // Changing a terminator must invalidate the cached cfg
+    func.dfg.set_block_terminator(b2, TerminatorInstruction::Return { return_values: vec![] });
+    assert_eq!(func.predecessors(b3), vec![b1]);
+    assert!(func.successors(b2).is_empty());
+}
+
+#[test]
+fn predecessors_are_not_limited_to_two() {
+    // fn func {
+    //   b0(v0: u1):
+    //     jmpif v0, then: b1, else: b2
+    //   b1():
+    //     jmp b3()
+    //   b2():
+    //     jmpif v0, then: b3, else: b3
+    //   b3():
+    //     jmpif v0, then: b3, else: b4
+    //   b4():
+    //     return
+    // }
+    let mut func = Function::new("func".into(), Id::test_new(0));
+    let b0 = func.entry_block();
+    let v0 = func.dfg.add_block_parameter(b0, Type::bool());
+    let b1 = func.dfg.make_block();
+    let b2 = func.dfg.make_block();
+    let b3 = func.dfg.make_block();
+    let b4 = func.dfg.make_block();
+
+    let jmpif = |then_destination, else_destination| TerminatorInstruction::JmpIf {
+        condition: v0,
+        then_destination,
+        else_destination,
+    };
+    func.dfg.set_block_terminator(b0, jmpif(b1, b2));
+    func.dfg.set_block_terminator(
+        b1,
+        TerminatorInstruction::Jmp { destination: b3, arguments: vec![] },
+    );
+    func.dfg.set_block_terminator(b2, jmpif(b3, b3));
+    func.dfg.set_block_terminator(b3, jmpif(b3, b4));
+    func.dfg.set_block_terminator(b4, TerminatorInstruction::Return { return_values: vec![] });
+
+    let mut loop_predecessors = func.predecessors(b3);
+    loop_predecessors.sort();
+    assert_eq!(loop_predecessors, vec![b1, b2, b3]);
+    assert_eq!(func.successors(b2), vec![b3]);
+    assert!(func.predecessors(b0).is_empty());
+}
+
+#[test]
+fn append_block_to_non_current_function() {
+    use crate::ssa_refactor::ssa_builder::FunctionBuilder;
+
+    let main_id = Id::test_new(0);
+    let f1_id = Id::test_new(1);
+
+    let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+    builder.terminate_with_return(vec![]);
+    builder.new_function("f1".into(), f1_id);
+    builder.terminate_with_return(vec![]);
+
+    let mut ssa = builder.finish();
+    let main = ssa.functions.get_mut(&main_id).unwrap();
+    let block = main.append_block();
+    assert_ne!(block, main.entry_block());
+    assert_eq!(main.dfg.basic_blocks_iter().count(), 2);
+    assert_eq!(ssa.functions[&f1_id].dfg.basic_blocks_iter().count(), 1);
+}
+
+#[test]
+fn split_block_at_instruction() {
+    use super::instruction::BinaryOp;
+    use crate::ssa_refactor::ssa_builder::FunctionBuilder;
+
+    // fn main f0 {
+    //   b0(v0: Field):
+    //     v1 = add v0, Field 1
+    //     v2 = mul v1, Field 2
+    //     v3 = sub v2, Field 3
+    //     return v3
+    // }
+    let main_id = Id::test_new(0);
+    let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+    let v0 = builder.add_parameter(Type::field());
+    let one = builder.field_constant(1u128);
+    let v1 = builder.insert_binary(v0, BinaryOp::Add, one);
+    let two = builder.field_constant(2u128);
+    let v2 = builder.insert_binary(v1, BinaryOp::Mul, two);
+    let three = builder.field_constant(3u128);
+    let v3 = builder.insert_binary(v2, BinaryOp::Sub, three);
+    builder.terminate_with_return(vec![v3]);
+
+    // Expected output:
+    // fn main f0 {
+    //   b0(v0: Field):
+    //     v1 = add v0, Field 1
+    //     v2 = mul v1, Field 2
+    //     jmp b1()
+    //   b1():
+    //     v3 = sub v2, Field 3
+    //     return v3
+    // }
+    let mut ssa = builder.finish();
+    let main = ssa.functions.get_mut(&main_id).unwrap();
+    let entry = main.entry_block();
+    let original = main.dfg[entry].instructions().to_vec();
+
+    let new_block = main.split_block_at(entry, 1);
+    assert_eq!(main.dfg[entry].instructions(), &original[..2]);
+    assert_eq!(main.dfg[new_block].instructions(), &original[2..]);
+    assert_eq!(
+        main.dfg[entry].unwrap_terminator(),
+        &TerminatorInstruction::Jmp { destination: new_block, arguments: vec![] }
+    );
+    assert_eq!(
+        main.dfg[new_block].unwrap_terminator(),
+        &TerminatorInstruction::Return { return_values: vec![v3] }
+    );
+    assert_eq!(main.predecessors(new_block), vec![entry]);
+}
+
+#[test]
+fn remove_block_rejects_referenced_block() {
+    use crate::ssa_refactor::ssa_builder::FunctionBuilder;
+
+    // fn main f0 {
+    //   b0():
+    //     jmp b1(Field 1)
+    //   b1(v0: Field):
+    //     return v0
+    //   b2():
+    //     return
+    // }
+    let main_id = Id::test_new(0);
+    let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+    let b1 = builder.insert_block();
+    let b2 = builder.insert_block();
+    let one = builder.field_constant(1u128);
+    builder.terminate_with_jmp(b1, vec![one]);
+
+    builder.switch_to_block(b1);
+    let v0 = builder.add_block_parameter(b1, Type::field());
+    builder.terminate_with_return(vec![v0]);
+
+    builder.switch_to_block(b2);
+    builder.terminate_with_return(vec![]);
+
+    let mut ssa = builder.finish();
+    let main = ssa.functions.get_mut(&main_id).unwrap();
+    let entry = main.entry_block();
+
+    // b1 is still the destination of b0's jmp
+    assert_eq!(main.remove_block(b1), Err(vec![entry]));
+    assert_eq!(main.dfg.block_parameters(b1), &[v0]);
+    assert!(main.dfg[b1].terminator().is_some());
+
+    // b2 is unreferenced
+    assert_eq!(main.remove_block(b2), Ok(()));
+    assert!(main.dfg[b2].terminator().is_none());
+}
+
+#[test]
+fn blocks_in_dominator_order_of_diamond() {
+    use crate::ssa_refactor::ssa_builder::FunctionBuilder;
+
+    // fn main f0 {
+    //   b0(v0: u1):
+    //     jmpif v0, then: b1, else: b2
+    //   b1():
+    //     jmp b3()
+    //   b2():
+    //     jmp b3()
+    //   b3():
+    //     return
+    // }
+    let main_id = Id::test_new(0);
+    let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+    let v0 = builder.add_parameter(Type::bool());
+    let b1 = builder.insert_block();
+    let b2 = builder.insert_block();
+    let b3 = builder.insert_block();
+    // An unreachable block is never visited
+    builder.insert_block();
+
+    builder.terminate_with_jmpif(v0, b1, b2);
+    builder.switch_to_block(b1);
+    builder.terminate_with_jmp(b3, vec![]);
+    builder.switch_to_block(b2);
+    builder.terminate_with_jmp(b3, vec![]);
+    builder.switch_to_block(b3);
+    builder.terminate_with_return(vec![]);
+
+    let ssa = builder.finish();
+    let main = ssa.main();
+    let order: Vec<_> = main.blocks_in_dominator_order().collect();
+    assert_eq!(order.len(), 4);
+
+    let position = |block| order.iter().position(|b| *b == block).unwrap();
+    assert_eq!(position(main.entry_block()), 0);
+    assert!(position(b1) < position(b3));
+    assert!(position(b2) < position(b3));
+}
+
+#[test]
+fn insert_instruction_into_existing_function() {
+    use super::instruction::{Binary, BinaryOp};
+    use crate::ssa_refactor::ssa_builder::FunctionBuilder;
+
+    // fn main f0 {
+    //   b0(v0: Field):
+    //     v1 = mul v0, Field 2
+    //     return v1
+    // }
+    let main_id = Id::test_new(0);
+    let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+    let v0 = builder.add_parameter(Type::field());
+    let two = builder.field_constant(2u128);
+    let v1 = builder.insert_binary(v0, BinaryOp::Mul, two);
+    builder.terminate_with_return(vec![v1]);
+
+    // A pass adds `v2 = add v0, v0` ahead of the mul, then doubles v2 instead of v0:
+    // fn main f0 {
+    //   b0(v0: Field):
+    //     v2 = add v0, v0
+    //     v1 = mul v2, Field 2
+    //     return v1
+    // }
+    let mut ssa = builder.finish();
+    let main = ssa.main_mut();
+    let entry = main.entry_block();
+    let add = Instruction::Binary(Binary { lhs: v0, rhs: v0, operator: BinaryOp::Add });
+    let v2 = main.insert_instruction(entry, 0, add, None).first();
+    assert!(v2 != v0 && v2 != v1);
+    assert_eq!(main.dfg.type_of_value(v2), Type::field());
+
+    let mul = main.dfg[entry].instructions()[1];
+    main.dfg[mul] = Instruction::Binary(Binary { lhs: v2, rhs: two, operator: BinaryOp::Mul });
+
+    let instructions = main.dfg[entry].instructions();
+    assert_eq!(instructions.len(), 2);
+    assert_eq!(main.dfg.instruction_results(instructions[0]), &[v2]);
+    assert_eq!(main.dfg.instruction_results(instructions[1]), &[v1]);
+
+    // Instructions simplifying to an existing value are not inserted
+    let zero = main.dfg.make_constant(0u128.into(), Type::field());
+    let add_zero = Instruction::Binary(Binary { lhs: v0, rhs: zero, operator: BinaryOp::Add });
+    assert_eq!(main.insert_instruction(entry, 0, add_zero, None).first(), v0);
+    assert_eq!(main.dfg[entry].instructions().len(), 2);
+}
@@ -32,22 +32,50 @@ pub(crate) type InstructionId = Id<Instruction>;
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum Intrinsic {
     Sort,
+    ArrayGetOr,
+    DivMod,
+    SaturatingAdd,
+    SaturatingSub,
+    BitLength,
+    Abs,
+    ConditionalSwap,
     Println,
     ToBits(Endian),
     ToRadix(Endian),
     BlackBox(BlackBoxFunc),
 }
 
+/// The name of each intrinsic which is not a black box function, as used in the `#[builtin]`
+/// attribute of its declaration in the standard library.
+///
+/// Adding an intrinsic only requires an entry here along with its lowering.
+const INTRINSIC_NAMES: [(&str, Intrinsic); 13] = [
+    ("println", Intrinsic::Println),
+    ("arraysort", Intrinsic::Sort),
+    ("array_get_or", Intrinsic::ArrayGetOr),
+    ("divmod", Intrinsic::DivMod),
+    ("saturating_add", Intrinsic::SaturatingAdd),
+    ("saturating_sub", Intrinsic::SaturatingSub),
+    ("bit_length", Intrinsic::BitLength),
+    ("abs", Intrinsic::Abs),
+    ("cswap", Intrinsic::ConditionalSwap),
+    ("to_le_radix", Intrinsic::ToRadix(Endian::Little)),
+    ("to_be_radix", Intrinsic::ToRadix(Endian::Big)),
+    ("to_le_bits", Intrinsic::ToBits(Endian::Little)),
+    ("to_be_bits", Intrinsic::ToBits(Endian::Big)),
+];
+
 impl std::fmt::Display for Intrinsic {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Intrinsic::Println => write!(f, "println"),
-            Intrinsic::Sort => write!(f, "arraysort"),
-            Intrinsic::ToBits(Endian::Big) => write!(f, "to_be_bits"),
-            Intrinsic::ToBits(Endian::Little) => write!(f, "to_le_bits"),
-            Intrinsic::ToRadix(Endian::Big) => write!(f, "to_be_radix"),
-            Intrinsic::ToRadix(Endian::Little) => write!(f, "to_le_radix"),
             Intrinsic::BlackBox(function) => write!(f, "{function}"),
+            other => {
+                let (name, _) = INTRINSIC_NAMES
+                    .iter()
+                    .find(|(_, intrinsic)| intrinsic == other)
+                    .expect("ICE: Every intrinsic should have a name");
+                write!(f, "{name}")
+            }
         }
     }
 }
@@ -56,15 +84,11 @@ impl Intrinsic {
     /// Lookup an Intrinsic by name and return it if found.
     /// If there is no such intrinsic by that name, None is returned.
     pub(crate) fn lookup(name: &str) -> Option<Intrinsic> {
-        match name {
-            "println" => Some(Intrinsic::Println),
-            "arraysort" => Some(Intrinsic::Sort),
-            "to_le_radix" => Some(Intrinsic::ToRadix(Endian::Little)),
-            "to_be_radix" => Some(Intrinsic::ToRadix(Endian::Big)),
-            "to_le_bits" => Some(Intrinsic::ToBits(Endian::Little)),
-            "to_be_bits" => Some(Intrinsic::ToBits(Endian::Big)),
-            other => BlackBoxFunc::lookup(other).map(Intrinsic::BlackBox),
-        }
+        INTRINSIC_NAMES
+            .iter()
+            .find(|(intrinsic_name, _)| *intrinsic_name == name)
+            .map(|(_, intrinsic)| *intrinsic)
+            .or_else(|| BlackBoxFunc::lookup(name).map(Intrinsic::BlackBox))
     }
 }
 
@@ -94,9 +118,22 @@ pub(crate) enum Instruction {
     /// Constrains a value to be equal to true
     Constrain(ValueId),
 
+    /// Constrains two values to be equal. Backends can check this directly rather than
+    /// constraining the result of comparing them.
+    ConstrainEqual(ValueId, ValueId),
+
+    /// Constrains two values to differ. Backends can check this more cheaply than
+    /// constraining the negation of an equality.
+    ConstrainNotEqual(ValueId, ValueId),
+
     /// Performs a function call with a list of its arguments.
     Call { func: ValueId, arguments: Vec<ValueId> },
 
+    /// Calls an unconstrained function from a constrained one to compute its results as
+    /// witnesses. Unlike a `Call`, the results are only advice: they are not constrained to
+    /// be the outputs of the function, so any property of them must be constrained separately.
+    Hint { func: ValueId, arguments: Vec<ValueId> },
+
     /// Allocates a region of memory. Note that this is not concerned with
     /// the type of memory, the type of element is determined when loading this memory.
     /// This is used for representing mutable variables and references.
@@ -142,11 +179,39 @@ impl Instruction {
             }
             Instruction::ArraySet { array, .. } => InstructionResultType::Operand(*array),
             Instruction::Constrain(_)
+            | Instruction::ConstrainEqual(..)
+            | Instruction::ConstrainNotEqual(..)
             | Instruction::Store { .. }
             | Instruction::EnableSideEffects { .. } => InstructionResultType::None,
-            Instruction::Load { .. } | Instruction::ArrayGet { .. } | Instruction::Call { .. } => {
-                InstructionResultType::Unknown
-            }
+            Instruction::Load { .. }
+            | Instruction::ArrayGet { .. }
+            | Instruction::Call { .. }
+            | Instruction::Hint { .. } => InstructionResultType::Unknown,
+        }
+    }
+
+    /// Classifies the effects this instruction may have beyond computing its results.
+    ///
+    /// Passes should consult this rather than matching on instructions directly when deciding
+    /// whether an instruction may be removed, deduplicated, or moved.
+    pub(crate) fn side_effects(&self) -> SideEffects {
+        match self {
+            Instruction::Binary(_)
+            | Instruction::Cast(..)
+            | Instruction::Not(_)
+            | Instruction::Truncate { .. }
+            | Instruction::ArrayGet { .. }
+            | Instruction::ArraySet { .. } => SideEffects::Pure,
+            Instruction::Allocate => SideEffects::Allocates,
+            Instruction::Load { .. } => SideEffects::ReadsMemory,
+            Instruction::Store { .. } => SideEffects::WritesMemory,
+            // Enabling side effects has no results, but moving or removing it changes which of
+            // the constraints after it are enforced, so it must be kept in place as they are.
+            Instruction::Constrain(_)
+            | Instruction::ConstrainEqual(..)
+            | Instruction::ConstrainNotEqual(..)
+            | Instruction::EnableSideEffects { .. } => SideEffects::Constrains,
+            Instruction::Call { .. } | Instruction::Hint { .. } => SideEffects::Calls,
         }
     }
 
@@ -174,10 +239,18 @@ impl Instruction {
                 max_bit_size: *max_bit_size,
             },
             Instruction::Constrain(value) => Instruction::Constrain(f(*value)),
+            Instruction::ConstrainEqual(lhs, rhs) => Instruction::ConstrainEqual(f(*lhs), f(*rhs)),
+            Instruction::ConstrainNotEqual(lhs, rhs) => {
+                Instruction::ConstrainNotEqual(f(*lhs), f(*rhs))
+            }
             Instruction::Call { func, arguments } => Instruction::Call {
                 func: f(*func),
                 arguments: vecmap(arguments.iter().copied(), f),
             },
+            Instruction::Hint { func, arguments } => Instruction::Hint {
+                func: f(*func),
+                arguments: vecmap(arguments.iter().copied(), f),
+            },
             Instruction::Allocate => Instruction::Allocate,
             Instruction::Load { address } => Instruction::Load { address: f(*address) },
             Instruction::Store { address, value } => {
@@ -202,7 +275,7 @@ impl Instruction {
                 f(binary.lhs);
                 f(binary.rhs);
             }
-            Instruction::Call { func, arguments } => {
+            Instruction::Call { func, arguments } | Instruction::Hint { func, arguments } => {
                 f(*func);
                 for argument in arguments {
                     f(*argument);
@@ -215,7 +288,9 @@ impl Instruction {
             | Instruction::Load { address: value } => {
                 f(*value);
             }
-            Instruction::Store { address, value } => {
+            Instruction::Store { address, value }
+            | Instruction::ConstrainEqual(address, value)
+            | Instruction::ConstrainNotEqual(address, value) => {
                 f(*address);
                 f(*value);
             }
@@ -244,12 +319,16 @@ impl Instruction {
             Instruction::Cast(value, typ) => simplify_cast(*value, typ, dfg),
             Instruction::Not(value) => {
                 match &dfg[dfg.resolve(*value)] {
-                    // Limit optimizing ! on constants to only booleans. If we tried it on fields,
-                    // there is no Not on FieldElement, so we'd need to convert between u128. This
-                    // would be incorrect however since the extra bits on the field would not be flipped.
-                    Value::NumericConstant { constant, typ } if *typ == Type::bool() => {
-                        let value = constant.is_zero() as u128;
-                        SimplifiedTo(dfg.make_constant(value.into(), Type::bool()))
+                    // Limit optimizing ! on constants to only unsigned integers, including booleans.
+                    // There is no Not on FieldElement, so a field constant would need to be
+                    // converted to a u128, which would not flip the field's extra bits.
+                    Value::NumericConstant {
+                        constant,
+                        typ: Type::Numeric(NumericType::Unsigned { bit_size }),
+                    } if *bit_size <= 128 => {
+                        let value = !constant.to_u128() & (u128::MAX >> (128 - bit_size));
+                        let typ = Type::unsigned(*bit_size);
+                        SimplifiedTo(dfg.make_constant(value.into(), typ))
                     }
                     Value::Instruction { instruction, .. } => {
                         // !!v => v
@@ -270,6 +349,25 @@ impl Instruction {
                 }
                 None
             }
+            Instruction::ConstrainEqual(lhs, rhs) => {
+                if dfg.resolve(*lhs) == dfg.resolve(*rhs) {
+                    return Remove;
+                }
+                let lhs = dfg.get_numeric_constant(*lhs);
+                let rhs = dfg.get_numeric_constant(*rhs);
+                match (lhs, rhs) {
+                    (Some(lhs), Some(rhs)) if lhs == rhs => Remove,
+                    _ => None,
+                }
+            }
+            Instruction::ConstrainNotEqual(lhs, rhs) => {
+                let lhs = dfg.get_numeric_constant(*lhs);
+                let rhs = dfg.get_numeric_constant(*rhs);
+                match (lhs, rhs) {
+                    (Some(lhs), Some(rhs)) if lhs != rhs => Remove,
+                    _ => None,
+                }
+            }
             Instruction::ArrayGet { array, index } => {
                 let array = dfg.get_array_constant(*array);
                 let index = dfg.get_numeric_constant(*index);
@@ -299,16 +397,27 @@ impl Instruction {
                 }
                 None
             }
-            Instruction::Truncate { value, bit_size, .. } => {
+            Instruction::Truncate { value, bit_size, max_bit_size } => {
                 if let Some((numeric_constant, typ)) = dfg.get_numeric_constant_with_type(*value) {
                     let integer_modulus = 2_u128.pow(*bit_size);
-                    let truncated = numeric_constant.to_u128() % integer_modulus;
+
+                    // A constant wider than max_bit_size can only be a subtraction which
+                    // underflowed into the field modulus. acir_gen adds the integer modulus to
+                    // subtractions before truncating them, so the result wraps to
+                    // `integer_modulus - x` rather than to the low bits of `p - x`.
+                    let truncated = if numeric_constant.num_bits() > *max_bit_size {
+                        let negated = (-numeric_constant).to_u128() % integer_modulus;
+                        (integer_modulus - negated) % integer_modulus
+                    } else {
+                        numeric_constant.to_u128() % integer_modulus
+                    };
                     SimplifiedTo(dfg.make_constant(truncated.into(), typ))
                 } else {
                     None
                 }
             }
             Instruction::Call { func, arguments } => simplify_call(*func, arguments, dfg),
+            Instruction::Hint { .. } => None,
             Instruction::Allocate { .. } => None,
             Instruction::Load { .. } => None,
             Instruction::Store { .. } => None,
@@ -340,11 +449,25 @@ fn simplify_cast(value: ValueId, dst_typ: &Type, dfg: &mut DataFlowGraph) -> Sim
                 Type::Numeric(NumericType::Unsigned { bit_size }),
             ) => {
                 // Field/Unsigned -> unsigned: truncate
-                let integer_modulus = BigUint::from(2u128).pow(*bit_size);
-                let constant: BigUint = BigUint::from_bytes_be(&constant.to_be_bytes());
-                let truncated = constant % integer_modulus;
-                let truncated = FieldElement::from_be_bytes_reduce(&truncated.to_bytes_be());
-                SimplifiedTo(dfg.make_constant(truncated, dst_typ.clone()))
+                SimplifiedTo(
+                    dfg.make_constant(truncate_constant(constant, *bit_size), dst_typ.clone()),
+                )
+            }
+            (
+                Type::Numeric(
+                    NumericType::Signed { bit_size: src_bit_size }
+                    | NumericType::Unsigned { bit_size: src_bit_size },
+                ),
+                Type::Numeric(
+                    NumericType::Signed { bit_size } | NumericType::Unsigned { bit_size },
+                ),
+            ) if *bit_size <= src_bit_size => {
+                // Integer -> no wider integer, where either is signed: truncate. Signed integers
+                // are stored as their two's complement bit pattern, which truncating preserves.
+                // Widening a negative integer would instead need to sign-extend it.
+                SimplifiedTo(
+                    dfg.make_constant(truncate_constant(constant, *bit_size), dst_typ.clone()),
+                )
             }
             _ => None,
         }
@@ -363,6 +486,9 @@ fn simplify_call(func: ValueId, arguments: &[ValueId], dfg: &mut DataFlowGraph)
         Value::Intrinsic(intrinsic) => *intrinsic,
         _ => return None,
     };
+    if intrinsic == Intrinsic::Sort {
+        return constant_sort(arguments[0], dfg);
+    }
     let constant_args: Option<Vec<_>> =
         arguments.iter().map(|value_id| dfg.get_numeric_constant(*value_id)).collect();
     let constant_args = match constant_args {
@@ -381,7 +507,45 @@ fn simplify_call(func: ValueId, arguments: &[ValueId], dfg: &mut DataFlowGraph)
             let limb_count = constant_args[2].to_u128() as u32;
             SimplifiedTo(constant_to_radix(endian, field, radix, limb_count, dfg))
         }
-        Intrinsic::BlackBox(_) | Intrinsic::Println | Intrinsic::Sort => None,
+        // DivMod returns two values, so it cannot be simplified to a single one
+        Intrinsic::BlackBox(_)
+        | Intrinsic::Println
+        | Intrinsic::Sort
+        | Intrinsic::ArrayGetOr
+        | Intrinsic::DivMod
+        | Intrinsic::SaturatingAdd
+        | Intrinsic::SaturatingSub
+        | Intrinsic::BitLength
+        | Intrinsic::Abs
+        | Intrinsic::ConditionalSwap => None,
+    }
+}
+
+/// Sorts an array of unsigned integer constants at compile-time, in the ascending order the
+/// sorted witnesses are constrained to have when an array is sorted at runtime.
+fn constant_sort(array: ValueId, dfg: &mut DataFlowGraph) -> SimplifyResult {
+    let (elements, element_type) = match dfg.get_array_constant(array) {
+        Some((elements, element_type)) => (elements, element_type),
+        None => return SimplifyResult::None,
+    };
+    if !matches!(element_type.as_slice(), [Type::Numeric(NumericType::Unsigned { .. })]) {
+        return SimplifyResult::None;
+    }
+
+    let constants: Option<Vec<_>> = elements
+        .iter()
+        .map(|element| {
+            let constant = dfg.get_numeric_constant(*element)?;
+            Some((constant.to_u128(), *element))
+        })
+        .collect();
+    match constants {
+        Some(mut constants) => {
+            constants.sort_by_key(|(constant, _)| *constant);
+            let sorted = constants.into_iter().map(|(_, element)| element).collect();
+            SimplifyResult::SimplifiedTo(dfg.make_array(sorted, element_type))
+        }
+        None => SimplifyResult::None,
     }
 }
 
@@ -437,6 +601,43 @@ pub(crate) enum InstructionResultType {
     None,
 }
 
+/// The kinds of effects an instruction may have, as returned by Instruction::side_effects
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub(crate) enum SideEffects {
+    /// The results of this instruction depend only on its operands.
+    Pure,
+
+    /// This instruction returns a new reference, distinct from that of any other allocation.
+    /// It neither reads nor writes memory, so it may be moved past loads and stores, but two
+    /// allocations can never be merged into one.
+    Allocates,
+
+    /// The results of this instruction depend on the state of memory.
+    ReadsMemory,
+
+    /// This instruction modifies memory.
+    WritesMemory,
+
+    /// This instruction adds constraints to the program, or changes when they apply.
+    Constrains,
+
+    /// This instruction calls a function which may have any of the effects above.
+    Calls,
+}
+
+impl SideEffects {
+    /// True if an instruction with these effects can be removed when none of its results are used.
+    pub(crate) fn can_be_removed_if_unused(self) -> bool {
+        matches!(self, SideEffects::Pure | SideEffects::Allocates | SideEffects::ReadsMemory)
+    }
+
+    /// True if two identical instructions with these effects always produce the same results,
+    /// such that one may be replaced by the other.
+    pub(crate) fn can_be_deduplicated(self) -> bool {
+        self == SideEffects::Pure
+    }
+}
+
 /// These are operations which can exit a basic block
 /// ie control flow type operations
 ///
@@ -526,6 +727,14 @@ impl TerminatorInstruction {
     }
 }
 
+/// Returns the given constant modulo `2^bit_size`.
+fn truncate_constant(constant: FieldElement, bit_size: u32) -> FieldElement {
+    let integer_modulus = BigUint::from(2u128).pow(bit_size);
+    let constant: BigUint = BigUint::from_bytes_be(&constant.to_be_bytes());
+    let truncated = constant % integer_modulus;
+    FieldElement::from_be_bytes_reduce(&truncated.to_bytes_be())
+}
+
 /// A binary instruction in the IR.
 #[derive(Debug, PartialEq, Eq, Hash, Clone)]
 pub(crate) struct Binary {
@@ -601,6 +810,14 @@ impl Binary {
                     let zero = dfg.make_constant(FieldElement::zero(), operand_type);
                     return SimplifyResult::SimplifiedTo(zero);
                 }
+                // An unsigned integer is always smaller than a modulus too large for its type
+                if let (Some(rhs), Type::Numeric(NumericType::Unsigned { bit_size })) =
+                    (rhs, &operand_type)
+                {
+                    if rhs.num_bits() > *bit_size {
+                        return SimplifyResult::SimplifiedTo(self.lhs);
+                    }
+                }
             }
             BinaryOp::Eq => {
                 if dfg.resolve(self.lhs) == dfg.resolve(self.rhs) {
@@ -661,7 +878,13 @@ impl Binary {
             BinaryOp::Add => lhs + rhs,
             BinaryOp::Sub => lhs - rhs,
             BinaryOp::Mul => lhs * rhs,
-            BinaryOp::Div => lhs / rhs,
+            BinaryOp::Div => match operand_type {
+                // Dividing by zero fails at runtime, so it must not be folded away
+                Type::Numeric(NumericType::NativeField) if rhs.is_zero() => return None,
+                Type::Numeric(NumericType::NativeField) => lhs / rhs,
+                // Integer division rounds down rather than multiplying by the inverse
+                _ => self.eval_constant_u128_operations(lhs, rhs, &operand_type)?,
+            },
             BinaryOp::Eq => {
                 operand_type = Type::bool();
                 (lhs == rhs).into()
@@ -672,30 +895,55 @@ impl Binary {
             }
 
             // The rest of the operators we must try to convert to u128 first
-            BinaryOp::Mod => self.eval_constant_u128_operations(lhs, rhs)?,
-            BinaryOp::And => self.eval_constant_u128_operations(lhs, rhs)?,
-            BinaryOp::Or => self.eval_constant_u128_operations(lhs, rhs)?,
-            BinaryOp::Xor => self.eval_constant_u128_operations(lhs, rhs)?,
-            BinaryOp::Shl => self.eval_constant_u128_operations(lhs, rhs)?,
-            BinaryOp::Shr => self.eval_constant_u128_operations(lhs, rhs)?,
+            BinaryOp::Mod
+            | BinaryOp::And
+            | BinaryOp::Or
+            | BinaryOp::Xor
+            | BinaryOp::Shl
+            | BinaryOp::Shr => self.eval_constant_u128_operations(lhs, rhs, &operand_type)?,
         };
         Some(dfg.make_constant(value, operand_type))
     }
 
     /// Try to evaluate the given operands as u128s for operators that are only valid on u128s,
-    /// like the bitwise operators and modulus.
+    /// like the bitwise operators, modulus, and integer division.
     fn eval_constant_u128_operations(
         &self,
         lhs: FieldElement,
         rhs: FieldElement,
+        operand_type: &Type,
     ) -> Option<FieldElement> {
         let lhs = lhs.try_into_u128()?;
         let rhs = rhs.try_into_u128()?;
         match self.operator {
-            BinaryOp::Mod => Some((lhs % rhs).into()),
+            // Signed integers are stored as their two's complement bit pattern, on which
+            // unsigned division gives the wrong result for negative operands.
+            BinaryOp::Div | BinaryOp::Mod
+                if matches!(operand_type, Type::Numeric(NumericType::Signed { .. })) =>
+            {
+                None
+            }
+            BinaryOp::Div => lhs.checked_div(rhs).map(Into::into),
+            BinaryOp::Mod => lhs.checked_rem(rhs).map(Into::into),
             BinaryOp::And => Some((lhs & rhs).into()),
             BinaryOp::Or => Some((lhs | rhs).into()),
-            BinaryOp::Shr => Some((lhs >> rhs).into()),
+            BinaryOp::Shr => {
+                let shift = |value: u128| {
+                    u32::try_from(rhs).ok().and_then(|rhs| value.checked_shr(rhs)).unwrap_or(0)
+                };
+
+                // A signed integer is stored as its two's complement bit pattern, so shifting
+                // a negative one right must fill the vacated high bits with ones.
+                match operand_type {
+                    Type::Numeric(NumericType::Signed { bit_size })
+                        if (lhs >> (bit_size - 1)) & 1 == 1 =>
+                    {
+                        let max = u128::MAX >> (128 - bit_size);
+                        Some((shift(lhs) | (max ^ shift(max))).into())
+                    }
+                    _ => Some(shift(lhs).into()),
+                }
+            }
             // Check for overflow and return None if anything does overflow
             BinaryOp::Shl => {
                 let rhs = rhs.try_into().ok()?;
@@ -703,17 +951,17 @@ impl Binary {
             }
 
             // Converting a field xor to a u128 xor would be incorrect since we wouldn't have the
-            // extra bits of the field. So we don't optimize it here.
-            BinaryOp::Xor => None,
-
-            op @ (BinaryOp::Add
-            | BinaryOp::Sub
-            | BinaryOp::Mul
-            | BinaryOp::Div
-            | BinaryOp::Eq
-            | BinaryOp::Lt) => panic!(
-                "eval_constant_u128_operations invalid for {op:?} use eval_constants instead"
-            ),
+            // extra bits of the field. So we only optimize it for integers.
+            BinaryOp::Xor => match operand_type {
+                Type::Numeric(NumericType::NativeField) => None,
+                _ => Some((lhs ^ rhs).into()),
+            },
+
+            op @ (BinaryOp::Add | BinaryOp::Sub | BinaryOp::Mul | BinaryOp::Eq | BinaryOp::Lt) => {
+                panic!(
+                    "eval_constant_u128_operations invalid for {op:?} use eval_constants instead"
+                )
+            }
         }
     }
 }
@@ -789,3 +1037,72 @@ pub(crate) enum SimplifyResult {
     /// Instruction could not be simplified
     None,
 }
+
+#[cfg(test)]
+mod tests {
+    use acvm::acir::BlackBoxFunc;
+
+    use crate::ssa_refactor::ir::{map::Id, types::Type};
+
+    use super::{BinaryOp, Endian, Instruction, Intrinsic, SideEffects, INTRINSIC_NAMES};
+
+    #[test]
+    fn intrinsic_names_round_trip() {
+        for (name, intrinsic) in INTRINSIC_NAMES {
+            assert_eq!(Intrinsic::lookup(name), Some(intrinsic));
+            assert_eq!(intrinsic.to_string(), name);
+        }
+
+        assert_eq!(Intrinsic::lookup("to_le_bits"), Some(Intrinsic::ToBits(Endian::Little)));
+        assert_eq!(
+            Intrinsic::lookup("pedersen"),
+            Some(Intrinsic::BlackBox(BlackBoxFunc::Pedersen))
+        );
+    }
+
+    #[test]
+    fn unregistered_names_are_not_intrinsics() {
+        // Normal standard library functions are compiled as ordinary function calls
+        assert_eq!(Intrinsic::lookup("to_le_bytes"), None);
+        assert_eq!(Intrinsic::lookup("std::hash::pedersen"), None);
+    }
+
+    #[test]
+    fn classifies_side_effects() {
+        let v0 = Id::test_new(0);
+        let v1 = Id::test_new(1);
+
+        let cases = [
+            (Instruction::binary(BinaryOp::Add, v0, v1), SideEffects::Pure),
+            (Instruction::Cast(v0, Type::field()), SideEffects::Pure),
+            (Instruction::ArrayGet { array: v0, index: v1 }, SideEffects::Pure),
+            (Instruction::Allocate, SideEffects::Allocates),
+            (Instruction::Load { address: v0 }, SideEffects::ReadsMemory),
+            (Instruction::Store { address: v0, value: v1 }, SideEffects::WritesMemory),
+            (Instruction::Constrain(v0), SideEffects::Constrains),
+            (Instruction::ConstrainEqual(v0, v1), SideEffects::Constrains),
+            (Instruction::ConstrainNotEqual(v0, v1), SideEffects::Constrains),
+            (Instruction::EnableSideEffects { condition: v0 }, SideEffects::Constrains),
+            (Instruction::Call { func: v0, arguments: vec![v1] }, SideEffects::Calls),
+            (Instruction::Hint { func: v0, arguments: vec![v1] }, SideEffects::Calls),
+        ];
+
+        for (instruction, expected) in cases {
+            assert_eq!(instruction.side_effects(), expected, "{instruction:?}");
+        }
+    }
+
+    #[test]
+    fn only_pure_allocating_and_reading_instructions_are_removable() {
+        assert!(SideEffects::Pure.can_be_removed_if_unused());
+        assert!(SideEffects::Allocates.can_be_removed_if_unused());
+        assert!(SideEffects::ReadsMemory.can_be_removed_if_unused());
+        assert!(!SideEffects::WritesMemory.can_be_removed_if_unused());
+        assert!(!SideEffects::Constrains.can_be_removed_if_unused());
+        assert!(!SideEffects::Calls.can_be_removed_if_unused());
+
+        assert!(SideEffects::Pure.can_be_deduplicated());
+        assert!(!SideEffects::Allocates.can_be_deduplicated());
+        assert!(!SideEffects::ReadsMemory.can_be_deduplicated());
+    }
+}
@@ -0,0 +1,332 @@
+//! A simple interpreter for the SSA IR.
+//!
+//! This is only used within tests, as an oracle to check that optimization passes
+//! preserve the observable behavior of the functions they transform. It evaluates a
+//! single function directly on field elements, so any calls are unsupported.
+use std::collections::HashMap;
+
+use acvm::FieldElement;
+use num_bigint::BigUint;
+
+use super::{
+    basic_block::BasicBlockId,
+    dfg::DataFlowGraph,
+    function::Function,
+    instruction::{Binary, BinaryOp, Instruction, InstructionId, TerminatorInstruction},
+    types::{NumericType, Type},
+    value::{Value, ValueId},
+};
+
+/// The maximum number of blocks to execute before giving up, to prevent
+/// interpreting an infinite loop from hanging a test.
+const BLOCK_LIMIT: usize = 10_000;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum InterpreterValue {
+    Numeric(FieldElement),
+    Array(Vec<InterpreterValue>),
+
+    /// The index of an allocation within the interpreter's memory
+    Reference(usize),
+}
+
+impl InterpreterValue {
+    /// A numeric value holding the given integer.
+    pub(crate) fn numeric(value: u128) -> Self {
+        InterpreterValue::Numeric(FieldElement::from(value))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum InterpreterError {
+    /// A constrain instruction failed while side effects were enabled
+    ConstraintFailed,
+    DivisionByZero,
+    IndexOutOfBounds {
+        index: usize,
+        length: usize,
+    },
+    UninitializedLoad,
+    BlockLimitReached,
+    Unsupported(String),
+}
+
+/// Interprets the given function with the given arguments, returning its return values.
+pub(crate) fn interpret(
+    function: &Function,
+    mut arguments: Vec<InterpreterValue>,
+) -> Result<Vec<InterpreterValue>, InterpreterError> {
+    let mut interpreter = Interpreter::new(&function.dfg);
+    let mut block = function.entry_block();
+
+    for _ in 0..BLOCK_LIMIT {
+        let parameters = function.dfg.block_parameters(block);
+        assert_eq!(parameters.len(), arguments.len());
+        for (parameter, argument) in parameters.iter().zip(arguments) {
+            interpreter.values.insert(*parameter, argument);
+        }
+
+        for instruction in function.dfg[block].instructions() {
+            interpreter.interpret_instruction(*instruction)?;
+        }
+
+        match function.dfg[block].unwrap_terminator() {
+            TerminatorInstruction::Jmp { destination, arguments: jump_arguments } => {
+                arguments = interpreter.lookup_all(jump_arguments)?;
+                block = *destination;
+            }
+            TerminatorInstruction::JmpIf { condition, then_destination, else_destination } => {
+                let condition = interpreter.lookup_numeric(*condition)?;
+                arguments = Vec::new();
+                block = next_block(condition, *then_destination, *else_destination);
+            }
+            TerminatorInstruction::Return { return_values } => {
+                return interpreter.lookup_all(return_values);
+            }
+        }
+    }
+
+    Err(InterpreterError::BlockLimitReached)
+}
+
+fn next_block(
+    condition: FieldElement,
+    then_destination: BasicBlockId,
+    else_destination: BasicBlockId,
+) -> BasicBlockId {
+    if condition.is_zero() {
+        else_destination
+    } else {
+        then_destination
+    }
+}
+
+struct Interpreter<'dfg> {
+    dfg: &'dfg DataFlowGraph,
+    values: HashMap<ValueId, InterpreterValue>,
+    memory: Vec<Option<InterpreterValue>>,
+    side_effects_enabled: bool,
+}
+
+impl<'dfg> Interpreter<'dfg> {
+    fn new(dfg: &'dfg DataFlowGraph) -> Self {
+        Self { dfg, values: HashMap::new(), memory: Vec::new(), side_effects_enabled: true }
+    }
+
+    fn lookup(&self, value: ValueId) -> Result<InterpreterValue, InterpreterError> {
+        let value = self.dfg.resolve(value);
+        if let Some(result) = self.values.get(&value) {
+            return Ok(result.clone());
+        }
+
+        match &self.dfg[value] {
+            Value::NumericConstant { constant, .. } => Ok(InterpreterValue::Numeric(*constant)),
+            Value::Array { array, .. } => {
+                let elements = array.iter().map(|element| self.lookup(*element));
+                Ok(InterpreterValue::Array(elements.collect::<Result<_, _>>()?))
+            }
+            other => Err(InterpreterError::Unsupported(format!("Cannot evaluate {other:?}"))),
+        }
+    }
+
+    fn lookup_all(&self, values: &[ValueId]) -> Result<Vec<InterpreterValue>, InterpreterError> {
+        values.iter().map(|value| self.lookup(*value)).collect()
+    }
+
+    fn lookup_numeric(&self, value: ValueId) -> Result<FieldElement, InterpreterError> {
+        match self.lookup(value)? {
+            InterpreterValue::Numeric(value) => Ok(value),
+            other => {
+                Err(InterpreterError::Unsupported(format!("Expected a numeric, got {other:?}")))
+            }
+        }
+    }
+
+    fn lookup_u128(&self, value: ValueId) -> Result<u128, InterpreterError> {
+        let value = self.lookup_numeric(value)?;
+        value.try_into_u128().ok_or_else(|| {
+            InterpreterError::Unsupported(format!("{value} does not fit into a u128"))
+        })
+    }
+
+    fn lookup_index(&self, value: ValueId) -> Result<usize, InterpreterError> {
+        Ok(self.lookup_u128(value)? as usize)
+    }
+
+    fn lookup_reference(&self, value: ValueId) -> Result<usize, InterpreterError> {
+        match self.lookup(value)? {
+            InterpreterValue::Reference(index) => Ok(index),
+            other => {
+                Err(InterpreterError::Unsupported(format!("Expected a reference, got {other:?}")))
+            }
+        }
+    }
+
+    fn interpret_instruction(&mut self, id: InstructionId) -> Result<(), InterpreterError> {
+        let dfg = self.dfg;
+        let result = match &dfg[id] {
+            Instruction::Binary(binary) => {
+                Some(InterpreterValue::Numeric(self.interpret_binary(binary)?))
+            }
+            Instruction::Cast(value, typ) => {
+                let value = self.lookup_numeric(*value)?;
+                let value = match typ {
+                    Type::Numeric(NumericType::Unsigned { bit_size }) => truncate(value, *bit_size),
+                    _ => value,
+                };
+                Some(InterpreterValue::Numeric(value))
+            }
+            Instruction::Not(value) => {
+                let bit_size = match self.dfg.type_of_value(*value) {
+                    Type::Numeric(NumericType::Unsigned { bit_size }) => bit_size,
+                    other => {
+                        return Err(InterpreterError::Unsupported(format!("not on {other}")));
+                    }
+                };
+                let max = FieldElement::from((1u128 << bit_size) - 1);
+                Some(InterpreterValue::Numeric(max - self.lookup_numeric(*value)?))
+            }
+            Instruction::Truncate { value, bit_size, .. } => {
+                let mut result = self.lookup_numeric(*value)?;
+
+                // Mirror acir_gen by adding the integer modulus to subtractions before
+                // truncating them, which prevents underflowing into the field modulus.
+                if let Value::Instruction { instruction, .. } = &dfg[dfg.resolve(*value)] {
+                    if let Instruction::Binary(Binary { operator: BinaryOp::Sub, .. }) =
+                        &dfg[*instruction]
+                    {
+                        result = result + FieldElement::from(2u128.pow(*bit_size));
+                    }
+                }
+                Some(InterpreterValue::Numeric(truncate(result, *bit_size)))
+            }
+            Instruction::Constrain(value) => {
+                if self.side_effects_enabled && !self.lookup_numeric(*value)?.is_one() {
+                    return Err(InterpreterError::ConstraintFailed);
+                }
+                None
+            }
+            Instruction::ConstrainEqual(lhs, rhs) => {
+                let equal = self.lookup_numeric(*lhs)? == self.lookup_numeric(*rhs)?;
+                if self.side_effects_enabled && !equal {
+                    return Err(InterpreterError::ConstraintFailed);
+                }
+                None
+            }
+            Instruction::ConstrainNotEqual(lhs, rhs) => {
+                let equal = self.lookup_numeric(*lhs)? == self.lookup_numeric(*rhs)?;
+                if self.side_effects_enabled && equal {
+                    return Err(InterpreterError::ConstraintFailed);
+                }
+                None
+            }
+            Instruction::Call { .. } | Instruction::Hint { .. } => {
+                return Err(InterpreterError::Unsupported("calls".to_owned()));
+            }
+            Instruction::Allocate => {
+                self.memory.push(None);
+                Some(InterpreterValue::Reference(self.memory.len() - 1))
+            }
+            Instruction::Load { address } => {
+                let address = self.lookup_reference(*address)?;
+                let value = self.memory[address].clone();
+                Some(value.ok_or(InterpreterError::UninitializedLoad)?)
+            }
+            Instruction::Store { address, value } => {
+                let address = self.lookup_reference(*address)?;
+                let value = self.lookup(*value)?;
+                self.memory[address] = Some(value);
+                None
+            }
+            Instruction::EnableSideEffects { condition } => {
+                self.side_effects_enabled = !self.lookup_numeric(*condition)?.is_zero();
+                None
+            }
+            Instruction::ArrayGet { array, index } => {
+                let array = self.lookup_array(*array)?;
+                let index = self.lookup_index(*index)?;
+                match array.get(index) {
+                    Some(element) => Some(element.clone()),
+                    None => {
+                        return Err(InterpreterError::IndexOutOfBounds {
+                            index,
+                            length: array.len(),
+                        });
+                    }
+                }
+            }
+            Instruction::ArraySet { array, index, value } => {
+                let mut array = self.lookup_array(*array)?;
+                let index = self.lookup_index(*index)?;
+                if index >= array.len() {
+                    let length = array.len();
+                    return Err(InterpreterError::IndexOutOfBounds { index, length });
+                }
+                array[index] = self.lookup(*value)?;
+                Some(InterpreterValue::Array(array))
+            }
+        };
+
+        if let Some(result) = result {
+            let results = dfg.instruction_results(id);
+            assert_eq!(results.len(), 1);
+            self.values.insert(results[0], result);
+        }
+        Ok(())
+    }
+
+    fn lookup_array(&self, value: ValueId) -> Result<Vec<InterpreterValue>, InterpreterError> {
+        match self.lookup(value)? {
+            InterpreterValue::Array(array) => Ok(array),
+            other => {
+                Err(InterpreterError::Unsupported(format!("Expected an array, got {other:?}")))
+            }
+        }
+    }
+
+    fn interpret_binary(&self, binary: &Binary) -> Result<FieldElement, InterpreterError> {
+        let lhs = self.lookup_numeric(binary.lhs)?;
+        let rhs = self.lookup_numeric(binary.rhs)?;
+        let is_field = self.dfg.type_of_value(binary.lhs) == Type::field();
+
+        let result = match binary.operator {
+            BinaryOp::Add => lhs + rhs,
+            BinaryOp::Sub => lhs - rhs,
+            BinaryOp::Mul => lhs * rhs,
+            BinaryOp::Div if is_field => {
+                if rhs.is_zero() {
+                    return Err(InterpreterError::DivisionByZero);
+                }
+                lhs / rhs
+            }
+            BinaryOp::Eq => (lhs == rhs).into(),
+            BinaryOp::Lt => (lhs < rhs).into(),
+            operator => {
+                let lhs = self.lookup_u128(binary.lhs)?;
+                let rhs = self.lookup_u128(binary.rhs)?;
+                let result = match operator {
+                    BinaryOp::Div | BinaryOp::Mod if rhs == 0 => {
+                        return Err(InterpreterError::DivisionByZero);
+                    }
+                    BinaryOp::Div => lhs / rhs,
+                    BinaryOp::Mod => lhs % rhs,
+                    BinaryOp::And => lhs & rhs,
+                    BinaryOp::Or => lhs | rhs,
+                    BinaryOp::Xor => lhs ^ rhs,
+                    BinaryOp::Shl => lhs.checked_shl(rhs as u32).unwrap_or(0),
+                    BinaryOp::Shr => lhs.checked_shr(rhs as u32).unwrap_or(0),
+                    _ => unreachable!("Remaining operators are handled above"),
+                };
+                result.into()
+            }
+        };
+        Ok(result)
+    }
+}
+
+/// Returns `value` modulo `2^bit_size`, treating `value` as an integer.
+fn truncate(value: FieldElement, bit_size: u32) -> FieldElement {
+    let value = BigUint::from_bytes_be(&value.to_be_bytes());
+    let modulus = BigUint::from(1u32) << bit_size;
+    FieldElement::from_be_bytes_reduce(&(value % modulus).to_bytes_be())
+}
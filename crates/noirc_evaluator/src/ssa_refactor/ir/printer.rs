@@ -148,9 +148,18 @@ pub(crate) fn display_instruction(
         Instruction::Constrain(value) => {
             writeln!(f, "constrain {}", show(*value))
         }
+        Instruction::ConstrainEqual(lhs, rhs) => {
+            writeln!(f, "constrain {} == {}", show(*lhs), show(*rhs))
+        }
+        Instruction::ConstrainNotEqual(lhs, rhs) => {
+            writeln!(f, "constrain {} != {}", show(*lhs), show(*rhs))
+        }
         Instruction::Call { func, arguments } => {
             writeln!(f, "call {}({})", show(*func), value_list(function, arguments))
         }
+        Instruction::Hint { func, arguments } => {
+            writeln!(f, "hint {}({})", show(*func), value_list(function, arguments))
+        }
         Instruction::Allocate => writeln!(f, "allocate"),
         Instruction::Load { address } => writeln!(f, "load {}", show(*address)),
         Instruction::Store { address, value } => {
@@ -173,3 +182,52 @@ pub(crate) fn display_instruction(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{function::RuntimeType, instruction::BinaryOp, map::Id, types::Type},
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn function_is_printed_in_block_order() {
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::bool());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+        let v2 = builder.add_block_parameter(b3, Type::field());
+        builder.terminate_with_jmpif(v1, b1, b2);
+
+        builder.switch_to_block(b1);
+        let v3 = builder.insert_binary(v0, BinaryOp::Add, v0);
+        builder.terminate_with_jmp(b3, vec![v3]);
+
+        builder.switch_to_block(b2);
+        builder.terminate_with_jmp(b3, vec![v0]);
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![v2]);
+
+        // Each block is followed by its successors depth-first, so b3 is printed before b2
+        let expected = "\
+fn main f0 {
+  b0(v0: Field, v1: u1):
+    jmpif v1 then: b1, else: b2
+  b1():
+    v3 = add v0, v0
+    jmp b3(v3)
+  b3(v2: Field):
+    return v2
+  b2():
+    jmp b3(v0)
+}
+";
+        let ssa = builder.finish();
+        assert_eq!(ssa.to_string(), expected);
+    }
+}
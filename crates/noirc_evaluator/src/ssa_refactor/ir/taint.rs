@@ -0,0 +1,247 @@
+//! A taint analysis tracking which values of a program may depend on its private inputs.
+//!
+//! Each parameter of main is tagged as public or private according to its visibility, and
+//! the tag is propagated forward: the results of an instruction are private if any of its
+//! inputs are, a block parameter is private if any argument passed for it is, and a value
+//! stored to a private reference or returned from a call to a function returning a private
+//! value is private as well. Calls propagate the tags of their arguments into the parameters
+//! of the called function, so the analysis covers every function of the program at once.
+//!
+//! Only explicit data flow is tracked. A value which merely depends on the outcome of a
+//! branch on a private value is not itself tagged private, but the branch is reported by
+//! `Taint::private_branches` instead.
+use std::collections::{BTreeMap, HashSet};
+
+use crate::ssa_refactor::ssa_gen::Ssa;
+
+use super::{
+    basic_block::BasicBlockId,
+    function::{Function, FunctionId, RuntimeType},
+    instruction::{Instruction, TerminatorInstruction},
+    value::{Value, ValueId},
+};
+
+/// Whether a value may depend on a private input of the program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Visibility {
+    Public,
+    Private,
+}
+
+/// The values of each function in a program which may depend on a private input.
+pub(crate) struct Taint {
+    private: BTreeMap<FunctionId, HashSet<ValueId>>,
+}
+
+impl Taint {
+    /// Compute the private values of each function in the given program, starting from the
+    /// given private parameters of main.
+    pub(crate) fn with_ssa(ssa: &Ssa, private_parameters: &[ValueId]) -> Self {
+        let mut private: BTreeMap<_, HashSet<_>> =
+            ssa.functions.keys().map(|id| (*id, HashSet::new())).collect();
+
+        let main = &ssa.functions[&ssa.main_id];
+        let main_private = private.get_mut(&ssa.main_id).expect("Expected main to have an entry");
+        main_private.extend(private_parameters.iter().map(|value| main.dfg.resolve(*value)));
+
+        // Each pass over the program may make the parameters or returned values of another
+        // function private, so repeat until nothing more is tagged.
+        let mut taint = Self { private };
+        loop {
+            let mut changed = false;
+            for function in ssa.functions.values() {
+                changed |= taint.propagate(ssa, function);
+            }
+            if !changed {
+                return taint;
+            }
+        }
+    }
+
+    /// Returns the visibility of the given value within the given function.
+    pub(crate) fn visibility(&self, function: FunctionId, value: ValueId) -> Visibility {
+        if self.private[&function].contains(&value) {
+            Visibility::Private
+        } else {
+            Visibility::Public
+        }
+    }
+
+    /// Returns each block of an unconstrained function which branches on a private value,
+    /// ordered by function. Such a branch may leak the value through the time taken to
+    /// execute the function.
+    pub(crate) fn private_branches(&self, ssa: &Ssa) -> Vec<(FunctionId, BasicBlockId)> {
+        let mut branches = Vec::new();
+        for (id, function) in &ssa.functions {
+            if function.runtime() != RuntimeType::Brillig {
+                continue;
+            }
+            let mut blocks: Vec<_> = function.reachable_blocks().into_iter().collect();
+            blocks.sort();
+
+            for block in blocks {
+                if let TerminatorInstruction::JmpIf { condition, .. } =
+                    function.dfg[block].unwrap_terminator()
+                {
+                    let condition = function.dfg.resolve(*condition);
+                    if self.visibility(*id, condition) == Visibility::Private {
+                        branches.push((*id, block));
+                    }
+                }
+            }
+        }
+        branches
+    }
+
+    /// Propagates the private values of the given function through its body once, returning
+    /// true if any value within it or any other function was newly tagged private.
+    fn propagate(&mut self, ssa: &Ssa, function: &Function) -> bool {
+        let dfg = &function.dfg;
+        let mut changed = false;
+
+        for block in function.reachable_blocks() {
+            for instruction in dfg[block].instructions() {
+                let private = &self.private[&function.id()];
+                let mut is_private = false;
+                dfg[*instruction].for_each_value(|value| {
+                    is_private |= private.contains(&dfg.resolve(value));
+                });
+
+                let mut tagged = Vec::new();
+                match &dfg[*instruction] {
+                    Instruction::Store { address, value } => {
+                        if private.contains(&dfg.resolve(*value)) {
+                            tagged.push(dfg.resolve(*address));
+                        }
+                    }
+                    Instruction::Call { func, arguments } => {
+                        if let Value::Function(callee) = &dfg[dfg.resolve(*func)] {
+                            changed |= self.tag_call(ssa, function, *callee, arguments);
+                            is_private |= self.returns_private_value(&ssa.functions[callee]);
+                        }
+                    }
+                    _ => (),
+                }
+                if is_private {
+                    tagged.extend(dfg.instruction_results(*instruction));
+                }
+                changed |= self.tag(function.id(), tagged);
+            }
+
+            if let TerminatorInstruction::Jmp { destination, arguments } =
+                dfg[block].unwrap_terminator()
+            {
+                let parameters = dfg.block_parameters(*destination);
+                let private = &self.private[&function.id()];
+                let tagged = arguments
+                    .iter()
+                    .zip(parameters)
+                    .filter(|(argument, _)| private.contains(&dfg.resolve(**argument)))
+                    .map(|(_, parameter)| *parameter)
+                    .collect();
+                changed |= self.tag(function.id(), tagged);
+            }
+        }
+        changed
+    }
+
+    /// Tags each parameter of `callee` as private if the argument passed for it by a call
+    /// within `caller` is private, returning true if any was newly tagged.
+    fn tag_call(
+        &mut self,
+        ssa: &Ssa,
+        caller: &Function,
+        callee: FunctionId,
+        arguments: &[ValueId],
+    ) -> bool {
+        let private = &self.private[&caller.id()];
+        let parameters = ssa.functions[&callee].parameters();
+        let tagged = arguments
+            .iter()
+            .zip(parameters)
+            .filter(|(argument, _)| private.contains(&caller.dfg.resolve(**argument)))
+            .map(|(_, parameter)| *parameter)
+            .collect();
+        self.tag(callee, tagged)
+    }
+
+    /// Returns true if the given function may return a private value.
+    fn returns_private_value(&self, function: &Function) -> bool {
+        let private = &self.private[&function.id()];
+        function.reachable_blocks().into_iter().any(|block| {
+            match function.dfg[block].unwrap_terminator() {
+                TerminatorInstruction::Return { return_values } => return_values
+                    .iter()
+                    .any(|value| private.contains(&function.dfg.resolve(*value))),
+                _ => false,
+            }
+        })
+    }
+
+    /// Tags the given values of a function as private, returning true if any was newly tagged.
+    fn tag(&mut self, function: FunctionId, values: Vec<ValueId>) -> bool {
+        let private = self.private.get_mut(&function).expect("Expected function to have an entry");
+        values.into_iter().fold(false, |changed, value| private.insert(value) || changed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{function::RuntimeType, instruction::BinaryOp, map::Id, types::Type},
+        ssa_builder::FunctionBuilder,
+    };
+
+    use super::{Taint, Visibility};
+
+    #[test]
+    fn branch_on_private_value_in_brillig_is_flagged() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     call f1(v0, v1)
+        //     return
+        // }
+        // brillig fn check f1 {
+        //   b0(v2: Field, v3: Field):
+        //     v4 = eq v3, Field 1
+        //     jmpif v4 then: b1, else: b2
+        //   b1():
+        //     v5 = eq v2, Field 1
+        //     jmpif v5 then: b2, else: b2
+        //   b2():
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let check_id = Id::test_new(1);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let check = builder.import_function(check_id);
+        builder.insert_call(check, vec![v0, v1], vec![]);
+        builder.terminate_with_return(vec![]);
+
+        builder.new_brillig_function("check".into(), check_id);
+        let v2 = builder.add_parameter(Type::field());
+        let v3 = builder.add_parameter(Type::field());
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let one = builder.field_constant(1u128);
+        let v4 = builder.insert_binary(v3, BinaryOp::Eq, one);
+        builder.terminate_with_jmpif(v4, b1, b2);
+
+        builder.switch_to_block(b1);
+        let v5 = builder.insert_binary(v2, BinaryOp::Eq, one);
+        builder.terminate_with_jmpif(v5, b2, b2);
+
+        builder.switch_to_block(b2);
+        builder.terminate_with_return(vec![]);
+
+        // Only v0 is private, so only the branch on v5 depends on a private value
+        let ssa = builder.finish();
+        let taint = Taint::with_ssa(&ssa, &[v0]);
+        assert_eq!(taint.visibility(check_id, v2), Visibility::Private);
+        assert_eq!(taint.visibility(check_id, v3), Visibility::Public);
+        assert_eq!(taint.visibility(check_id, v5), Visibility::Private);
+        assert_eq!(taint.private_branches(&ssa), vec![(check_id, b1)]);
+    }
+}
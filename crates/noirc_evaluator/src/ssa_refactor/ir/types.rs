@@ -1,5 +1,6 @@
 use std::rc::Rc;
 
+use acvm::FieldElement;
 use iter_extended::vecmap;
 
 /// A numeric type in the Intermediate representation
@@ -58,6 +59,18 @@ impl Type {
     pub(crate) fn field() -> Type {
         Type::Numeric(NumericType::NativeField)
     }
+
+    /// Returns true if the given constant is within the range of values of this type.
+    ///
+    /// Integers are stored as their unsigned bit pattern, so the constant for an integer of
+    /// either signedness must fit within its bit size. Any field element is a valid Field.
+    pub(crate) fn can_represent(&self, constant: FieldElement) -> bool {
+        match self {
+            Type::Numeric(NumericType::Signed { bit_size })
+            | Type::Numeric(NumericType::Unsigned { bit_size }) => constant.num_bits() <= *bit_size,
+            _ => true,
+        }
+    }
 }
 
 /// Composite Types are essentially flattened struct or tuple types.
@@ -0,0 +1,176 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+
+use crate::ssa_refactor::ssa_gen::Ssa;
+
+use super::{
+    function::{Function, FunctionId},
+    instruction::Instruction,
+    value::{Value, ValueId},
+};
+
+/// The parameters of each function in a program which are never used within its body.
+///
+/// A parameter is used if any instruction or terminator within a reachable block of its
+/// function refers to it. This includes a reference being loaded from or stored to, and a
+/// parameter escaping by being stored, passed to another call or block, or returned.
+///
+/// The unused parameters of a function may only be removed if every call to it can be
+/// rewritten to match, so main and any function whose id escapes as a value other than the
+/// callee of a call are never reported. See `Ssa::change_parameters` for removing them.
+pub(crate) struct UnusedParameters {
+    unused: BTreeMap<FunctionId, Vec<usize>>,
+}
+
+impl UnusedParameters {
+    /// Compute the unused parameters of each function in the given program.
+    pub(crate) fn with_ssa(ssa: &Ssa) -> Self {
+        let escaping = escaping_functions(ssa);
+
+        let unused = ssa
+            .functions
+            .iter()
+            .filter(|(id, _)| **id != ssa.main_id && !escaping.contains(id))
+            .map(|(id, function)| (*id, unused_parameters(function)))
+            .filter(|(_, unused)| !unused.is_empty())
+            .collect();
+
+        Self { unused }
+    }
+
+    /// Returns the index of each unused parameter of the given function, in ascending order.
+    pub(crate) fn unused_parameters(&self, function: FunctionId) -> &[usize] {
+        self.unused.get(&function).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// Returns each function with at least one unused parameter.
+    pub(crate) fn functions(&self) -> impl Iterator<Item = FunctionId> + '_ {
+        self.unused.keys().copied()
+    }
+}
+
+/// Returns the index of each parameter of the given function which is never used.
+fn unused_parameters(function: &Function) -> Vec<usize> {
+    let dfg = &function.dfg;
+    let mut used = HashSet::new();
+
+    for block in function.reachable_blocks() {
+        let mut insert = |value: ValueId| used.insert(dfg.resolve(value));
+        for instruction in dfg[block].instructions() {
+            dfg[*instruction].for_each_value(&mut insert);
+        }
+        dfg[block].unwrap_terminator().for_each_value(&mut insert);
+    }
+
+    let parameters = function.parameters().iter().enumerate();
+    parameters.filter(|(_, parameter)| !used.contains(*parameter)).map(|(i, _)| i).collect()
+}
+
+/// Collects every function whose id is used anywhere other than as the callee of a call.
+/// Such a function may be called indirectly, so not every call to it can be rewritten.
+pub(crate) fn escaping_functions(ssa: &Ssa) -> BTreeSet<FunctionId> {
+    let mut escaping = BTreeSet::new();
+
+    for function in ssa.functions.values() {
+        let dfg = &function.dfg;
+        let mut insert = |value: ValueId| {
+            if let Value::Function(id) = &dfg[dfg.resolve(value)] {
+                escaping.insert(*id);
+            }
+        };
+
+        for block in function.reachable_blocks() {
+            for instruction in dfg[block].instructions() {
+                match &dfg[*instruction] {
+                    Instruction::Call { arguments, .. } | Instruction::Hint { arguments, .. } => {
+                        arguments.iter().for_each(|argument| insert(*argument));
+                    }
+                    other => other.for_each_value(&mut insert),
+                }
+            }
+            dfg[block].unwrap_terminator().for_each_value(&mut insert);
+        }
+    }
+
+    escaping
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{function::RuntimeType, instruction::BinaryOp, map::Id, types::Type},
+        ssa_builder::FunctionBuilder,
+    };
+
+    use super::UnusedParameters;
+
+    #[test]
+    fn unused_parameter_is_flagged() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     v2 = call f1(v0, v0)
+        //     return v2
+        // }
+        // fn foo f1 {
+        //   b0(v0: Field, v1: Field):
+        //     v2 = mul v0, v0
+        //     return v2
+        // }
+        let main_id = Id::test_new(0);
+        let foo_id = Id::test_new(1);
+
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let foo = builder.import_function(foo_id);
+        let results = builder.insert_call(foo, vec![v0, v0], vec![Type::field()]).to_vec();
+        builder.terminate_with_return(results);
+
+        builder.new_function("foo".into(), foo_id);
+        let v0 = builder.add_parameter(Type::field());
+        builder.add_parameter(Type::field());
+        let v2 = builder.insert_binary(v0, BinaryOp::Mul, v0);
+        builder.terminate_with_return(vec![v2]);
+
+        let ssa = builder.finish();
+        let unused = UnusedParameters::with_ssa(&ssa);
+        assert_eq!(unused.unused_parameters(foo_id), &[1]);
+        assert_eq!(unused.functions().collect::<Vec<_>>(), vec![foo_id]);
+    }
+
+    #[test]
+    fn escaping_parameters_are_used() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     jmp b1(v0)
+        //   b1(v2: Field):
+        //     return v2
+        // }
+        // fn foo f1 {
+        //   b0(v0: Field, v1: reference):
+        //     store v0 at v1
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let foo_id = Id::test_new(1);
+
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        builder.add_parameter(Type::field());
+        let b1 = builder.insert_block();
+        let v2 = builder.add_block_parameter(b1, Type::field());
+        builder.terminate_with_jmp(b1, vec![v0]);
+        builder.switch_to_block(b1);
+        builder.terminate_with_return(vec![v2]);
+
+        builder.new_function("foo".into(), foo_id);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::Reference);
+        builder.insert_store(v1, v0);
+        builder.terminate_with_return(vec![]);
+
+        // The unused parameter of main is never reported since main has no callers to rewrite
+        let ssa = builder.finish();
+        let unused = UnusedParameters::with_ssa(&ssa);
+        assert!(unused.unused_parameters(main_id).is_empty());
+        assert!(unused.unused_parameters(foo_id).is_empty());
+    }
+}
@@ -37,7 +37,10 @@ pub(crate) enum Value {
     /// This Value originates from a numeric constant
     NumericConstant { constant: FieldElement, typ: Type },
 
-    /// Represents a constant array value
+    /// Represents an array value whose elements may be any other value.
+    ///
+    /// An array whose elements are all numeric constants is known entirely at compile time,
+    /// which `DataFlowGraph::is_constant_array` checks for.
     Array { array: im::Vector<ValueId>, element_type: Rc<CompositeType> },
 
     /// This Value refers to a function in the IR.
@@ -64,7 +67,11 @@ impl Value {
             Value::Instruction { typ, .. } => typ.clone(),
             Value::Param { typ, .. } => typ.clone(),
             Value::NumericConstant { typ, .. } => typ.clone(),
-            Value::Array { element_type, array } => Type::Array(element_type.clone(), array.len()),
+            // The elements of each array entry are stored flattened, so an array of structs
+            // holds several values per entry.
+            Value::Array { element_type, array } => {
+                Type::Array(element_type.clone(), array.len() / element_type.len().max(1))
+            }
             Value::Function { .. } => Type::Function,
             Value::Intrinsic { .. } => Type::Function,
             Value::ForeignFunction { .. } => Type::Function,
@@ -0,0 +1,207 @@
+//! A conservative analysis of the range of integer values each SSA value may take.
+//!
+//! Each value is assigned an inclusive range `min..=max` when one can be proven, by
+//! propagating the ranges of parameters and constants forward through the instructions
+//! of a function. A value without a known range may be any field element.
+//!
+//! Note that an unsigned type alone does not imply its range: the result of an unsigned
+//! addition, for example, is not truncated until a later truncate instruction. Types are
+//! only trusted for the function's parameters, which are range constrained as inputs, and
+//! for the results of truncations and casts which enforce them.
+use std::collections::HashMap;
+
+use super::{
+    dfg::DataFlowGraph,
+    function::Function,
+    instruction::{Binary, BinaryOp, Instruction},
+    post_order::PostOrder,
+    types::{NumericType, Type},
+    value::ValueId,
+};
+
+/// An inclusive range of integers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct ValueRange {
+    pub(crate) min: u128,
+    pub(crate) max: u128,
+}
+
+impl ValueRange {
+    fn new(min: u128, max: u128) -> Self {
+        Self { min, max }
+    }
+
+    /// The range of every value representable with the given number of bits,
+    /// or None if these values do not all fit within a u128.
+    fn with_bit_size(bit_size: u32) -> Option<Self> {
+        (bit_size < 128).then(|| Self::new(0, (1 << bit_size) - 1))
+    }
+
+    /// Returns true if every value within this range can be represented with `bit_size` bits.
+    pub(crate) fn fits_in_bits(&self, bit_size: u32) -> bool {
+        bit_size >= 128 || self.max < (1 << bit_size)
+    }
+}
+
+/// The known ranges of each value within a function.
+#[derive(Default)]
+pub(crate) struct ValueRanges {
+    ranges: HashMap<ValueId, ValueRange>,
+}
+
+impl ValueRanges {
+    /// Computes the ranges of each value within the given function.
+    ///
+    /// Blocks are visited in reverse post-order so that each value is defined before its uses,
+    /// except for block parameters receiving values from a loop's back edge. Since those may be
+    /// anything, only the parameters of the entry block are given a range.
+    pub(crate) fn with_function(function: &Function) -> Self {
+        let mut this = Self::default();
+        let dfg = &function.dfg;
+
+        for parameter in function.parameters() {
+            if let Type::Numeric(NumericType::Unsigned { bit_size }) = dfg.type_of_value(*parameter)
+            {
+                if let Some(range) = ValueRange::with_bit_size(bit_size) {
+                    this.ranges.insert(*parameter, range);
+                }
+            }
+        }
+
+        for block in PostOrder::with_function(function).as_slice().iter().rev() {
+            for instruction in dfg[*block].instructions() {
+                let results = dfg.instruction_results(*instruction);
+                if results.len() == 1 {
+                    if let Some(range) = this.instruction_range(dfg, &dfg[*instruction]) {
+                        this.ranges.insert(results[0], range);
+                    }
+                }
+            }
+        }
+        this
+    }
+
+    /// Returns the known range of the given value, if any.
+    pub(crate) fn get(&self, dfg: &DataFlowGraph, value: ValueId) -> Option<ValueRange> {
+        let value = dfg.resolve(value);
+        match dfg.get_numeric_constant(value) {
+            Some(constant) => {
+                constant.try_into_u128().map(|constant| ValueRange::new(constant, constant))
+            }
+            None => self.ranges.get(&value).copied(),
+        }
+    }
+
+    /// Returns true if the given value is known to be representable with `bit_size` bits.
+    pub(crate) fn fits_in_bits(&self, dfg: &DataFlowGraph, value: ValueId, bit_size: u32) -> bool {
+        self.get(dfg, value).map_or(false, |range| range.fits_in_bits(bit_size))
+    }
+
+    fn instruction_range(
+        &self,
+        dfg: &DataFlowGraph,
+        instruction: &Instruction,
+    ) -> Option<ValueRange> {
+        match instruction {
+            Instruction::Binary(binary) => self.binary_range(dfg, binary),
+            Instruction::Cast(value, Type::Numeric(NumericType::Unsigned { bit_size }))
+            | Instruction::Truncate { value, bit_size, .. } => match self.get(dfg, *value) {
+                Some(range) if range.fits_in_bits(*bit_size) => Some(range),
+                _ => ValueRange::with_bit_size(*bit_size),
+            },
+            Instruction::Cast(value, Type::Numeric(NumericType::NativeField)) => {
+                self.get(dfg, *value)
+            }
+            Instruction::Not(value) => {
+                let bit_size = match dfg.type_of_value(*value) {
+                    Type::Numeric(NumericType::Unsigned { bit_size }) => bit_size,
+                    _ => return None,
+                };
+                let type_range = ValueRange::with_bit_size(bit_size)?;
+                let range = self.get(dfg, *value).filter(|range| range.fits_in_bits(bit_size))?;
+                Some(ValueRange::new(type_range.max - range.max, type_range.max - range.min))
+            }
+            _ => None,
+        }
+    }
+
+    fn binary_range(&self, dfg: &DataFlowGraph, binary: &Binary) -> Option<ValueRange> {
+        if matches!(binary.operator, BinaryOp::Eq | BinaryOp::Lt) {
+            return Some(ValueRange::new(0, 1));
+        }
+
+        let lhs = self.get(dfg, binary.lhs)?;
+        let rhs = self.get(dfg, binary.rhs)?;
+        let is_field = dfg.type_of_value(binary.lhs) == Type::field();
+
+        match binary.operator {
+            BinaryOp::Add => {
+                Some(ValueRange::new(lhs.min.checked_add(rhs.min)?, lhs.max.checked_add(rhs.max)?))
+            }
+            BinaryOp::Sub if lhs.min >= rhs.max => {
+                Some(ValueRange::new(lhs.min - rhs.max, lhs.max - rhs.min))
+            }
+            BinaryOp::Mul => {
+                Some(ValueRange::new(lhs.min.checked_mul(rhs.min)?, lhs.max.checked_mul(rhs.max)?))
+            }
+            // Field division multiplies by an inverse, so it may produce any field element
+            BinaryOp::Div if !is_field && rhs.min > 0 => {
+                Some(ValueRange::new(lhs.min / rhs.max, lhs.max / rhs.min))
+            }
+            BinaryOp::Mod if rhs.min > 0 => Some(ValueRange::new(0, lhs.max.min(rhs.max - 1))),
+            BinaryOp::And => Some(ValueRange::new(0, lhs.max.min(rhs.max))),
+            BinaryOp::Or | BinaryOp::Xor => {
+                let bit_size = u128::BITS - lhs.max.max(rhs.max).leading_zeros();
+                ValueRange::with_bit_size(bit_size)
+            }
+            BinaryOp::Shr => Some(ValueRange::new(lhs.min.checked_shr(rhs.max as u32)?, lhs.max)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{function::RuntimeType, instruction::BinaryOp, map::Id, types::Type},
+        ssa_builder::FunctionBuilder,
+    };
+
+    use super::{ValueRange, ValueRanges};
+
+    #[test]
+    fn ranges_propagate_from_parameters() {
+        // fn main f0 {
+        //   b0(v0: u8, v1: Field):
+        //     v2 = cast v0 as Field
+        //     v3 = add v2, Field 1
+        //     v4 = add v3, v1
+        //     v5 = sub v2, Field 300
+        //     return v3, v4, v5
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(8));
+        let v1 = builder.add_parameter(Type::field());
+
+        let v2 = builder.insert_cast(v0, Type::field());
+        let one = builder.field_constant(1u128);
+        let v3 = builder.insert_binary(v2, BinaryOp::Add, one);
+        let v4 = builder.insert_binary(v3, BinaryOp::Add, v1);
+        let three_hundred = builder.field_constant(300u128);
+        let v5 = builder.insert_binary(v2, BinaryOp::Sub, three_hundred);
+        builder.terminate_with_return(vec![v3, v4, v5]);
+
+        let ssa = builder.finish();
+        let main = ssa.main();
+        let ranges = ValueRanges::with_function(main);
+
+        assert_eq!(ranges.get(&main.dfg, v2), Some(ValueRange { min: 0, max: 255 }));
+        assert_eq!(ranges.get(&main.dfg, v3), Some(ValueRange { min: 1, max: 256 }));
+        assert!(!ranges.fits_in_bits(&main.dfg, v3, 8));
+
+        // v1 may be any field element, and v5 may underflow
+        assert_eq!(ranges.get(&main.dfg, v4), None);
+        assert_eq!(ranges.get(&main.dfg, v5), None);
+    }
+}
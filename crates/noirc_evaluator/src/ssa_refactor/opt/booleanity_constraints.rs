@@ -0,0 +1,280 @@
+//! This file contains the pass removing redundant booleanity constraints from the SSA IR.
+//!
+//! A booleanity constraint is a constrain instruction of the form `x * (x - 1) == 0`, either
+//! as a constrained equality or as an equality constraint, which only holds if `x` is either
+//! zero or one. When a value is reused as several conditions, each
+//! use may assert its booleanity separately. Since each assertion computes its own `x - 1` and
+//! product, the constrained values differ and the redundant constraints pass does not see
+//! them as identical. This pass instead keys each booleanity constraint by `x` itself, and
+//! removes any which is dominated by another on the same value.
+//!
+//! As with the redundant constraints pass, constraints are only considered identical if they
+//! were made under the same side effects condition.
+use std::collections::{HashMap, HashSet};
+
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId,
+        dfg::DataFlowGraph,
+        dom::DominatorTree,
+        function::Function,
+        instruction::{Binary, BinaryOp, Instruction, InstructionId},
+        value::{Value, ValueId},
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Removes each booleanity constraint on a value which was already constrained to be
+    /// boolean earlier in dominator order.
+    pub(crate) fn remove_redundant_booleanity_constraints(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            remove_redundant_booleanity_constraints(function);
+        }
+        self
+    }
+}
+
+/// The side effects condition and the value constrained to be boolean.
+/// A condition of None means side effects have not been disabled.
+type BooleanityKey = (Option<ValueId>, ValueId);
+
+/// The booleanity constraints known to hold at the end of a block.
+#[derive(Clone, Default)]
+struct KnownBooleans {
+    side_effects_condition: Option<ValueId>,
+    booleans: HashSet<BooleanityKey>,
+}
+
+/// Visits the blocks of the function's dominator tree depth-first, starting each block
+/// with the booleanity constraints known at the end of its immediate dominator.
+fn remove_redundant_booleanity_constraints(function: &mut Function) {
+    let dominator_tree = DominatorTree::with_function(function);
+
+    let mut children: HashMap<BasicBlockId, Vec<BasicBlockId>> = HashMap::new();
+    for block in function.reachable_blocks() {
+        if let Some(dominator) = dominator_tree.immediate_dominator(block) {
+            children.entry(dominator).or_default().push(block);
+        }
+    }
+
+    let mut stack = vec![(function.entry_block(), KnownBooleans::default())];
+    while let Some((block, mut known)) = stack.pop() {
+        let redundant = find_redundant_booleanity_constraints(&function.dfg, block, &mut known);
+        if !redundant.is_empty() {
+            function.dfg[block].instructions_mut().retain(|id| !redundant.contains(id));
+        }
+
+        for child in children.get(&block).into_iter().flatten() {
+            stack.push((*child, known.clone()));
+        }
+    }
+}
+
+/// Returns each booleanity constraint in the given block on a value already known to be
+/// boolean, adding the booleanity constraints of the block to `known`.
+fn find_redundant_booleanity_constraints(
+    dfg: &DataFlowGraph,
+    block: BasicBlockId,
+    known: &mut KnownBooleans,
+) -> HashSet<InstructionId> {
+    let mut redundant = HashSet::new();
+
+    for instruction in dfg[block].instructions() {
+        match &dfg[*instruction] {
+            Instruction::Constrain(value) => {
+                if let Some(boolean) = booleanity_constrained_value(dfg, *value) {
+                    if !known.booleans.insert((known.side_effects_condition, boolean)) {
+                        redundant.insert(*instruction);
+                    }
+                }
+            }
+            Instruction::ConstrainEqual(lhs, rhs) => {
+                let (lhs, rhs) = (dfg.resolve(*lhs), dfg.resolve(*rhs));
+                if let Some(boolean) = booleanity_constrained_equality(dfg, lhs, rhs) {
+                    if !known.booleans.insert((known.side_effects_condition, boolean)) {
+                        redundant.insert(*instruction);
+                    }
+                }
+            }
+            Instruction::EnableSideEffects { condition } => {
+                known.side_effects_condition = Some(dfg.resolve(*condition));
+            }
+            _ => (),
+        }
+    }
+    redundant
+}
+
+/// If the given constrained value is `x * (x - 1) == 0`, returns `x`.
+fn booleanity_constrained_value(dfg: &DataFlowGraph, constrained: ValueId) -> Option<ValueId> {
+    let (lhs, rhs) = binary_operands(dfg, constrained, BinaryOp::Eq)?;
+    booleanity_constrained_equality(dfg, lhs, rhs)
+}
+
+/// If the resolved values `lhs` and `rhs` are `x * (x - 1)` and `0`, returns `x`.
+///
+/// The operands of both the multiplication and the equality may be in either order.
+fn booleanity_constrained_equality(
+    dfg: &DataFlowGraph,
+    lhs: ValueId,
+    rhs: ValueId,
+) -> Option<ValueId> {
+    let product = if is_numeric_constant(dfg, rhs, 0) {
+        lhs
+    } else if is_numeric_constant(dfg, lhs, 0) {
+        rhs
+    } else {
+        return None;
+    };
+
+    let (lhs, rhs) = binary_operands(dfg, product, BinaryOp::Mul)?;
+    [(lhs, rhs), (rhs, lhs)].into_iter().find_map(|(value, decremented)| {
+        let (minuend, subtrahend) = binary_operands(dfg, decremented, BinaryOp::Sub)?;
+        (minuend == value && is_numeric_constant(dfg, subtrahend, 1)).then_some(value)
+    })
+}
+
+/// Returns the resolved operands of the given value if it is the result of a binary
+/// instruction with the given operator.
+fn binary_operands(
+    dfg: &DataFlowGraph,
+    value: ValueId,
+    operator: BinaryOp,
+) -> Option<(ValueId, ValueId)> {
+    match &dfg[dfg.resolve(value)] {
+        Value::Instruction { instruction, .. } => match &dfg[*instruction] {
+            Instruction::Binary(Binary { lhs, rhs, operator: op }) if *op == operator => {
+                Some((dfg.resolve(*lhs), dfg.resolve(*rhs)))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+/// True if the given value is a numeric constant equal to `expected`.
+fn is_numeric_constant(dfg: &DataFlowGraph, value: ValueId, expected: u128) -> bool {
+    dfg.get_numeric_constant(value).map_or(false, |constant| constant == expected.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::{Function, RuntimeType},
+            instruction::{BinaryOp, Instruction},
+            map::Id,
+            types::Type,
+            value::ValueId,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    fn count_constraints(function: &Function) -> usize {
+        function
+            .reachable_blocks()
+            .into_iter()
+            .flat_map(|block| function.dfg[block].instructions())
+            .filter(|id| matches!(function.dfg[**id], Instruction::Constrain(_)))
+            .count()
+    }
+
+    /// Inserts `constrain (value * (value - 1)) == 0`
+    fn insert_booleanity_constraint(builder: &mut FunctionBuilder, value: ValueId) {
+        let zero = builder.field_constant(0u128);
+        let one = builder.field_constant(1u128);
+        let decremented = builder.insert_binary(value, BinaryOp::Sub, one);
+        let product = builder.insert_binary(value, BinaryOp::Mul, decremented);
+        let is_boolean = builder.insert_binary(product, BinaryOp::Eq, zero);
+        builder.insert_constrain(is_boolean, None);
+    }
+
+    #[test]
+    fn boolean_reused_as_two_conditions_keeps_one_constraint() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     v1 = sub v0, Field 1
+        //     v2 = mul v0, v1
+        //     v3 = eq v2, Field 0
+        //     constrain v3
+        //     jmp b1()
+        //   b1():
+        //     v4 = sub v0, Field 1
+        //     v5 = mul v0, v4
+        //     v6 = eq v5, Field 0
+        //     constrain v6
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        insert_booleanity_constraint(&mut builder, v0);
+
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+        builder.switch_to_block(b1);
+        insert_booleanity_constraint(&mut builder, v0);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish();
+        assert_eq!(count_constraints(ssa.main()), 2);
+
+        // The constrained values differ, so only this pass can remove the second constraint
+        let ssa = ssa.remove_redundant_constraints();
+        assert_eq!(count_constraints(ssa.main()), 2);
+
+        let ssa = ssa.remove_redundant_booleanity_constraints();
+        let main = ssa.main();
+        assert_eq!(count_constraints(main), 1);
+        assert_eq!(main.dfg[main.entry_block()].instructions().len(), 4);
+    }
+
+    #[test]
+    fn booleanity_constraints_on_different_values_are_kept() {
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        insert_booleanity_constraint(&mut builder, v0);
+        insert_booleanity_constraint(&mut builder, v1);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish().remove_redundant_booleanity_constraints();
+        assert_eq!(count_constraints(ssa.main()), 2);
+    }
+
+    #[test]
+    fn equality_constraint_of_a_known_boolean_is_removed() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     v1 = sub v0, Field 1
+        //     v2 = mul v0, v1
+        //     v3 = eq v2, Field 0
+        //     constrain v3
+        //     v4 = sub v0, Field 1
+        //     v5 = mul v4, v0
+        //     constrain v5 == Field 0
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        insert_booleanity_constraint(&mut builder, v0);
+
+        let zero = builder.field_constant(0u128);
+        let one = builder.field_constant(1u128);
+        let v4 = builder.insert_binary(v0, BinaryOp::Sub, one);
+        let v5 = builder.insert_binary(v4, BinaryOp::Mul, v0);
+        builder.insert_constrain_eq(v5, zero, None);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish().remove_redundant_booleanity_constraints();
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 6);
+        assert!(!instructions
+            .iter()
+            .any(|id| matches!(main.dfg[*id], Instruction::ConstrainEqual(..))));
+    }
+}
@@ -0,0 +1,141 @@
+//! This file contains the pass combining nested truncations within the SSA IR.
+//!
+//! The IR has no dedicated range check; each truncate is what ACIR lowers to a range
+//! constraint on the truncated value. After narrowing and casts, a value may be truncated to
+//! 16 bits and the result then truncated to 8 bits. Since `(x mod 2^16) mod 2^8` equals
+//! `x mod 2^8`, the outer truncate can be applied to `x` directly, after which the inner
+//! truncate is removed by dead instruction elimination if it has no other uses.
+//!
+//! Truncating the result of a subtraction first adds the integer modulus of the truncated bit
+//! size to prevent underflow, which is only sufficient for the bit size of the subtraction's
+//! own truncate. Truncates of subtractions are therefore never combined.
+use crate::ssa_refactor::{
+    ir::{
+        dfg::DataFlowGraph,
+        function::Function,
+        instruction::{Binary, BinaryOp, Instruction},
+        value::{Value, ValueId},
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Replaces each truncate of an already truncated value with a single truncate of the
+    /// original value to the narrower bit size.
+    pub(crate) fn combine_truncations(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            combine_truncations(function);
+        }
+        self
+    }
+}
+
+fn combine_truncations(function: &mut Function) {
+    for block in function.reachable_blocks() {
+        for instruction_id in function.dfg[block].instructions().to_vec() {
+            let (value, bit_size) = match &function.dfg[instruction_id] {
+                Instruction::Truncate { value, bit_size, .. } => (*value, *bit_size),
+                _ => continue,
+            };
+            if let Some((value, max_bit_size)) = inner_truncation(&function.dfg, value, bit_size) {
+                function.dfg[instruction_id] =
+                    Instruction::Truncate { value, bit_size, max_bit_size };
+            }
+        }
+    }
+}
+
+/// If the given value is the result of a truncate to at least `bit_size` bits, returns the
+/// value that truncate was applied to along with its maximum bit size.
+fn inner_truncation(dfg: &DataFlowGraph, value: ValueId, bit_size: u32) -> Option<(ValueId, u32)> {
+    let (value, max_bit_size) = match &dfg[dfg.resolve(value)] {
+        Value::Instruction { instruction, .. } => match &dfg[*instruction] {
+            Instruction::Truncate { value, bit_size: inner_bit_size, max_bit_size }
+                if *inner_bit_size >= bit_size =>
+            {
+                (dfg.resolve(*value), *max_bit_size)
+            }
+            _ => return None,
+        },
+        _ => return None,
+    };
+
+    let is_subtraction = match &dfg[value] {
+        Value::Instruction { instruction, .. } => {
+            matches!(
+                &dfg[*instruction],
+                Instruction::Binary(Binary { operator: BinaryOp::Sub, .. })
+            )
+        }
+        _ => false,
+    };
+    (!is_subtraction).then_some((value, max_bit_size))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{BinaryOp, Instruction},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn nested_truncations_collapse_to_the_narrowest() {
+        // fn main f0 {
+        //   b0(v0: u32):
+        //     v1 = add v0, v0
+        //     v2 = truncate v1 to 16 bits, max_bit_size: 33
+        //     v3 = truncate v2 to 8 bits, max_bit_size: 16
+        //     return v3
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(32));
+        let v1 = builder.insert_binary(v0, BinaryOp::Add, v0);
+        let v2 = builder.insert_truncate(v1, 16, 33);
+        let v3 = builder.insert_truncate(v2, 8, 16);
+        builder.terminate_with_return(vec![v3]);
+
+        // After combining and removing the unused 16-bit truncate:
+        // fn main f0 {
+        //   b0(v0: u32):
+        //     v1 = add v0, v0
+        //     v3 = truncate v1 to 8 bits, max_bit_size: 33
+        //     return v3
+        // }
+        let ssa = builder.finish().combine_truncations().dead_instruction_elimination();
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(
+            main.dfg[instructions[1]],
+            Instruction::Truncate { value: v1, bit_size: 8, max_bit_size: 33 }
+        );
+    }
+
+    #[test]
+    fn truncations_of_subtractions_are_not_combined() {
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(32));
+        let v1 = builder.add_parameter(Type::unsigned(32));
+        let v2 = builder.insert_binary(v0, BinaryOp::Sub, v1);
+        let v3 = builder.insert_truncate(v2, 16, 33);
+        let v4 = builder.insert_truncate(v3, 8, 16);
+        builder.terminate_with_return(vec![v4]);
+
+        let ssa = builder.finish().combine_truncations().dead_instruction_elimination();
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 3);
+        assert_eq!(
+            main.dfg[instructions[2]],
+            Instruction::Truncate { value: v3, bit_size: 8, max_bit_size: 16 }
+        );
+    }
+}
@@ -0,0 +1,263 @@
+//! This file contains a pass evaluating black box function calls on constant inputs.
+//!
+//! Black box functions such as hashes are normally left for the backend to constrain, even
+//! when each of their inputs is known at compile-time. When the compiler has access to an
+//! implementation of the same function, such calls can instead be evaluated once during
+//! compilation and replaced by their constant outputs.
+//!
+//! Which functions can be evaluated is decided by the given [BlackBoxHost]. Any call to a
+//! function the host does not implement, or with any non-constant input, is left unchanged.
+use std::collections::HashSet;
+
+use acvm::{acir::BlackBoxFunc, FieldElement};
+use iter_extended::vecmap;
+
+use crate::ssa_refactor::{
+    ir::{
+        dfg::DataFlowGraph,
+        function::Function,
+        instruction::{Instruction, InstructionId, Intrinsic},
+        types::Type,
+        value::{Value, ValueId},
+    },
+    ssa_gen::Ssa,
+};
+
+/// An implementation of black box functions which can be run during compilation.
+pub trait BlackBoxHost {
+    /// Evaluates the given black box function, returning its outputs flattened into a list
+    /// of field elements, or None if this host does not implement it.
+    ///
+    /// Each array input is flattened into its elements, in order.
+    fn evaluate(&self, func: BlackBoxFunc, inputs: &[FieldElement]) -> Option<Vec<FieldElement>>;
+}
+
+impl Ssa {
+    /// Replaces each call to a black box function with constant inputs by its outputs,
+    /// for each function implemented by the given host.
+    pub(crate) fn fold_constant_black_box_calls(mut self, host: &dyn BlackBoxHost) -> Ssa {
+        for function in self.functions.values_mut() {
+            fold_constant_black_box_calls(function, host);
+        }
+        self
+    }
+}
+
+fn fold_constant_black_box_calls(function: &mut Function, host: &dyn BlackBoxHost) {
+    for block in function.reachable_blocks() {
+        let mut folded = HashSet::new();
+
+        for instruction in function.dfg[block].instructions().to_vec() {
+            if try_fold_call(&mut function.dfg, instruction, host) {
+                folded.insert(instruction);
+            }
+        }
+
+        if !folded.is_empty() {
+            function.dfg[block].instructions_mut().retain(|id| !folded.contains(id));
+        }
+    }
+}
+
+/// Replaces the results of the given instruction by constants if it is a call to a black box
+/// function which the host can evaluate. Returns true if the instruction was folded.
+fn try_fold_call(
+    dfg: &mut DataFlowGraph,
+    instruction: InstructionId,
+    host: &dyn BlackBoxHost,
+) -> bool {
+    let (func, arguments) = match &dfg[instruction] {
+        Instruction::Call { func, arguments } => match dfg[*func] {
+            Value::Intrinsic(Intrinsic::BlackBox(func)) => (func, arguments.clone()),
+            _ => return false,
+        },
+        _ => return false,
+    };
+
+    let mut inputs = Vec::new();
+    for argument in arguments {
+        if !flatten_constant(dfg, argument, &mut inputs) {
+            return false;
+        }
+    }
+
+    let outputs = match host.evaluate(func, &inputs) {
+        Some(outputs) => outputs,
+        None => return false,
+    };
+
+    let results = dfg.instruction_results(instruction).to_vec();
+    let result_types = vecmap(&results, |result| dfg.type_of_value(*result));
+
+    // Check the number of outputs before creating any values so nothing is left half-replaced
+    let expected_outputs: usize = result_types.iter().map(flattened_size).sum();
+    if expected_outputs != outputs.len() {
+        return false;
+    }
+
+    let mut outputs = outputs.into_iter();
+    for (result, typ) in results.into_iter().zip(result_types) {
+        let constant = make_constant(dfg, &typ, &mut outputs);
+        dfg.set_value_from_id(result, constant);
+    }
+    true
+}
+
+/// Pushes each field element of the given constant onto `inputs`.
+/// Returns false if the value is not a constant.
+fn flatten_constant(dfg: &DataFlowGraph, value: ValueId, inputs: &mut Vec<FieldElement>) -> bool {
+    if let Some(constant) = dfg.get_numeric_constant(value) {
+        inputs.push(constant);
+        true
+    } else if let Some((array, _)) = dfg.get_array_constant(value) {
+        array.into_iter().all(|element| flatten_constant(dfg, element, inputs))
+    } else {
+        false
+    }
+}
+
+/// The number of field elements a constant of the given type is flattened into.
+fn flattened_size(typ: &Type) -> usize {
+    match typ {
+        Type::Numeric(_) => 1,
+        Type::Array(elements, length) => {
+            elements.iter().map(flattened_size).sum::<usize>() * length
+        }
+        Type::Reference | Type::Function => 0,
+    }
+}
+
+/// Creates a constant of the given type from the next elements of `outputs`.
+fn make_constant(
+    dfg: &mut DataFlowGraph,
+    typ: &Type,
+    outputs: &mut impl Iterator<Item = FieldElement>,
+) -> ValueId {
+    match typ {
+        Type::Numeric(_) => {
+            let output = outputs.next().expect("ICE: outputs were checked to match result types");
+            dfg.make_constant(output, typ.clone())
+        }
+        Type::Array(elements, length) => {
+            let mut array = im::Vector::new();
+            for _ in 0..*length {
+                for element in elements.iter() {
+                    array.push_back(make_constant(dfg, element, outputs));
+                }
+            }
+            dfg.make_array(array, elements.clone())
+        }
+        Type::Reference | Type::Function => {
+            unreachable!("ICE: black box functions do not return references or functions")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use acvm::{acir::BlackBoxFunc, FieldElement};
+
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{Instruction, Intrinsic},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    use super::BlackBoxHost;
+
+    /// A host implementing only pedersen, as the sum and the number of its inputs.
+    struct TestHost;
+
+    impl BlackBoxHost for TestHost {
+        fn evaluate(
+            &self,
+            func: BlackBoxFunc,
+            inputs: &[FieldElement],
+        ) -> Option<Vec<FieldElement>> {
+            match func {
+                BlackBoxFunc::Pedersen => {
+                    let sum = inputs.iter().fold(FieldElement::zero(), |acc, x| acc + *x);
+                    let count = FieldElement::from(inputs.len() as u128);
+                    Some(vec![sum, count])
+                }
+                _ => None,
+            }
+        }
+    }
+
+    #[test]
+    fn hash_of_constants_is_folded() {
+        // fn main f0 {
+        //   b0():
+        //     v3 = call pedersen([Field 2, Field 3], u32 0)
+        //     return v3
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let two = builder.field_constant(2u128);
+        let three = builder.field_constant(3u128);
+        let inputs = builder.array_constant(im::vector![two, three], Rc::new(vec![Type::field()]));
+        let domain_separator = builder.numeric_constant(0u128, Type::unsigned(32));
+
+        let pedersen = builder.import_intrinsic_id(Intrinsic::BlackBox(BlackBoxFunc::Pedersen));
+        let result_type = Type::Array(Rc::new(vec![Type::field()]), 2);
+        let result =
+            builder.insert_call(pedersen, vec![inputs, domain_separator], vec![result_type])[0];
+        builder.terminate_with_return(vec![result]);
+
+        // Expected output:
+        // fn main f0 {
+        //   b0():
+        //     return [Field 5, Field 3]
+        // }
+        let ssa = builder.finish().fold_constant_black_box_calls(&TestHost);
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+
+        let (array, _) = main.dfg.get_array_constant(result).expect("Expected a constant array");
+        let outputs: Vec<_> =
+            array.iter().map(|element| main.dfg.get_numeric_constant(*element)).collect();
+        assert_eq!(outputs, vec![Some(5u128.into()), Some(3u128.into())]);
+    }
+
+    #[test]
+    fn unimplemented_and_non_constant_calls_are_kept() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     v3 = call pedersen([v0], u32 0)
+        //     v5 = call blake2s([u8 1])
+        //     return v3, v5
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let inputs = builder.array_constant(im::vector![v0], Rc::new(vec![Type::field()]));
+        let domain_separator = builder.numeric_constant(0u128, Type::unsigned(32));
+
+        let pedersen = builder.import_intrinsic_id(Intrinsic::BlackBox(BlackBoxFunc::Pedersen));
+        let result_type = Type::Array(Rc::new(vec![Type::field()]), 2);
+        let v3 =
+            builder.insert_call(pedersen, vec![inputs, domain_separator], vec![result_type])[0];
+
+        let one = builder.numeric_constant(1u128, Type::unsigned(8));
+        let bytes = builder.array_constant(im::vector![one], Rc::new(vec![Type::unsigned(8)]));
+        let blake2s = builder.import_intrinsic_id(Intrinsic::BlackBox(BlackBoxFunc::Blake2s));
+        let result_type = Type::Array(Rc::new(vec![Type::unsigned(8)]), 32);
+        let v5 = builder.insert_call(blake2s, vec![bytes], vec![result_type])[0];
+        builder.terminate_with_return(vec![v3, v5]);
+
+        let ssa = builder.finish().fold_constant_black_box_calls(&TestHost);
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 2);
+        for instruction in instructions {
+            assert!(matches!(main.dfg[*instruction], Instruction::Call { .. }));
+        }
+    }
+}
@@ -71,12 +71,17 @@ impl Context {
             .requires_ctrl_typevars()
             .then(|| vecmap(&old_results, |result| function.dfg.type_of_value(*result)));
 
-        let new_results =
-            match function.dfg.insert_instruction_and_results(instruction, block, ctrl_typevars) {
-                InsertInstructionResult::SimplifiedTo(new_result) => vec![new_result],
-                InsertInstructionResult::Results(new_results) => new_results.to_vec(),
-                InsertInstructionResult::InstructionRemoved => vec![],
-            };
+        let location = function.dfg.get_location(id);
+        let new_results = match function.dfg.insert_instruction_with_location(
+            instruction,
+            block,
+            ctrl_typevars,
+            location,
+        ) {
+            InsertInstructionResult::SimplifiedTo(new_result) => vec![new_result],
+            InsertInstructionResult::Results(new_results) => new_results.to_vec(),
+            InsertInstructionResult::InstructionRemoved => vec![],
+        };
         assert_eq!(old_results.len(), new_results.len());
         for (old_result, new_result) in old_results.iter().zip(new_results) {
             function.dfg.set_value_from_id(*old_result, new_result);
@@ -194,4 +199,43 @@ mod test {
         // The return element is expected to refer to the new add instruction result.
         assert_eq!(main.dfg.resolve(new_add_instr_result), main.dfg.resolve(return_element));
     }
+
+    #[test]
+    fn same_type_casts_are_removed() {
+        // fn main f0 {
+        //   b0(v0: u8, v1: Field):
+        //     v2 = cast v0 as Field
+        //     v3 = add v2, v2
+        //     return v3
+        // }
+        //
+        // After constructing this IR, we set the value of v0 to v1, leaving a cast of a Field
+        // to a Field which is removed, with each of its uses referring to v1 instead.
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(8));
+        let v1 = builder.add_parameter(Type::field());
+        let v2 = builder.insert_cast(v0, Type::field());
+        let v3 = builder.insert_binary(v2, BinaryOp::Add, v2);
+        builder.terminate_with_return(vec![v3]);
+
+        let mut ssa = builder.finish();
+        let main = ssa.main_mut();
+        assert_eq!(main.dfg[main.entry_block()].instructions().len(), 2);
+        main.dfg.set_value_from_id(v0, v1);
+
+        // Expected output:
+        //
+        // fn main f0 {
+        //   b0(v1: Field, v1: Field):
+        //     v3 = add v1, v1
+        //     return v3
+        // }
+        let ssa = ssa.fold_constants();
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(main.dfg.resolve(v2), v1);
+        main.dfg[instructions[0]].for_each_value(|value| assert_eq!(main.dfg.resolve(value), v1));
+    }
 }
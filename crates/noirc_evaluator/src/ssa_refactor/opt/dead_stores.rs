@@ -0,0 +1,230 @@
+//! Dead store elimination: removes any stores to an allocation whose address is never
+//! loaded from and never escapes the function, along with the allocation itself.
+//!
+//! An allocation's address escapes if it is used anywhere other than as the address of
+//! a store: loaded from, passed to a call, stored as a value, returned, or passed as a
+//! block argument. Since such an address is never read, any value stored to it can
+//! never be observed.
+use std::collections::{HashMap, HashSet};
+
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId,
+        dfg::DataFlowGraph,
+        function::Function,
+        instruction::{Instruction, InstructionId},
+        value::{Value, ValueId},
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Removes stores to allocations which are never read from and do not escape,
+    /// along with the allocations themselves once all of their stores are removed.
+    pub(crate) fn dead_store_elimination(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            dead_store_elimination(function);
+        }
+        self
+    }
+}
+
+/// Removes the dead stores and allocations of a single function.
+///
+/// The entire function must be analyzed before anything is removed since an allocation
+/// may be stored to in one block and loaded from in another.
+fn dead_store_elimination(function: &mut Function) {
+    let mut context = Context::default();
+    let blocks = function.reachable_blocks();
+
+    for block in &blocks {
+        context.analyze_block(&function.dfg, *block);
+    }
+
+    let instructions_to_remove = context.dead_instructions();
+    if instructions_to_remove.is_empty() {
+        return;
+    }
+
+    for block in blocks {
+        function.dfg[block]
+            .instructions_mut()
+            .retain(|instruction| !instructions_to_remove.contains(instruction));
+    }
+}
+
+/// An AllocId is the ValueId returned from an allocate instruction. E.g. v0 in v0 = allocate.
+type AllocId = ValueId;
+
+/// Per function context tracking each allocation and how its address is used.
+#[derive(Default)]
+struct Context {
+    /// Maps each allocation to the allocate instruction that created it.
+    allocations: HashMap<AllocId, InstructionId>,
+
+    /// Maps each allocation to every store using it as an address.
+    stores: HashMap<AllocId, Vec<InstructionId>>,
+
+    /// Every value used for anything other than the address of a store.
+    escaped_values: HashSet<ValueId>,
+}
+
+impl Context {
+    /// Records the allocations, stores, and uses of any other values within the given block.
+    fn analyze_block(&mut self, dfg: &DataFlowGraph, block: BasicBlockId) {
+        for instruction_id in dfg[block].instructions() {
+            match &dfg[*instruction_id] {
+                Instruction::Allocate => {
+                    let address = dfg.instruction_results(*instruction_id)[0];
+                    self.allocations.insert(address, *instruction_id);
+                }
+                Instruction::Store { address, value } => {
+                    let address = dfg.resolve(*address);
+                    self.stores.entry(address).or_default().push(*instruction_id);
+                    self.mark_escaped(dfg, *value);
+                }
+                other => other.for_each_value(|value| self.mark_escaped(dfg, value)),
+            }
+        }
+
+        if let Some(terminator) = dfg[block].terminator() {
+            terminator.for_each_value(|value| self.mark_escaped(dfg, value));
+        }
+    }
+
+    /// Marks the given value as escaping. Arrays are inspected recursively since any
+    /// allocation contained within them escapes as well.
+    fn mark_escaped(&mut self, dfg: &DataFlowGraph, value: ValueId) {
+        let value = dfg.resolve(value);
+        match &dfg[value] {
+            Value::Array { array, .. } => {
+                for element in array {
+                    self.mark_escaped(dfg, *element);
+                }
+            }
+            _ => {
+                self.escaped_values.insert(value);
+            }
+        }
+    }
+
+    /// Returns every allocate and store instruction belonging to an allocation
+    /// whose address never escapes.
+    fn dead_instructions(&self) -> HashSet<InstructionId> {
+        let mut dead_instructions = HashSet::new();
+
+        for (address, allocate) in &self.allocations {
+            if !self.escaped_values.contains(address) {
+                dead_instructions.insert(*allocate);
+                if let Some(stores) = self.stores.get(address) {
+                    dead_instructions.extend(stores.iter().copied());
+                }
+            }
+        }
+
+        dead_instructions
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use acvm::FieldElement;
+    use im::vector;
+
+    use crate::ssa_refactor::{
+        ir::{function::RuntimeType, instruction::Instruction, map::Id, types::Type},
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn unread_array_is_removed() {
+        // fn func() {
+        //   b0():
+        //     v0 = allocate
+        //     store [Field 1, Field 2] in v0
+        //     store [Field 2, Field 2] in v0
+        //     return
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.insert_allocate();
+
+        let one = builder.field_constant(FieldElement::one());
+        let two = builder.field_constant(2u128);
+
+        let element_type = Rc::new(vec![Type::field()]);
+        let array = builder.array_constant(vector![one, two], element_type.clone());
+        builder.insert_store(v0, array);
+
+        let array = builder.array_constant(vector![two, two], element_type);
+        builder.insert_store(v0, array);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish();
+        assert_eq!(ssa.main().dfg[ssa.main().entry_block()].instructions().len(), 3);
+
+        // Expected output:
+        // fn func() {
+        //   b0():
+        //     return
+        // }
+        let ssa = ssa.dead_store_elimination();
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+    }
+
+    #[test]
+    fn loaded_allocation_is_kept() {
+        // fn func() {
+        //   b0():
+        //     v0 = allocate
+        //     store Field 1 in v0
+        //     jmp b1()
+        //   b1():
+        //     v1 = load v0
+        //     return v1
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.insert_allocate();
+        let one = builder.field_constant(FieldElement::one());
+        builder.insert_store(v0, one);
+
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+
+        builder.switch_to_block(b1);
+        let v1 = builder.insert_load(v0, Type::field());
+        builder.terminate_with_return(vec![v1]);
+
+        let ssa = builder.finish().dead_store_elimination();
+        let main = ssa.main();
+
+        let entry_instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(entry_instructions.len(), 2);
+        assert!(matches!(main.dfg[entry_instructions[0]], Instruction::Allocate));
+        assert!(matches!(main.dfg[entry_instructions[1]], Instruction::Store { .. }));
+    }
+
+    #[test]
+    fn escaping_allocation_is_kept() {
+        // fn func() {
+        //   b0():
+        //     v0 = allocate
+        //     store Field 1 in v0
+        //     return v0
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.insert_allocate();
+        let one = builder.field_constant(FieldElement::one());
+        builder.insert_store(v0, one);
+        builder.terminate_with_return(vec![v0]);
+
+        let ssa = builder.finish().dead_store_elimination();
+        let main = ssa.main();
+        assert_eq!(main.dfg[main.entry_block()].instructions().len(), 2);
+    }
+}
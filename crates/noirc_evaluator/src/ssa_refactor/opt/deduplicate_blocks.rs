@@ -0,0 +1,334 @@
+//! This file contains the block deduplication pass of the SSA IR.
+//!
+//! Two blocks are equivalent if they have the same parameter types, the same instructions,
+//! and the same terminator, after renaming each value defined within one block to the
+//! corresponding value defined within the other. Since equivalent blocks always compute
+//! the same results, a jmpif between two equivalent blocks can jump to the first of them
+//! unconditionally, leaving the other unreachable.
+//!
+//! Only the two arms of the same jmpif are merged, and only when that jmpif is the single
+//! predecessor of both. The jmpif then becomes a jmp to the remaining arm, which keeps its
+//! single predecessor. Merging blocks with other predecessors could leave a block with more
+//! than two predecessors, which the cfg and the structure expected by flattening disallow.
+//!
+//! A block is only merged away if none of the values it defines are used outside of it,
+//! since those uses would otherwise refer to values which are no longer defined.
+use std::collections::{HashMap, HashSet};
+
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId, dfg::DataFlowGraph, function::Function,
+        instruction::TerminatorInstruction, value::ValueId,
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Replaces each jmpif whose two arms are equivalent with a jmp to the first arm,
+    /// removing the duplicate arm from the cfg.
+    pub(crate) fn deduplicate_blocks(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            deduplicate_blocks(function);
+        }
+        self
+    }
+}
+
+/// Merges duplicate arms one jmpif at a time until no duplicates remain. Merging the arms
+/// of a jmpif may make the arms of an enclosing jmpif equivalent as well, so the search
+/// restarts after each merge.
+fn deduplicate_blocks(function: &mut Function) {
+    while let Some((block, canonical)) = find_duplicate_arms(function) {
+        let jmp = TerminatorInstruction::Jmp { destination: canonical, arguments: Vec::new() };
+        function.dfg.set_block_terminator(block, jmp);
+    }
+}
+
+/// Returns a block ending in a jmpif whose else arm is equivalent to its then arm, along
+/// with the then arm, if any.
+fn find_duplicate_arms(function: &Function) -> Option<(BasicBlockId, BasicBlockId)> {
+    let mut blocks: Vec<_> = function.reachable_blocks().into_iter().collect();
+    blocks.sort();
+
+    blocks.into_iter().find_map(|block| {
+        let (canonical, duplicate) = match function.dfg[block].unwrap_terminator() {
+            TerminatorInstruction::JmpIf { then_destination, else_destination, .. } => {
+                (*then_destination, *else_destination)
+            }
+            _ => return None,
+        };
+
+        let is_only_predecessor = |arm| function.predecessors(arm) == vec![block];
+        let is_duplicate = canonical != duplicate
+            && is_only_predecessor(canonical)
+            && is_only_predecessor(duplicate)
+            && blocks_are_equivalent(&function.dfg, canonical, duplicate)
+            && !defines_escaping_values(function, duplicate);
+
+        is_duplicate.then_some((block, canonical))
+    })
+}
+
+/// Returns each value defined within the given block: its parameters followed by
+/// the results of each of its instructions, in order.
+fn defined_values(dfg: &DataFlowGraph, block: BasicBlockId) -> Vec<ValueId> {
+    let mut values = dfg[block].parameters().to_vec();
+    for instruction in dfg[block].instructions() {
+        values.extend_from_slice(dfg.instruction_results(*instruction));
+    }
+    values
+}
+
+/// Returns true if `block` computes the same results as `canonical`, once each value
+/// defined within `block` is renamed to the corresponding value of `canonical`.
+fn blocks_are_equivalent(
+    dfg: &DataFlowGraph,
+    canonical: BasicBlockId,
+    block: BasicBlockId,
+) -> bool {
+    let canonical_values = defined_values(dfg, canonical);
+    let block_values = defined_values(dfg, block);
+    if canonical_values.len() != block_values.len() {
+        return false;
+    }
+
+    let mut renames = HashMap::new();
+    for (canonical_value, block_value) in canonical_values.into_iter().zip(block_values) {
+        if dfg.type_of_value(canonical_value) != dfg.type_of_value(block_value) {
+            return false;
+        }
+        renames.insert(block_value, canonical_value);
+    }
+
+    let rename = |value: ValueId| {
+        let value = dfg.resolve(value);
+        renames.get(&value).copied().unwrap_or(value)
+    };
+
+    let canonical_instructions = dfg[canonical].instructions();
+    let block_instructions = dfg[block].instructions();
+    for (canonical_instruction, instruction) in
+        canonical_instructions.iter().zip(block_instructions)
+    {
+        let canonical_results = dfg.instruction_results(*canonical_instruction).len();
+        if canonical_results != dfg.instruction_results(*instruction).len() {
+            return false;
+        }
+
+        let canonical_instruction = dfg[*canonical_instruction].map_values(|v| dfg.resolve(v));
+        if canonical_instruction != dfg[*instruction].map_values(rename) {
+            return false;
+        }
+    }
+
+    let canonical_terminator = dfg[canonical].unwrap_terminator().map_values(|v| dfg.resolve(v));
+    canonical_terminator == dfg[block].unwrap_terminator().map_values(rename)
+}
+
+/// Returns true if any value defined within the given block is used by another block.
+fn defines_escaping_values(function: &Function, block: BasicBlockId) -> bool {
+    let dfg = &function.dfg;
+    let defined: HashSet<_> = defined_values(dfg, block).into_iter().collect();
+    let mut escapes = false;
+
+    for other in function.reachable_blocks() {
+        if other == block {
+            continue;
+        }
+
+        let mut check = |value: ValueId| escapes |= defined.contains(&dfg.resolve(value));
+        for instruction in dfg[other].instructions() {
+            dfg[*instruction].for_each_value(&mut check);
+        }
+        dfg[other].unwrap_terminator().for_each_value(&mut check);
+    }
+    escapes
+}
+
+#[cfg(test)]
+mod tests {
+    use acvm::FieldElement;
+
+    use crate::ssa_refactor::{
+        ir::{
+            cfg::ControlFlowGraph, function::RuntimeType, instruction::BinaryOp,
+            instruction::TerminatorInstruction, map::Id, types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn identical_branches_are_merged() {
+        // fn main f0 {
+        //   b0(v0: u1, v1: Field):
+        //     jmpif v0 then: b1, else: b2
+        //   b1():
+        //     v2 = add v1, Field 1
+        //     jmp b3(v2)
+        //   b2():
+        //     v3 = add v1, Field 1
+        //     jmp b3(v3)
+        //   b3(v4: Field):
+        //     return v4
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::bool());
+        let v1 = builder.add_parameter(Type::field());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+        let v4 = builder.add_block_parameter(b3, Type::field());
+        builder.terminate_with_jmpif(v0, b1, b2);
+
+        let one = builder.field_constant(FieldElement::one());
+        for block in [b1, b2] {
+            builder.switch_to_block(block);
+            let sum = builder.insert_binary(v1, BinaryOp::Add, one);
+            builder.terminate_with_jmp(b3, vec![sum]);
+        }
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![v4]);
+
+        let ssa = builder.finish();
+        assert_eq!(ssa.main().reachable_blocks().len(), 4);
+
+        // Expected output:
+        // fn main f0 {
+        //   b0(v0: u1, v1: Field):
+        //     jmp b1()
+        //   b1():
+        //     v2 = add v1, Field 1
+        //     jmp b3(v2)
+        //   b3(v4: Field):
+        //     return v4
+        // }
+        let ssa = ssa.deduplicate_blocks();
+        let main = ssa.main();
+        assert_eq!(main.reachable_blocks().len(), 3);
+
+        match main.dfg[main.entry_block()].unwrap_terminator() {
+            TerminatorInstruction::Jmp { destination, arguments } => {
+                assert_eq!(*destination, b1);
+                assert!(arguments.is_empty());
+            }
+            other => panic!("Expected a jmp to the merged block, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn different_branches_are_kept() {
+        // fn main f0 {
+        //   b0(v0: u1, v1: Field):
+        //     jmpif v0 then: b1, else: b2
+        //   b1():
+        //     v2 = add v1, Field 2
+        //     jmp b3(v2)
+        //   b2():
+        //     v3 = mul v1, Field 2
+        //     jmp b3(v3)
+        //   b3(v4: Field):
+        //     return v4
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::bool());
+        let v1 = builder.add_parameter(Type::field());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+        let v4 = builder.add_block_parameter(b3, Type::field());
+        builder.terminate_with_jmpif(v0, b1, b2);
+
+        let two = builder.field_constant(2u128);
+        for (block, operator) in [(b1, BinaryOp::Add), (b2, BinaryOp::Mul)] {
+            builder.switch_to_block(block);
+            let result = builder.insert_binary(v1, operator, two);
+            builder.terminate_with_jmp(b3, vec![result]);
+        }
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![v4]);
+
+        let ssa = builder.finish().deduplicate_blocks();
+        assert_eq!(ssa.main().reachable_blocks().len(), 4);
+    }
+
+    #[test]
+    fn join_blocks_of_different_ifs_are_kept() {
+        // fn main f0 {
+        //   b0(v0: u1, v1: u1, v2: Field):
+        //     jmpif v0 then: b1, else: b2
+        //   b1():
+        //     jmpif v1 then: b3, else: b4
+        //   b3():
+        //     constrain v2 == Field 1
+        //     jmp b7()
+        //   b4():
+        //     constrain v2 == Field 2
+        //     jmp b7()
+        //   b7():
+        //     jmp b9()
+        //   b2():
+        //     jmpif v1 then: b5, else: b6
+        //   b5():
+        //     constrain v2 == Field 3
+        //     jmp b8()
+        //   b6():
+        //     constrain v2 == Field 4
+        //     jmp b8()
+        //   b8():
+        //     jmp b9()
+        //   b9():
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::bool());
+        let v1 = builder.add_parameter(Type::bool());
+        let v2 = builder.add_parameter(Type::field());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b9 = builder.insert_block();
+        builder.terminate_with_jmpif(v0, b1, b2);
+
+        // The join blocks b7 and b8 of the inner ifs are equivalent, but each has two
+        // predecessors of its own, so merging them would give the remaining one four.
+        let mut next_constant = 0u128;
+        for outer_arm in [b1, b2] {
+            let then_block = builder.insert_block();
+            let else_block = builder.insert_block();
+            let join_block = builder.insert_block();
+
+            builder.switch_to_block(outer_arm);
+            builder.terminate_with_jmpif(v1, then_block, else_block);
+
+            for block in [then_block, else_block] {
+                builder.switch_to_block(block);
+                next_constant += 1;
+                let constant = builder.field_constant(next_constant);
+                builder.insert_constrain_eq(v2, constant, None);
+                builder.terminate_with_jmp(join_block, vec![]);
+            }
+
+            builder.switch_to_block(join_block);
+            builder.terminate_with_jmp(b9, vec![]);
+        }
+
+        builder.switch_to_block(b9);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish().deduplicate_blocks();
+        let main = ssa.main();
+        assert_eq!(main.reachable_blocks().len(), 10);
+
+        let cfg = ControlFlowGraph::with_function(main);
+        for block in main.reachable_blocks() {
+            assert!(cfg.predecessors(block).len() <= 2);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! This file contains a pass which merges identical constant arrays within each function.
+//!
+//! Each array literal creates its own `Value::Array`, so the same lookup table built
+//! twice results in two arrays which backends would allocate separately. Since constant arrays
+//! are immutable and known entirely at compile time, every array with the same elements and
+//! element type can instead refer to the first such array, which is then the only one the
+//! backend needs to allocate.
+use std::{collections::HashMap, rc::Rc};
+
+use crate::ssa_refactor::{
+    ir::{
+        function::Function,
+        types::CompositeType,
+        value::{Value, ValueId},
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Replaces each constant array which is identical to an earlier one with that array.
+    pub(crate) fn deduplicate_constant_arrays(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            deduplicate_constant_arrays(function);
+        }
+        self
+    }
+}
+
+fn deduplicate_constant_arrays(function: &mut Function) {
+    let mut blocks: Vec<_> = function.reachable_blocks().into_iter().collect();
+    blocks.sort();
+
+    let mut used = Vec::new();
+    for block in blocks {
+        let dfg = &function.dfg;
+        for instruction in dfg[block].instructions() {
+            dfg[*instruction].for_each_value(|value| used.push(value));
+        }
+        dfg[block].unwrap_terminator().for_each_value(|value| used.push(value));
+    }
+
+    let mut arrays: HashMap<(im::Vector<ValueId>, Rc<CompositeType>), ValueId> = HashMap::new();
+    for value in used {
+        let dfg = &function.dfg;
+        let value = dfg.resolve(value);
+        let key = match &dfg[value] {
+            Value::Array { array, element_type } if dfg.is_constant_array(value) => {
+                let array = array.iter().map(|element| dfg.resolve(*element)).collect();
+                (array, element_type.clone())
+            }
+            _ => continue,
+        };
+        let canonical = *arrays.entry(key).or_insert(value);
+        function.dfg.set_value_from_id(value, canonical);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::ssa_refactor::{
+        ir::{function::RuntimeType, instruction::BinaryOp, map::Id, types::Type},
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn identical_tables_are_merged() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     v3 = array_get [Field 1, Field 2, Field 3], index v0
+        //     v4 = array_get [Field 1, Field 2, Field 3], index v0
+        //     v5 = array_get [Field 1, Field 2, Field 4], index v0
+        //     v6 = add v3, v4
+        //     v7 = add v6, v5
+        //     return v7
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+
+        let mut table = |elements: [u128; 3]| {
+            let elements = elements.iter().map(|element| builder.field_constant(*element));
+            let elements = elements.collect();
+            builder.array_constant(elements, Rc::new(vec![Type::field()]))
+        };
+        let v1 = table([1, 2, 3]);
+        let v2 = table([1, 2, 3]);
+        let other = table([1, 2, 4]);
+        assert_ne!(v1, v2);
+
+        let v3 = builder.insert_array_get(v1, v0, Type::field());
+        let v4 = builder.insert_array_get(v2, v0, Type::field());
+        let v5 = builder.insert_array_get(other, v0, Type::field());
+        let v6 = builder.insert_binary(v3, BinaryOp::Add, v4);
+        let v7 = builder.insert_binary(v6, BinaryOp::Add, v5);
+        builder.terminate_with_return(vec![v7]);
+
+        // Both reads of [Field 1, Field 2, Field 3] now refer to the same array
+        let ssa = builder.finish().deduplicate_constant_arrays();
+        let dfg = &ssa.main().dfg;
+        assert_eq!(dfg.resolve(v1), dfg.resolve(v2));
+        assert_ne!(dfg.resolve(v1), dfg.resolve(other));
+    }
+}
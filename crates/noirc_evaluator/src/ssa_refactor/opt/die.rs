@@ -1,5 +1,10 @@
 //! Dead Instruction Elimination (DIE) pass: Removes any instruction without side-effects for
 //! which the results are unused.
+//!
+//! Block parameters are never removed by this pass. The parameters of main's entry block are
+//! each assigned a witness in order during ACIR generation, so keeping them, even if unused,
+//! preserves the witness index of each public input expected by the ABI. `Ssa::finalize`
+//! checks that no pass has changed them.
 use std::collections::HashSet;
 
 use crate::ssa_refactor::{
@@ -7,7 +12,7 @@ use crate::ssa_refactor::{
         basic_block::{BasicBlock, BasicBlockId},
         dfg::DataFlowGraph,
         function::Function,
-        instruction::{Instruction, InstructionId},
+        instruction::InstructionId,
         post_order::PostOrder,
         value::{Value, ValueId},
     },
@@ -88,15 +93,9 @@ impl Context {
     /// An instruction can be removed as long as it has no side-effects, and none of its result
     /// values have been referenced.
     fn is_unused(&self, instruction_id: InstructionId, function: &Function) -> bool {
-        use Instruction::*;
-
         let instruction = &function.dfg[instruction_id];
 
-        // These instruction types cannot be removed
-        if matches!(
-            instruction,
-            Constrain(_) | Call { .. } | Store { .. } | EnableSideEffects { .. }
-        ) {
+        if !instruction.side_effects().can_be_removed_if_unused() {
             return false;
         }
 
@@ -134,7 +133,12 @@ impl Context {
 #[cfg(test)]
 mod test {
     use crate::ssa_refactor::{
-        ir::{function::RuntimeType, instruction::BinaryOp, map::Id, types::Type},
+        ir::{
+            function::RuntimeType,
+            instruction::{BinaryOp, Instruction, TerminatorInstruction},
+            map::Id,
+            types::Type,
+        },
         ssa_builder::FunctionBuilder,
     };
 
@@ -218,4 +222,67 @@ mod test {
         assert_eq!(main.dfg[main.entry_block()].instructions().len(), 1);
         assert_eq!(main.dfg[b1].instructions().len(), 6);
     }
+
+    #[test]
+    fn unused_result_is_removed_but_constraint_is_kept() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     v2 = add v0, v1
+        //     v3 = eq v0, v1
+        //     constrain v3
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let _v2 = builder.insert_binary(v0, BinaryOp::Add, v1);
+        let v3 = builder.insert_binary(v0, BinaryOp::Eq, v1);
+        builder.insert_constrain(v3, None);
+        builder.terminate_with_return(vec![]);
+
+        // The constrain has no results, but is kept along with the eq it uses
+        let ssa = builder.finish().dead_instruction_elimination();
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(main.dfg[instructions[1]], Instruction::Constrain(v3));
+    }
+
+    #[test]
+    fn unused_public_input_is_kept() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field, v2: Field):
+        //     v3 = add v0, v1
+        //     v4 = mul v3, v1
+        //     return v2
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let v2 = builder.add_parameter(Type::field());
+        let v3 = builder.insert_binary(v0, BinaryOp::Add, v1);
+        let _v4 = builder.insert_binary(v3, BinaryOp::Mul, v1);
+        builder.terminate_with_return(vec![v2]);
+
+        // Expected output:
+        // fn main f1 {
+        //   b0(v0: Field, v1: Field, v2: Field):
+        //     return v2
+        // }
+        let ssa = builder.finish().inline_functions().dead_instruction_elimination();
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+
+        // Each parameter keeps its position, so v2 is still the third input
+        let parameters = main.parameters();
+        assert_eq!(parameters.len(), 3);
+        match main.dfg[main.entry_block()].unwrap_terminator() {
+            TerminatorInstruction::Return { return_values } => {
+                assert_eq!(return_values, &[parameters[2]]);
+            }
+            other => panic!("Expected a return, found {other:?}"),
+        }
+    }
 }
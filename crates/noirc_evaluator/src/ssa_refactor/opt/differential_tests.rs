@@ -0,0 +1,157 @@
+//! Property tests checking that optimization passes preserve program behavior.
+//!
+//! Each test generates a number of small random programs, interprets each of them on
+//! random inputs before and after running a pass, and asserts that the results are
+//! identical. The SSA interpreter acts as a differential oracle here, so any pass
+//! which changes a program's results or which constraints fail is caught.
+use acvm::FieldElement;
+use iter_extended::vecmap;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+
+use crate::ssa_refactor::{
+    ir::{
+        function::RuntimeType,
+        instruction::BinaryOp,
+        interpreter::{interpret, InterpreterValue},
+        map::Id,
+        types::Type,
+        value::ValueId,
+    },
+    ssa_builder::FunctionBuilder,
+    ssa_gen::Ssa,
+};
+
+/// The number of random programs to generate for each pass
+const PROGRAM_COUNT: u64 = 50;
+
+/// The number of random inputs to interpret each program with
+const INPUT_COUNT: usize = 8;
+
+/// The number of field parameters each generated program has
+const PARAMETER_COUNT: usize = 2;
+
+/// Generates a random program with `PARAMETER_COUNT` field parameters. The program is a
+/// sequence of random arithmetic, comparisons, and constraints, optionally followed by an
+/// if-else diamond whose branches each compute their own result.
+fn generate_program(rng: &mut StdRng) -> Ssa {
+    let mut builder = FunctionBuilder::new("main".into(), Id::test_new(0), RuntimeType::Acir);
+    let mut fields = vecmap(0..PARAMETER_COUNT, |_| builder.add_parameter(Type::field()));
+    let mut bools = Vec::new();
+
+    generate_instructions(rng, &mut builder, &mut fields, &mut bools);
+
+    if !bools.is_empty() && rng.gen_bool(0.5) {
+        let condition = *bools.choose(rng).unwrap();
+        let then_block = builder.insert_block();
+        let else_block = builder.insert_block();
+        let merge_block = builder.insert_block();
+        let merged = builder.add_block_parameter(merge_block, Type::field());
+        builder.terminate_with_jmpif(condition, then_block, else_block);
+
+        for block in [then_block, else_block] {
+            builder.switch_to_block(block);
+            // Values created within a branch are not visible after it
+            let mut branch_fields = fields.clone();
+            let mut branch_bools = bools.clone();
+            generate_instructions(rng, &mut builder, &mut branch_fields, &mut branch_bools);
+            let result = *branch_fields.choose(rng).unwrap();
+            builder.terminate_with_jmp(merge_block, vec![result]);
+        }
+
+        builder.switch_to_block(merge_block);
+        fields.push(merged);
+    }
+
+    let mut return_values = vec![*fields.choose(rng).unwrap()];
+    if let Some(boolean) = bools.choose(rng) {
+        return_values.push(*boolean);
+    }
+    builder.terminate_with_return(return_values);
+    builder.finish()
+}
+
+/// Appends between 1 and 10 random instructions to the current block. Each operand is
+/// either a random small constant or an existing value of the appropriate type.
+fn generate_instructions(
+    rng: &mut StdRng,
+    builder: &mut FunctionBuilder,
+    fields: &mut Vec<ValueId>,
+    bools: &mut Vec<ValueId>,
+) {
+    for _ in 0..rng.gen_range(1..=10) {
+        let field_operand = |rng: &mut StdRng, builder: &mut FunctionBuilder| {
+            if rng.gen_bool(0.3) {
+                builder.field_constant(rng.gen_range(0..4u128))
+            } else {
+                *fields.choose(rng).unwrap()
+            }
+        };
+        let lhs = field_operand(rng, builder);
+        let rhs = field_operand(rng, builder);
+
+        match rng.gen_range(0..7) {
+            0 => fields.push(builder.insert_binary(lhs, BinaryOp::Add, rhs)),
+            1 => fields.push(builder.insert_binary(lhs, BinaryOp::Sub, rhs)),
+            2 => fields.push(builder.insert_binary(lhs, BinaryOp::Mul, rhs)),
+            3 => bools.push(builder.insert_binary(lhs, BinaryOp::Eq, rhs)),
+            4 => bools.push(builder.insert_binary(lhs, BinaryOp::Lt, rhs)),
+            5 if !bools.is_empty() => {
+                let boolean = *bools.choose(rng).unwrap();
+                bools.push(builder.insert_not(boolean));
+            }
+            6 if !bools.is_empty() => {
+                let boolean = *bools.choose(rng).unwrap();
+                builder.insert_constrain(boolean, None);
+            }
+            _ => (),
+        }
+    }
+}
+
+fn random_inputs(rng: &mut StdRng) -> Vec<InterpreterValue> {
+    // Small inputs make it likely for equalities and constraints to hold at least sometimes
+    vecmap(0..PARAMETER_COUNT, |_| {
+        InterpreterValue::Numeric(FieldElement::from(rng.gen_range(0..4u128)))
+    })
+}
+
+/// Asserts that the given pass does not change the result of interpreting
+/// any of the generated programs.
+fn assert_pass_preserves_behavior(pass: impl Fn(Ssa) -> Ssa) {
+    for seed in 0..PROGRAM_COUNT {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let ssa = generate_program(&mut rng);
+        let inputs = vecmap(0..INPUT_COUNT, |_| random_inputs(&mut rng));
+
+        let original = ssa.to_string();
+        let expected = vecmap(&inputs, |inputs| interpret(ssa.main(), inputs.clone()));
+
+        let ssa = pass(ssa);
+        let actual = vecmap(&inputs, |inputs| interpret(ssa.main(), inputs.clone()));
+
+        assert_eq!(
+            expected, actual,
+            "Pass changed the behavior of program {seed}.\nBefore:\n{original}\nAfter:\n{ssa}"
+        );
+    }
+}
+
+#[test]
+fn dead_instruction_elimination_preserves_behavior() {
+    assert_pass_preserves_behavior(Ssa::dead_instruction_elimination);
+}
+
+#[test]
+fn constant_folding_preserves_behavior() {
+    assert_pass_preserves_behavior(Ssa::fold_constants);
+}
+
+#[test]
+fn simplify_cfg_preserves_behavior() {
+    assert_pass_preserves_behavior(Ssa::simplify_cfg);
+}
+
+#[test]
+fn remove_redundant_constraints_preserves_behavior() {
+    assert_pass_preserves_behavior(Ssa::remove_redundant_constraints);
+}
@@ -0,0 +1,147 @@
+//! This file contains a check over the SSA IR for constraints which can never be satisfied.
+//!
+//! Comparisons of values known at compile-time, such as the lengths of two fixed-size arrays
+//! in `assert(a.len() == b.len())`, are folded to constants. If the folded constraint is false,
+//! the program can never be proven, so this is reported as an error at compile-time rather than
+//! producing a circuit which always fails. This includes a generic function's assertion about
+//! its numeric generics, such as `assert(N > 0)`, for an instantiation which does not satisfy
+//! it. The error points to the failing assertion whenever its location is known.
+//!
+//! Only the entry block of each constrained function is checked since every instruction within
+//! it is always executed. After flattening, this is the entire function, and constraints made
+//! within a branch have been rewritten to depend on the branch condition.
+use crate::{
+    errors::{RuntimeError, RuntimeErrorKind},
+    ssa_refactor::{
+        ir::{function::RuntimeType, instruction::Instruction},
+        ssa_gen::Ssa,
+    },
+};
+
+impl Ssa {
+    /// Returns an error if any constrained function always constrains a value equal to zero,
+    /// two different constants to be equal, or a value to differ from itself.
+    ///
+    /// This does not modify the SSA, so it is returned unchanged if no such constraint is found.
+    pub(crate) fn check_for_false_constraints(self) -> Result<Ssa, RuntimeError> {
+        for function in self.functions.values() {
+            if function.runtime() != RuntimeType::Acir {
+                continue;
+            }
+
+            let dfg = &function.dfg;
+            for instruction in dfg[function.entry_block()].instructions() {
+                let is_false = match &dfg[*instruction] {
+                    Instruction::Constrain(value) => {
+                        dfg.get_numeric_constant(*value).map_or(false, |value| value.is_zero())
+                    }
+                    Instruction::ConstrainEqual(lhs, rhs) => {
+                        let lhs = dfg.get_numeric_constant(*lhs);
+                        let rhs = dfg.get_numeric_constant(*rhs);
+                        matches!((lhs, rhs), (Some(lhs), Some(rhs)) if lhs != rhs)
+                    }
+                    Instruction::ConstrainNotEqual(lhs, rhs) => {
+                        dfg.resolve(*lhs) == dfg.resolve(*rhs)
+                    }
+                    _ => false,
+                };
+                if is_false {
+                    let message = "Constraint is always false".to_owned();
+                    return Err(match dfg.get_location(*instruction) {
+                        Some(location) => {
+                            let kind = RuntimeErrorKind::UnstructuredError { message };
+                            RuntimeError::new(kind, Some(location))
+                        }
+                        None => RuntimeErrorKind::Spanless(message).into(),
+                    });
+                }
+            }
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use fm::FileId;
+    use noirc_errors::{Location, Span};
+
+    use crate::{
+        errors::RuntimeErrorKind,
+        ssa_refactor::{
+            ir::{function::RuntimeType, instruction::BinaryOp, map::Id, types::Type},
+            ssa_builder::FunctionBuilder,
+        },
+    };
+
+    #[test]
+    fn unequal_constant_lengths_are_an_error() {
+        // Equivalent to `assert(a.len() == b.len())` for `a: [Field; 3]` and `b: [Field; 4]`
+        // fn main f0 {
+        //   b0():
+        //     v2 = eq Field 3, Field 4
+        //     constrain v2
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let three = builder.field_constant(3u128);
+        let four = builder.field_constant(4u128);
+        let equal = builder.insert_binary(three, BinaryOp::Eq, four);
+        builder.insert_constrain(equal, None);
+        builder.terminate_with_return(vec![]);
+
+        assert!(builder.finish().check_for_false_constraints().is_err());
+    }
+
+    #[test]
+    fn runtime_length_comparisons_are_kept() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     v2 = eq v0, v1
+        //     constrain v2
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let equal = builder.insert_binary(v0, BinaryOp::Eq, v1);
+        builder.insert_constrain(equal, None);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish().check_for_false_constraints().expect("Should not fail");
+        let main = ssa.main();
+        assert_eq!(main.dfg[main.entry_block()].instructions().len(), 2);
+    }
+
+    #[test]
+    fn unsatisfied_generic_assertion_is_reported_at_its_location() {
+        // Equivalent to `assert(N > 0)` within `fn f<N>()`, instantiated with `N = 0`. The
+        // comparison of two constants folds, leaving:
+        // fn f f0 {
+        //   b0():
+        //     constrain u1 0
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("f".into(), main_id, RuntimeType::Acir);
+        let zero = builder.numeric_constant(0u128, Type::unsigned(32));
+        let is_positive = builder.insert_binary(zero, BinaryOp::Lt, zero);
+        let location = Location::new(Span::new(0..13), FileId::dummy());
+        builder.insert_constrain(is_positive, Some(location));
+        builder.terminate_with_return(vec![]);
+
+        let error = match builder.finish().check_for_false_constraints() {
+            Ok(_) => panic!("Expected the assertion to be reported"),
+            Err(error) => error,
+        };
+        assert_eq!(error.location, Some(location));
+        match error.kind {
+            RuntimeErrorKind::UnstructuredError { message } => {
+                assert_eq!(message, "Constraint is always false");
+            }
+            other => panic!("Expected an unstructured error, found {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,168 @@
+//! This file contains the final check made over the SSA IR before it is handed to ACIR
+//! generation.
+//!
+//! Every pass is expected to leave each function well formed, but a pass which forgets to
+//! terminate a block it creates would otherwise only be caught by a panic deep within ACIR
+//! generation, far from the pass at fault. Checking here instead reports the first function
+//! and block which is malformed, before any ACIR is generated from it.
+//!
+//! The parameters of main are also checked against those recorded when the program was
+//! created, since each is assigned the witness of a public input in order.
+use iter_extended::vecmap;
+
+use crate::{
+    errors::{RuntimeError, RuntimeErrorKind},
+    ssa_refactor::{
+        ir::{function::Function, instruction::TerminatorInstruction},
+        ssa_gen::Ssa,
+    },
+};
+
+impl Ssa {
+    /// Returns an error if any reachable block of any function has no terminator, if any
+    /// function has no reachable block which returns, or if the parameters of main were changed.
+    ///
+    /// This does not modify the SSA, so it is returned unchanged if every function is well formed.
+    pub(crate) fn finalize(self) -> Result<Ssa, RuntimeError> {
+        for function in self.functions.values() {
+            if let Some(message) = check_function(function) {
+                return Err(RuntimeErrorKind::Spanless(message).into());
+            }
+        }
+
+        let main = self.main();
+        let parameter_types = vecmap(main.parameters(), |p| main.dfg.type_of_value(*p));
+        if parameter_types != self.main_parameter_types {
+            let message = format!(
+                "ICE: The parameters of main were changed from ({}) to ({})",
+                vecmap(&self.main_parameter_types, ToString::to_string).join(", "),
+                vecmap(&parameter_types, ToString::to_string).join(", "),
+            );
+            return Err(RuntimeErrorKind::Spanless(message).into());
+        }
+        Ok(self)
+    }
+}
+
+/// Returns a message describing the first problem found within the given function, if any.
+fn check_function(function: &Function) -> Option<String> {
+    let mut blocks: Vec<_> = function.reachable_blocks().into_iter().collect();
+    blocks.sort();
+
+    let mut returns = false;
+    for block in blocks {
+        match function.dfg[block].terminator() {
+            Some(TerminatorInstruction::Return { .. }) => returns = true,
+            Some(_) => (),
+            None => {
+                return Some(format!(
+                    "ICE: Block {block} of function {} ({}) is not terminated",
+                    function.name(),
+                    function.id()
+                ))
+            }
+        }
+    }
+
+    (!returns)
+        .then(|| format!("ICE: Function {} ({}) never returns", function.name(), function.id()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        errors::RuntimeErrorKind,
+        ssa_refactor::{
+            ir::{function::RuntimeType, map::Id, types::Type},
+            ssa_builder::FunctionBuilder,
+        },
+    };
+
+    #[test]
+    fn unterminated_block_fails_finalization() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     jmp b1()
+        //   b1():
+        //     return v0
+        // }
+        // brillig fn helper f1 {
+        //   b0():
+        //     jmp b1()
+        //   b1():
+        // }
+        let main_id = Id::test_new(0);
+        let helper_id = Id::test_new(1);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+        builder.switch_to_block(b1);
+        builder.terminate_with_return(vec![v0]);
+
+        builder.new_brillig_function("helper".into(), helper_id);
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+
+        let error = match builder.finish().finalize() {
+            Ok(_) => panic!("Expected the unterminated block to be reported"),
+            Err(error) => error,
+        };
+        match error.kind {
+            RuntimeErrorKind::Spanless(message) => {
+                assert_eq!(message, "ICE: Block b1 of function helper (f1) is not terminated");
+            }
+            other => panic!("Expected a spanless error, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn function_without_a_return_fails_finalization() {
+        // fn main f0 {
+        //   b0():
+        //     jmp b0()
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let entry = builder.current_block();
+        builder.terminate_with_jmp(entry, vec![]);
+
+        let error = builder.finish().finalize().err().expect("Expected main to be reported");
+        match error.kind {
+            RuntimeErrorKind::Spanless(message) => {
+                assert_eq!(message, "ICE: Function main (f0) never returns");
+            }
+            other => panic!("Expected a spanless error, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn dropping_a_parameter_of_main_fails_finalization() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: u8):
+        //     return v1
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::unsigned(8));
+        builder.terminate_with_return(vec![v1]);
+        let mut ssa = builder.finish();
+
+        // Simulate a pass removing the unused parameter v0
+        let main = ssa.main_mut();
+        let entry = main.entry_block();
+        main.dfg[entry].set_parameters(vec![v1]);
+
+        let error = ssa.finalize().err().expect("Expected the dropped parameter to be reported");
+        match error.kind {
+            RuntimeErrorKind::Spanless(message) => {
+                assert_eq!(
+                    message,
+                    "ICE: The parameters of main were changed from (Field, u8) to (u8)"
+                );
+            }
+            other => panic!("Expected a spanless error, found {other:?}"),
+        }
+    }
+}
@@ -33,13 +33,13 @@
 //! When we are flattening a block that was reached via a jmpif with a non-constant condition c,
 //! the following transformations of certain instructions within the block are expected:
 //!
-//! 1. A constraint is multiplied by the condition and changes the constraint to
-//! an equality with c:
+//! 1. A constraint is changed to an implication from the condition, so that it always holds
+//! when c is false:
 //!
 //! constrain v0
 //! ============
-//! v1 = mul v0, c
-//! v2 = eq v1, c
+//! v1 = not c
+//! v2 = or v0, v1
 //! constrain v2
 //!
 //! 2. If we reach the end block of the branch created by the jmpif instruction, its block parameters
@@ -146,7 +146,7 @@ use crate::ssa_refactor::{
         dfg::InsertInstructionResult,
         function::Function,
         function_inserter::FunctionInserter,
-        instruction::{BinaryOp, Instruction, InstructionId, TerminatorInstruction},
+        instruction::{BinaryOp, Instruction, InstructionId, SideEffects, TerminatorInstruction},
         types::{CompositeType, Type},
         value::ValueId,
     },
@@ -639,7 +639,7 @@ impl<'f> Context<'f> {
     fn push_instruction(&mut self, id: InstructionId) {
         let instruction = self.inserter.map_instruction(id);
         let instruction = self.handle_instruction_side_effects(instruction);
-        let is_allocate = matches!(instruction, Instruction::Allocate);
+        let is_allocate = instruction.side_effects() == SideEffects::Allocates;
 
         let entry = self.inserter.function.entry_block();
         let results = self.inserter.push_instruction_value(instruction, id, entry);
@@ -652,28 +652,51 @@ impl<'f> Context<'f> {
     }
 
     /// If we are currently in a branch, we need to modify constrain instructions
-    /// to multiply them by the branch's condition (see optimization #1 in the module comment).
+    /// to only hold when the branch's condition is true (see optimization #1 in the module comment).
     fn handle_instruction_side_effects(&mut self, instruction: Instruction) -> Instruction {
-        if let Some((_, condition)) = self.conditions.last().copied() {
-            match instruction {
-                Instruction::Constrain(value) => {
-                    let mul = self.insert_instruction(Instruction::binary(
-                        BinaryOp::Mul,
-                        value,
-                        condition,
-                    ));
-                    let eq =
-                        self.insert_instruction(Instruction::binary(BinaryOp::Eq, mul, condition));
-                    Instruction::Constrain(eq)
-                }
-                Instruction::Store { address, value } => {
-                    self.remember_store(address, value);
-                    Instruction::Store { address, value }
+        let condition = match self.conditions.last() {
+            Some((_, condition)) => *condition,
+            None => return instruction,
+        };
+        match instruction.side_effects() {
+            SideEffects::Constrains => self.handle_constraint(instruction, condition),
+            SideEffects::WritesMemory => {
+                if let Instruction::Store { address, value } = &instruction {
+                    self.remember_store(*address, *value);
                 }
-                other => other,
+                instruction
             }
-        } else {
-            instruction
+            _ => instruction,
+        }
+    }
+
+    /// Rewrites the given constraint made within a branch to only hold when the branch's
+    /// condition is true.
+    fn handle_constraint(&mut self, instruction: Instruction, condition: ValueId) -> Instruction {
+        match instruction {
+            Instruction::Constrain(value) => {
+                let not_condition = self.insert_instruction(Instruction::Not(condition));
+                let implication = self.insert_instruction(Instruction::binary(
+                    BinaryOp::Or,
+                    value,
+                    not_condition,
+                ));
+                Instruction::Constrain(implication)
+            }
+            Instruction::ConstrainEqual(lhs, rhs) => {
+                // As with ConstrainNotEqual, the condition is applied to the equality
+                let is_equal = self.insert_instruction(Instruction::binary(BinaryOp::Eq, lhs, rhs));
+                self.handle_instruction_side_effects(Instruction::Constrain(is_equal))
+            }
+            Instruction::ConstrainNotEqual(lhs, rhs) => {
+                // The condition can only be applied to a boolean, so this falls back to
+                // constraining the negation of an equality
+                let is_equal = self.insert_instruction(Instruction::binary(BinaryOp::Eq, lhs, rhs));
+                let is_not_equal = self.insert_instruction(Instruction::Not(is_equal));
+                self.handle_instruction_side_effects(Instruction::Constrain(is_not_equal))
+            }
+            // Enabling side effects is already conditional on the branch's condition
+            other => other,
         }
     }
 }
@@ -772,7 +795,7 @@ mod test {
         builder.terminate_with_jmpif(v0, b1, b2);
 
         builder.switch_to_block(b1);
-        builder.insert_constrain(v1);
+        builder.insert_constrain(v1, None);
         builder.terminate_with_jmp(b2, vec![]);
 
         builder.switch_to_block(b2);
@@ -785,8 +808,8 @@ mod test {
         // fn main f0 {
         //   b0(v0: u1, v1: u1):
         //     enable_side_effects v0
-        //     v3 = mul v1, v0
-        //     v4 = eq v3, v0
+        //     v3 = not v0
+        //     v4 = or v1, v3
         //     constrain v4
         //     v5 = not v0
         //     enable_side_effects v5
@@ -794,7 +817,30 @@ mod test {
         //     return
         // }
         let ssa = ssa.flatten_cfg();
-        assert_eq!(ssa.main().reachable_blocks().len(), 1);
+        let main = ssa.main();
+        assert_eq!(main.reachable_blocks().len(), 1);
+
+        let instructions = main.dfg[main.entry_block()].instructions();
+        let constrained = match &main.dfg[instructions[3]] {
+            Instruction::Constrain(value) => *value,
+            other => panic!("Expected a constrain, found {other:?}"),
+        };
+        let implication = match &main.dfg[constrained] {
+            Value::Instruction { instruction, .. } => &main.dfg[*instruction],
+            other => panic!("Expected an instruction result, found {other:?}"),
+        };
+        match implication {
+            Instruction::Binary(binary) => {
+                assert_eq!(binary.operator, BinaryOp::Or);
+                assert_eq!(binary.lhs, v1);
+                assert!(matches!(
+                    &main.dfg[binary.rhs],
+                    Value::Instruction { instruction, .. }
+                        if main.dfg[*instruction] == Instruction::Not(v0)
+                ));
+            }
+            other => panic!("Expected an or, found {other:?}"),
+        }
     }
 
     #[test]
@@ -1234,7 +1280,7 @@ mod test {
         builder.terminate_with_jmp(b2, vec![]);
 
         builder.switch_to_block(b2);
-        builder.insert_constrain(v_false); // should not be removed
+        builder.insert_constrain(v_false, None); // should not be removed
         builder.terminate_with_return(vec![]);
 
         let ssa = builder.finish().flatten_cfg();
@@ -1315,7 +1361,7 @@ mod test {
         builder.switch_to_block(b3);
         let b_true = builder.numeric_constant(1_u128, Type::unsigned(1));
         let v12 = builder.insert_binary(v9, BinaryOp::Eq, b_true);
-        builder.insert_constrain(v12);
+        builder.insert_constrain(v12, None);
         builder.terminate_with_return(vec![]);
 
         let ssa = builder.finish().flatten_cfg();
@@ -1333,4 +1379,58 @@ mod test {
         }
         assert_eq!(constrain_count, 1);
     }
+
+    #[test]
+    fn merge_with_constant_condition_is_folded() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     jmpif u1 1 then: b1, else: b2
+        //   b1():
+        //     jmp b3(v0)
+        //   b2():
+        //     jmp b3(v1)
+        //   b3(v2: Field):
+        //     return v2
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+
+        let true_value = builder.numeric_constant(1u128, Type::bool());
+        builder.terminate_with_jmpif(true_value, b1, b2);
+
+        builder.switch_to_block(b1);
+        builder.terminate_with_jmp(b3, vec![v0]);
+
+        builder.switch_to_block(b2);
+        builder.terminate_with_jmp(b3, vec![v1]);
+
+        builder.switch_to_block(b3);
+        let v2 = builder.add_block_parameter(b3, Type::field());
+        builder.terminate_with_return(vec![v2]);
+
+        // The merged value `1 * v0 + 0 * v1` is simplified to v0 as it is built,
+        // so no multiplications or casts are left behind for the merge.
+        let ssa = builder.finish().flatten_cfg();
+        let main = ssa.main();
+        assert_eq!(main.reachable_blocks().len(), 1);
+
+        match main.dfg[main.entry_block()].unwrap_terminator() {
+            TerminatorInstruction::Return { return_values } => {
+                assert_eq!(return_values.len(), 1);
+                assert_eq!(main.dfg.resolve(return_values[0]), v0);
+            }
+            other => panic!("Expected a return, found {other:?}"),
+        }
+
+        let merges = count_instruction(main, |instruction| {
+            matches!(instruction, Instruction::Binary(..) | Instruction::Cast(..))
+        });
+        assert_eq!(merges, 0);
+    }
 }
@@ -0,0 +1,301 @@
+//! This file contains a pass which hoists allocations of constants out of loops and into the
+//! entry block of their function.
+//!
+//! Before unrolling, a constant array built within a loop body is allocated and initialized
+//! again on each iteration. If such an allocation is only ever stored to once, with a constant,
+//! and its address is otherwise only loaded from, then each iteration observes the same contents
+//! and a single allocation made in the entry block can be shared by every iteration.
+use std::collections::{HashMap, HashSet};
+
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId,
+        cfg::ControlFlowGraph,
+        dfg::DataFlowGraph,
+        function::Function,
+        instruction::{Instruction, InstructionId},
+        value::ValueId,
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Moves each allocation within a loop which is initialized once with a constant, along
+    /// with its initializing store, into the entry block of its function.
+    pub(crate) fn hoist_constant_allocations(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            hoist_constant_allocations(function);
+        }
+        self
+    }
+}
+
+fn hoist_constant_allocations(function: &mut Function) {
+    let blocks = function.reachable_blocks();
+    let loop_blocks = find_loop_blocks(function, &blocks);
+    if loop_blocks.is_empty() {
+        return;
+    }
+
+    let mut context = Context::default();
+    for block in &blocks {
+        context.analyze_block(&function.dfg, *block);
+    }
+
+    let mut loop_blocks: Vec<_> = loop_blocks.into_iter().collect();
+    loop_blocks.sort();
+
+    let entry = function.entry_block();
+    for block in loop_blocks {
+        let hoisted = context.constant_allocations(&function.dfg, block);
+        if hoisted.is_empty() {
+            continue;
+        }
+
+        function.dfg[block].instructions_mut().retain(|instruction| {
+            !hoisted.iter().any(|(allocate, store)| instruction == allocate || instruction == store)
+        });
+
+        for (allocate, store) in hoisted {
+            function.dfg[entry].insert_instruction(allocate);
+            function.dfg[entry].insert_instruction(store);
+        }
+    }
+}
+
+/// Returns each of the given blocks which is part of a loop, i.e. each block which can
+/// reach itself again by following its successors.
+fn find_loop_blocks(function: &Function, blocks: &HashSet<BasicBlockId>) -> HashSet<BasicBlockId> {
+    let cfg = ControlFlowGraph::with_function(function);
+
+    blocks
+        .iter()
+        .copied()
+        .filter(|block| {
+            let mut visited = HashSet::new();
+            let mut stack: Vec<_> = cfg.successors(*block).collect();
+
+            while let Some(successor) = stack.pop() {
+                if successor == *block {
+                    return true;
+                }
+                if visited.insert(successor) {
+                    stack.extend(cfg.successors(successor));
+                }
+            }
+            false
+        })
+        .collect()
+}
+
+/// An AllocId is the ValueId returned from an allocate instruction. E.g. v0 in v0 = allocate.
+type AllocId = ValueId;
+
+/// Per function context tracking how the address of each allocation is used.
+#[derive(Default)]
+struct Context {
+    /// Maps each allocation to every store using it as an address, along with the value stored.
+    stores: HashMap<AllocId, Vec<(InstructionId, ValueId)>>,
+
+    /// Every value used for anything other than the address of a load or store.
+    escaped_values: HashSet<ValueId>,
+}
+
+impl Context {
+    /// Records the stores and uses of any other values within the given block.
+    fn analyze_block(&mut self, dfg: &DataFlowGraph, block: BasicBlockId) {
+        for instruction_id in dfg[block].instructions() {
+            match &dfg[*instruction_id] {
+                // Allocations have no arguments and loads only use their address, so neither
+                // can cause a value to escape
+                Instruction::Allocate | Instruction::Load { .. } => (),
+                Instruction::Store { address, value } => {
+                    let address = dfg.resolve(*address);
+                    self.stores.entry(address).or_default().push((*instruction_id, *value));
+                    self.escaped_values.insert(dfg.resolve(*value));
+                }
+                other => other.for_each_value(|value| {
+                    self.escaped_values.insert(dfg.resolve(value));
+                }),
+            }
+        }
+
+        if let Some(terminator) = dfg[block].terminator() {
+            terminator.for_each_value(|value| {
+                self.escaped_values.insert(dfg.resolve(value));
+            });
+        }
+    }
+
+    /// Returns the allocate and store instructions of each allocation within the given block
+    /// which is only ever initialized by a single store of a constant in that same block.
+    fn constant_allocations(
+        &self,
+        dfg: &DataFlowGraph,
+        block: BasicBlockId,
+    ) -> Vec<(InstructionId, InstructionId)> {
+        let instructions = dfg[block].instructions();
+        let mut allocations = Vec::new();
+
+        for allocate in instructions {
+            if !matches!(dfg[*allocate], Instruction::Allocate) {
+                continue;
+            }
+
+            let address = dfg.instruction_results(*allocate)[0];
+            if self.escaped_values.contains(&address) {
+                continue;
+            }
+
+            if let Some([(store, value)]) = self.stores.get(&address).map(Vec::as_slice) {
+                if instructions.contains(store) && dfg.is_constant(*value) {
+                    allocations.push((*allocate, *store));
+                }
+            }
+        }
+
+        allocations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use im::vector;
+
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{BinaryOp, Instruction},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn constant_array_in_loop_is_hoisted() {
+        // fn main f0 {
+        //   b0():
+        //     jmp b1(Field 0)
+        //   b1(v0: Field):
+        //     v1 = lt v0, Field 3
+        //     jmpif v1 then: b2, else: b3
+        //   b2():
+        //     v2 = allocate
+        //     store [Field 1, Field 2] in v2
+        //     v3 = load v2
+        //     v4 = add v0, Field 1
+        //     jmp b1(v4)
+        //   b3():
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+
+        let zero = builder.field_constant(0u128);
+        builder.terminate_with_jmp(b1, vec![zero]);
+
+        builder.switch_to_block(b1);
+        let v0 = builder.add_block_parameter(b1, Type::field());
+        let three = builder.field_constant(3u128);
+        let v1 = builder.insert_binary(v0, BinaryOp::Lt, three);
+        builder.terminate_with_jmpif(v1, b2, b3);
+
+        builder.switch_to_block(b2);
+        let one = builder.field_constant(1u128);
+        let two = builder.field_constant(2u128);
+        let array_type = Rc::new(vec![Type::field()]);
+        let array = builder.array_constant(vector![one, two], array_type.clone());
+        let v2 = builder.insert_allocate();
+        builder.insert_store(v2, array);
+        builder.insert_load(v2, Type::Array(array_type, 2));
+        let v4 = builder.insert_binary(v0, BinaryOp::Add, one);
+        builder.terminate_with_jmp(b1, vec![v4]);
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![]);
+
+        // Expected output:
+        // fn main f0 {
+        //   b0():
+        //     v2 = allocate
+        //     store [Field 1, Field 2] in v2
+        //     jmp b1(Field 0)
+        //   ...
+        //   b2():
+        //     v3 = load v2
+        //     v4 = add v0, Field 1
+        //     jmp b1(v4)
+        //   ...
+        // }
+        let ssa = builder.finish().hoist_constant_allocations();
+        let main = ssa.main();
+
+        let entry_instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(entry_instructions.len(), 2);
+        assert!(matches!(main.dfg[entry_instructions[0]], Instruction::Allocate));
+        assert_eq!(main.dfg.instruction_results(entry_instructions[0]), &[v2]);
+        assert!(matches!(main.dfg[entry_instructions[1]], Instruction::Store { .. }));
+
+        let body_instructions = main.dfg[b2].instructions();
+        assert_eq!(body_instructions.len(), 2);
+        assert!(matches!(main.dfg[body_instructions[0]], Instruction::Load { .. }));
+    }
+
+    #[test]
+    fn allocation_of_loop_variable_is_not_hoisted() {
+        // fn main f0 {
+        //   b0():
+        //     jmp b1(Field 0)
+        //   b1(v0: Field):
+        //     v1 = lt v0, Field 3
+        //     jmpif v1 then: b2, else: b3
+        //   b2():
+        //     v2 = allocate
+        //     store v0 in v2
+        //     v3 = load v2
+        //     v4 = add v3, Field 1
+        //     jmp b1(v4)
+        //   b3():
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+
+        let zero = builder.field_constant(0u128);
+        builder.terminate_with_jmp(b1, vec![zero]);
+
+        builder.switch_to_block(b1);
+        let v0 = builder.add_block_parameter(b1, Type::field());
+        let three = builder.field_constant(3u128);
+        let v1 = builder.insert_binary(v0, BinaryOp::Lt, three);
+        builder.terminate_with_jmpif(v1, b2, b3);
+
+        builder.switch_to_block(b2);
+        let v2 = builder.insert_allocate();
+        builder.insert_store(v2, v0);
+        let v3 = builder.insert_load(v2, Type::field());
+        let one = builder.field_constant(1u128);
+        let v4 = builder.insert_binary(v3, BinaryOp::Add, one);
+        builder.terminate_with_jmp(b1, vec![v4]);
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish().hoist_constant_allocations();
+        let main = ssa.main();
+
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+        assert_eq!(main.dfg[b2].instructions().len(), 4);
+    }
+}
@@ -0,0 +1,200 @@
+//! This file contains a pass which hoists instructions common to both arms of a branch into
+//! the block that branches.
+//!
+//! There is no match expression in the language, so each branch in the IR is a jmpif with a
+//! then and an else arm. When both arms begin with the same pure computation, such as the same
+//! cast of a value defined before the branch, that computation is performed on every path
+//! through the branch and can be done once before it instead. Besides saving an instruction in
+//! unconstrained code, this leaves flattening one fewer instruction to duplicate under each
+//! side of the condition.
+//!
+//! An arm is only considered if the branching block is its sole predecessor, so anything the
+//! arm's instructions refer to is also available in the branching block.
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId,
+        function::Function,
+        instruction::{InstructionId, TerminatorInstruction},
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Moves each leading instruction shared by both arms of a jmpif to the end of the
+    /// branching block, before its terminator.
+    pub(crate) fn hoist_common_branch_prefixes(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            hoist_common_branch_prefixes(function);
+        }
+        self
+    }
+}
+
+fn hoist_common_branch_prefixes(function: &mut Function) {
+    let mut branches = Vec::new();
+    for block in function.reachable_blocks() {
+        if let Some(TerminatorInstruction::JmpIf { then_destination, else_destination, .. }) =
+            function.dfg[block].terminator()
+        {
+            let (then_arm, else_arm) = (*then_destination, *else_destination);
+            if then_arm != else_arm
+                && function.predecessors(then_arm).len() == 1
+                && function.predecessors(else_arm).len() == 1
+            {
+                branches.push((block, then_arm, else_arm));
+            }
+        }
+    }
+
+    for (block, then_arm, else_arm) in branches {
+        while let Some((then_instruction, else_instruction)) =
+            common_first_instruction(function, then_arm, else_arm)
+        {
+            function.dfg[then_arm].instructions_mut().remove(0);
+            function.dfg[else_arm].instructions_mut().remove(0);
+            function.dfg[block].instructions_mut().push(then_instruction);
+
+            let then_results = function.dfg.instruction_results(then_instruction).to_vec();
+            let else_results = function.dfg.instruction_results(else_instruction).to_vec();
+            for (else_result, then_result) in else_results.into_iter().zip(then_results) {
+                function.dfg.set_value_from_id(else_result, then_result);
+            }
+        }
+    }
+}
+
+/// Returns the first instruction of each arm if both are the same pure instruction once
+/// their values are resolved.
+fn common_first_instruction(
+    function: &Function,
+    then_arm: BasicBlockId,
+    else_arm: BasicBlockId,
+) -> Option<(InstructionId, InstructionId)> {
+    let dfg = &function.dfg;
+    let then_instruction = *dfg[then_arm].instructions().first()?;
+    let else_instruction = *dfg[else_arm].instructions().first()?;
+
+    let resolve = |value| dfg.resolve(value);
+    let instruction = dfg[then_instruction].map_values(resolve);
+    let is_common = instruction.side_effects().can_be_deduplicated()
+        && instruction == dfg[else_instruction].map_values(resolve);
+    is_common.then_some((then_instruction, else_instruction))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{BinaryOp, Instruction, TerminatorInstruction},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn common_cast_is_hoisted() {
+        // fn main f0 {
+        //   b0(v0: u8, v1: u1):
+        //     jmpif v1 then: b1, else: b2
+        //   b1():
+        //     v2 = cast v0 as Field
+        //     v3 = add v2, Field 1
+        //     jmp b3(v3)
+        //   b2():
+        //     v4 = cast v0 as Field
+        //     v5 = mul v4, Field 2
+        //     jmp b3(v5)
+        //   b3(v6: Field):
+        //     return v6
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(8));
+        let v1 = builder.add_parameter(Type::bool());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+        let v6 = builder.add_block_parameter(b3, Type::field());
+        builder.terminate_with_jmpif(v1, b1, b2);
+
+        builder.switch_to_block(b1);
+        let v2 = builder.insert_cast(v0, Type::field());
+        let one = builder.field_constant(1u128);
+        let v3 = builder.insert_binary(v2, BinaryOp::Add, one);
+        builder.terminate_with_jmp(b3, vec![v3]);
+
+        builder.switch_to_block(b2);
+        let v4 = builder.insert_cast(v0, Type::field());
+        let two = builder.field_constant(2u128);
+        let v5 = builder.insert_binary(v4, BinaryOp::Mul, two);
+        builder.terminate_with_jmp(b3, vec![v5]);
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![v6]);
+
+        // After hoisting:
+        // fn main f0 {
+        //   b0(v0: u8, v1: u1):
+        //     v2 = cast v0 as Field
+        //     jmpif v1 then: b1, else: b2
+        //   b1():
+        //     v3 = add v2, Field 1
+        //     jmp b3(v3)
+        //   b2():
+        //     v5 = mul v2, Field 2
+        //     jmp b3(v5)
+        //   b3(v6: Field):
+        //     return v6
+        // }
+        let ssa = builder.finish().hoist_common_branch_prefixes();
+        let main = ssa.main();
+        let dfg = &main.dfg;
+
+        let entry_instructions = dfg[main.entry_block()].instructions();
+        assert_eq!(entry_instructions.len(), 1);
+        assert_eq!(dfg[entry_instructions[0]], Instruction::Cast(v0, Type::field()));
+        assert!(matches!(
+            dfg[main.entry_block()].terminator(),
+            Some(TerminatorInstruction::JmpIf { .. })
+        ));
+
+        assert_eq!(dfg[b1].instructions().len(), 1);
+        assert_eq!(dfg[b2].instructions().len(), 1);
+        assert_eq!(dfg.resolve(v4), dfg.resolve(v2));
+    }
+
+    #[test]
+    fn different_first_instructions_are_not_hoisted() {
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(8));
+        let v1 = builder.add_parameter(Type::bool());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+        let v4 = builder.add_block_parameter(b3, Type::field());
+        builder.terminate_with_jmpif(v1, b1, b2);
+
+        builder.switch_to_block(b1);
+        let v2 = builder.insert_cast(v0, Type::field());
+        builder.terminate_with_jmp(b3, vec![v2]);
+
+        builder.switch_to_block(b2);
+        let v3 = builder.insert_cast(v0, Type::unsigned(16));
+        let v3 = builder.insert_cast(v3, Type::field());
+        builder.terminate_with_jmp(b3, vec![v3]);
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![v4]);
+
+        let ssa = builder.finish().hoist_common_branch_prefixes();
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+        assert_eq!(main.dfg[b1].instructions().len(), 1);
+        assert_eq!(main.dfg[b2].instructions().len(), 2);
+    }
+}
@@ -0,0 +1,249 @@
+//! This file contains an analysis over the SSA IR to find likely-infinite loops
+//! within unconstrained functions.
+//!
+//! The analysis is intentionally conservative: a loop is only reported if none of
+//! the blocks within it can ever reach a `return`. Any loop with a `break` or other
+//! conditional exit has a path to a return and is therefore never reported.
+//!
+//! Constrained functions are skipped entirely since any loops within them must be
+//! unrolled, which will fail on its own if the loop has no exit.
+use std::collections::HashSet;
+
+use noirc_errors::Location;
+
+use crate::errors::RuntimeWarning;
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId,
+        function::{Function, FunctionId, RuntimeType},
+        instruction::TerminatorInstruction,
+    },
+    ssa_gen::Ssa,
+};
+
+/// A loop with no reachable exit edge, found within an unconstrained function.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct InfiniteLoopWarning {
+    pub(crate) function: FunctionId,
+    pub(crate) function_name: String,
+
+    /// The block through which control flow first enters the loop.
+    pub(crate) loop_entry: BasicBlockId,
+
+    /// The location of the first instruction within the loop entry which has one, if any.
+    pub(crate) location: Option<Location>,
+}
+
+impl std::fmt::Display for InfiniteLoopWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "unconstrained function {} ({}) contains a loop at {} with no reachable exit",
+            self.function_name, self.function, self.loop_entry
+        )
+    }
+}
+
+impl From<InfiniteLoopWarning> for RuntimeWarning {
+    fn from(warning: InfiniteLoopWarning) -> Self {
+        RuntimeWarning { location: warning.location, message: warning.to_string() }
+    }
+}
+
+impl Ssa {
+    /// Find each loop within an unconstrained function that has no reachable exit edge.
+    ///
+    /// This does not modify the SSA, it only reports loops which are likely to be bugs.
+    pub(crate) fn find_infinite_loops(&self) -> Vec<InfiniteLoopWarning> {
+        self.functions
+            .values()
+            .filter(|function| function.runtime() == RuntimeType::Brillig)
+            .flat_map(find_infinite_loops_in_function)
+            .collect()
+    }
+}
+
+/// Find the entry of each region of the function's CFG from which no return is reachable.
+///
+/// Since every block in such a region can never reach a return, each region must contain
+/// at least one loop with no exit.
+fn find_infinite_loops_in_function(function: &Function) -> Vec<InfiniteLoopWarning> {
+    let reachable_blocks = function.reachable_blocks();
+    let returning_blocks = reachable_blocks
+        .iter()
+        .copied()
+        .filter(|block| {
+            matches!(function.dfg[*block].terminator(), Some(TerminatorInstruction::Return { .. }))
+        })
+        .collect();
+
+    let can_return = blocks_reaching(function, returning_blocks);
+
+    let mut loop_entries: Vec<_> = reachable_blocks
+        .iter()
+        .copied()
+        .filter(|block| !can_return.contains(block))
+        .filter(|block| {
+            *block == function.entry_block()
+                || function
+                    .predecessors(*block)
+                    .iter()
+                    .any(|predecessor| can_return.contains(predecessor))
+        })
+        .collect();
+
+    // Sort the entries so the order of any warnings is deterministic
+    loop_entries.sort();
+
+    loop_entries
+        .into_iter()
+        .map(|loop_entry| InfiniteLoopWarning {
+            function: function.id(),
+            function_name: function.name().to_owned(),
+            loop_entry,
+            location: function.dfg[loop_entry]
+                .instructions()
+                .iter()
+                .find_map(|instruction| function.dfg.get_location(*instruction)),
+        })
+        .collect()
+}
+
+/// Return the set of blocks from which any of the given target blocks are reachable,
+/// including the target blocks themselves.
+fn blocks_reaching(function: &Function, targets: Vec<BasicBlockId>) -> HashSet<BasicBlockId> {
+    let mut visited = HashSet::new();
+    let mut stack = targets;
+
+    while let Some(block) = stack.pop() {
+        if visited.insert(block) {
+            let predecessors = function.predecessors(block);
+            stack.extend(predecessors.into_iter().filter(|block| !visited.contains(block)));
+        }
+    }
+
+    visited
+}
+
+#[cfg(test)]
+mod tests {
+    use fm::FileId;
+    use iter_extended::vecmap;
+    use noirc_errors::{CustomDiagnostic as Diagnostic, DiagnosticKind, Location, Span};
+
+    use crate::{
+        errors::RuntimeWarning,
+        ssa_refactor::{
+            ir::{function::RuntimeType, map::Id, types::Type},
+            ssa_builder::FunctionBuilder,
+        },
+    };
+
+    #[test]
+    fn loop_without_exit_is_flagged() {
+        // unconstrained fn main {
+        //   b0():
+        //     jmp b1()
+        //   b1():
+        //     jmp b1()
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Brillig);
+
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+
+        builder.switch_to_block(b1);
+        builder.terminate_with_jmp(b1, vec![]);
+
+        let ssa = builder.finish();
+        let mut warnings = ssa.find_infinite_loops();
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].function, main_id);
+        assert_eq!(warnings[0].loop_entry, b1);
+
+        // Without a location the warning is reported without a label
+        let diagnostic = Diagnostic::from(RuntimeWarning::from(warnings.remove(0)));
+        assert!(diagnostic.message.contains("with no reachable exit"));
+        assert!(diagnostic.secondaries.is_empty());
+        assert_eq!(diagnostic.kind, DiagnosticKind::Warning);
+    }
+
+    #[test]
+    fn warning_is_reported_at_the_loop_location() {
+        // unconstrained fn main {
+        //   b0(v0: u1):
+        //     jmp b1()
+        //   b1():
+        //     constrain v0
+        //     jmp b1()
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Brillig);
+        let v0 = builder.add_parameter(Type::bool());
+
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+
+        builder.switch_to_block(b1);
+        let location = Location::new(Span::new(4..12), FileId::dummy());
+        builder.insert_constrain(v0, Some(location));
+        builder.terminate_with_jmp(b1, vec![]);
+
+        let mut warnings = builder.finish().find_infinite_loops();
+        assert_eq!(warnings.len(), 1);
+
+        let warning = RuntimeWarning::from(warnings.remove(0));
+        assert_eq!(warning.location, Some(location));
+        assert!(warning.message.contains("with no reachable exit"));
+
+        let message = warning.message.clone();
+        let diagnostic = Diagnostic::from(warning);
+        assert_eq!(diagnostic.message, message);
+        assert_eq!(vecmap(&diagnostic.secondaries, |label| label.span), vec![location.span]);
+    }
+
+    #[test]
+    fn loop_with_break_is_not_flagged() {
+        // unconstrained fn main {
+        //   b0(v0: u1):
+        //     jmp b1()
+        //   b1():
+        //     jmpif v0, then: b2, else: b1
+        //   b2():
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Brillig);
+        let v0 = builder.add_parameter(Type::bool());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+
+        builder.switch_to_block(b1);
+        builder.terminate_with_jmpif(v0, b2, b1);
+
+        builder.switch_to_block(b2);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish();
+        assert!(ssa.find_infinite_loops().is_empty());
+    }
+
+    #[test]
+    fn constrained_functions_are_not_checked() {
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+
+        builder.switch_to_block(b1);
+        builder.terminate_with_jmp(b1, vec![]);
+
+        let ssa = builder.finish();
+        assert!(ssa.find_infinite_loops().is_empty());
+    }
+}
@@ -171,6 +171,7 @@ impl InlineContext {
             ssa.functions.insert(new_main.id(), new_main);
             ssa
         } else {
+            new_ssa.main_parameter_types = ssa.main_parameter_types;
             new_ssa
         }
     }
@@ -209,8 +210,11 @@ impl<'function> PerFunctionContext<'function> {
             value @ Value::Param { .. } => {
                 unreachable!("All Value::Params should already be known from previous calls to translate_block. Unknown value {id} = {value:?}")
             }
+            // Constants are copied directly rather than through the builder's range check, since
+            // folded arithmetic may produce constants outside their type's range which are only
+            // reduced by a later truncate.
             Value::NumericConstant { constant, typ } => {
-                self.context.builder.numeric_constant(*constant, typ.clone())
+                self.context.builder.current_function.dfg.make_constant(*constant, typ.clone())
             }
             Value::Function(function) => self.context.builder.import_function(*function),
             Value::Intrinsic(intrinsic) => self.context.builder.import_intrinsic_id(*intrinsic),
@@ -350,7 +354,9 @@ impl<'function> PerFunctionContext<'function> {
                         RuntimeType::Acir => self.inline_function(ssa, *id, function, arguments),
                         RuntimeType::Brillig => {
                             self.context.failed_to_inline_a_call = true;
-                            self.push_instruction(*id);
+                            let hint =
+                                Instruction::Hint { func: *func, arguments: arguments.clone() };
+                            self.push_instruction_as(*id, &hint);
                         }
                     },
                     None => self.push_instruction(*id),
@@ -378,7 +384,17 @@ impl<'function> PerFunctionContext<'function> {
     /// Push the given instruction from the source_function into the current block of the
     /// function being inlined into.
     fn push_instruction(&mut self, id: InstructionId) {
-        let instruction = self.source_function.dfg[id].map_values(|id| self.translate_value(id));
+        let source_function = self.source_function;
+        self.push_instruction_as(id, &source_function.dfg[id]);
+    }
+
+    /// Push the given instruction into the current block of the function being inlined into
+    /// in place of the instruction with the given id from the source_function.
+    ///
+    /// This is used to replace calls to unconstrained functions, which cannot be inlined into
+    /// a constrained function, with hints.
+    fn push_instruction_as(&mut self, id: InstructionId, instruction: &Instruction) {
+        let instruction = instruction.map_values(|id| self.translate_value(id));
         let results = self.source_function.dfg.instruction_results(id);
         let results = vecmap(results, |id| self.source_function.dfg.resolve(*id));
 
@@ -386,7 +402,12 @@ impl<'function> PerFunctionContext<'function> {
             .requires_ctrl_typevars()
             .then(|| vecmap(&results, |result| self.source_function.dfg.type_of_value(*result)));
 
-        let new_results = self.context.builder.insert_instruction(instruction, ctrl_typevars);
+        let location = self.source_function.dfg.get_location(id);
+        let new_results = self.context.builder.insert_instruction_with_location(
+            instruction,
+            ctrl_typevars,
+            location,
+        );
         Self::insert_new_instruction_results(&mut self.values, &results, new_results);
     }
 
@@ -477,9 +498,10 @@ mod test {
         ir::{
             basic_block::BasicBlockId,
             function::RuntimeType,
-            instruction::{BinaryOp, Intrinsic, TerminatorInstruction},
+            instruction::{BinaryOp, Instruction, Intrinsic, TerminatorInstruction},
             map::Id,
             types::Type,
+            value::Value,
         },
         ssa_builder::FunctionBuilder,
     };
@@ -750,4 +772,69 @@ mod test {
         let main = ssa.main();
         assert_eq!(main.reachable_blocks().len(), 4);
     }
+
+    #[test]
+    fn unconstrained_call_becomes_hint() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     v1 = call f1(v0)
+        //     v2 = mul v1, v1
+        //     v3 = eq v2, v0
+        //     constrain v3
+        //     return v1
+        // }
+        // unconstrained fn sqrt f1 {
+        //   b0(v0: Field):
+        //     return v0
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let sqrt_id = Id::test_new(1);
+        let sqrt = builder.import_function(sqrt_id);
+        let v1 = builder.insert_call(sqrt, vec![v0], vec![Type::field()])[0];
+        let v2 = builder.insert_binary(v1, BinaryOp::Mul, v1);
+        let v3 = builder.insert_binary(v2, BinaryOp::Eq, v0);
+        builder.insert_constrain(v3, None);
+        builder.terminate_with_return(vec![v1]);
+
+        builder.new_brillig_function("sqrt".into(), sqrt_id);
+        let sqrt_v0 = builder.add_parameter(Type::field());
+        builder.terminate_with_return(vec![sqrt_v0]);
+
+        // Expected result:
+        // fn main f2 {
+        //   b0(v0: Field):
+        //     v2 = hint f1(v0)
+        //     v3 = mul v2, v2
+        //     v4 = eq v3, v0
+        //     constrain v4
+        //     return v2
+        // }
+        // The original main and sqrt are kept since sqrt could not be inlined
+        let ssa = builder.finish().inline_functions();
+        assert_eq!(ssa.functions.len(), 3);
+
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 4);
+
+        let hint_result = match &main.dfg[instructions[0]] {
+            Instruction::Hint { func, arguments } => {
+                assert!(matches!(main.dfg[*func], Value::Function(id) if id == sqrt_id));
+                assert_eq!(arguments, main.parameters());
+                main.dfg.instruction_results(instructions[0])[0]
+            }
+            other => panic!("Expected a hint, found {other:?}"),
+        };
+
+        match &main.dfg[instructions[1]] {
+            Instruction::Binary(binary) => {
+                assert_eq!(binary.lhs, hint_result);
+                assert_eq!(binary.rhs, hint_result);
+            }
+            other => panic!("Expected a multiplication, found {other:?}"),
+        }
+        assert!(matches!(main.dfg[instructions[3]], Instruction::Constrain(_)));
+    }
 }
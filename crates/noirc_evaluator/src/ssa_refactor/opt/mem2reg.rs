@@ -9,7 +9,7 @@ use crate::ssa_refactor::{
     ir::{
         basic_block::BasicBlockId,
         dfg::DataFlowGraph,
-        instruction::{Instruction, InstructionId, TerminatorInstruction},
+        instruction::{Instruction, InstructionId},
         value::{Value, ValueId},
     },
     ssa_gen::Ssa,
@@ -18,7 +18,8 @@ use crate::ssa_refactor::{
 impl Ssa {
     /// Attempts to remove any load instructions that recover values that are already available in
     /// scope, and attempts to remove store that are subsequently redundant, as long as they are
-    /// not stores on memory that will be passed into a function call or returned.
+    /// not stores on memory whose address escapes, such as by being passed into a function call
+    /// or another block, stored, or returned.
     pub(crate) fn mem2reg(mut self) -> Ssa {
         for function in self.functions.values_mut() {
             let mut all_protected_allocations = HashSet::new();
@@ -72,6 +73,9 @@ impl PerBlockContext {
                 Instruction::Store { address, value } => {
                     self.last_stores.insert(*address, *value);
                     self.store_ids.push(*instruction_id);
+                    if Self::value_is_from_allocation(*value, dfg) {
+                        protected_allocations.insert(*value);
+                    }
                 }
                 Instruction::Load { address } => {
                     if let Some(last_value) = self.last_stores.get(address) {
@@ -80,27 +84,23 @@ impl PerBlockContext {
                         protected_allocations.insert(*address);
                     }
                 }
-                Instruction::Call { arguments, .. } => {
-                    for arg in arguments {
-                        if Self::value_is_from_allocation(*arg, dfg) {
-                            protected_allocations.insert(*arg);
+                other => {
+                    // Any other use of an allocation, such as passing it to a call, lets it escape
+                    other.for_each_value(|value| {
+                        if Self::value_is_from_allocation(value, dfg) {
+                            protected_allocations.insert(value);
                         }
-                    }
-                }
-                _ => {
-                    // Nothing to do
+                    });
                 }
             }
         }
 
-        // Identify any arrays that are returned from this function
-        if let TerminatorInstruction::Return { return_values } = block.unwrap_terminator() {
-            for value in return_values {
-                if Self::value_is_from_allocation(*value, dfg) {
-                    protected_allocations.insert(*value);
-                }
+        // Identify any allocations that are returned from this function or passed to another block
+        block.unwrap_terminator().for_each_value(|value| {
+            if Self::value_is_from_allocation(value, dfg) {
+                protected_allocations.insert(value);
             }
-        }
+        });
 
         // Substitute load result values
         for (instruction_id, new_value) in &loads_to_substitute {
@@ -166,6 +166,8 @@ mod tests {
     use acvm::FieldElement;
     use im::vector;
 
+    use noirc_frontend::monomorphization::ast;
+
     use crate::ssa_refactor::{
         ir::{
             basic_block::BasicBlockId,
@@ -176,6 +178,7 @@ mod tests {
             types::Type,
         },
         ssa_builder::FunctionBuilder,
+        ssa_gen::test_utils::{field, function, generate_functions, mutable_ident},
     };
 
     #[test]
@@ -287,6 +290,75 @@ mod tests {
         assert_eq!(func.dfg[*ret_val_id], func.dfg[v0]);
     }
 
+    #[test]
+    fn allocation_passed_to_another_block_is_kept() {
+        // fn func {
+        //   b0():
+        //     v0 = allocate
+        //     store Field 1 in v0
+        //     jmp b1(v0)
+        //   b1(v1: reference):
+        //     v2 = load v1
+        //     return v2
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.insert_allocate();
+        let one = builder.field_constant(FieldElement::one());
+        builder.insert_store(v0, one);
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![v0]);
+
+        builder.switch_to_block(b1);
+        let v1 = builder.add_block_parameter(b1, Type::Reference);
+        let v2 = builder.insert_load(v1, Type::field());
+        builder.terminate_with_return(vec![v2]);
+
+        // The load through the block parameter still needs the store
+        let ssa = builder.finish().mem2reg();
+        let func = ssa.main();
+        assert_eq!(count_stores(func.entry_block(), &func.dfg), 1);
+        assert_eq!(count_loads(b1, &func.dfg), 1);
+    }
+
+    #[test]
+    fn reassigned_mutable_variable_is_a_constant() {
+        // fn main() -> Field {
+        //     let mut x = 1;
+        //     x = 2;
+        //     x
+        // }
+        let x = mutable_ident(ast::LocalId(0), "x", ast::Type::Field);
+        let body = ast::Expression::Block(vec![
+            ast::Expression::Let(ast::Let {
+                id: ast::LocalId(0),
+                mutable: true,
+                name: "x".into(),
+                expression: Box::new(field(1)),
+            }),
+            ast::Expression::Semi(Box::new(ast::Expression::Assign(ast::Assign {
+                lvalue: ast::LValue::Ident(x.clone()),
+                expression: Box::new(field(2)),
+            }))),
+            ast::Expression::Ident(x),
+        ]);
+        let main = function(0, "main", Vec::new(), body, ast::Type::Field);
+        let ssa = generate_functions(vec![main]).mem2reg().dead_instruction_elimination();
+
+        // The allocation, both stores, and the load are all removed
+        let main = ssa.main();
+        let block = &main.dfg[main.entry_block()];
+        assert!(block.instructions().is_empty());
+        match block.terminator() {
+            Some(TerminatorInstruction::Return { return_values }) => {
+                assert_eq!(return_values.len(), 1);
+                let value = main.dfg.get_numeric_constant(return_values[0]);
+                assert_eq!(value, Some(FieldElement::from(2u128)));
+            }
+            other => panic!("Expected a return, found {other:?}"),
+        }
+    }
+
     fn count_stores(block: BasicBlockId, dfg: &DataFlowGraph) -> usize {
         dfg[block]
             .instructions()
@@ -3,10 +3,31 @@
 //! Each pass is generally expected to mutate the SSA IR into a gradually
 //! simpler form until the IR only has a single function remaining with 1 block within it.
 //! Generally, these passes are also expected to minimize the final amount of instructions.
+mod booleanity_constraints;
+mod combine_truncations;
+mod constant_black_box;
 mod constant_folding;
+mod dead_stores;
+mod deduplicate_blocks;
+mod deduplicate_constant_arrays;
 mod die;
+#[cfg(test)]
+mod differential_tests;
+mod false_constraints;
+mod finalize;
 mod flatten_cfg;
+mod hoist_allocations;
+mod hoist_common_prefix;
+mod infinite_loops;
 mod inlining;
 mod mem2reg;
+mod redundant_constraints;
 mod simplify_cfg;
+mod specialize_constant_arguments;
+mod strength_reduction;
+mod tail_recursion;
 mod unrolling;
+mod unused_block_parameters;
+mod vector_operations;
+
+pub use constant_black_box::BlackBoxHost;
@@ -0,0 +1,235 @@
+//! This file contains the pass removing redundant constraints from the SSA IR.
+//!
+//! Constraining the same boolean twice is common after inlining, when several inlined
+//! functions assert the same condition. Once a value has been constrained, any later
+//! constrain of that same value in a block dominated by the first is redundant, since
+//! the first constraint is always executed before it. Equality constraints between the
+//! same two values are treated the same way, whichever order the values are in.
+//!
+//! Constraints are only considered identical if they were made under the same side
+//! effects condition, since a constraint made while side effects are disabled is
+//! not enforced.
+use std::collections::{HashMap, HashSet};
+
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId,
+        dom::DominatorTree,
+        function::Function,
+        instruction::{Instruction, InstructionId, SideEffects},
+        value::ValueId,
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Removes each constrain instruction whose value was already constrained, and each
+    /// equality constraint between two values already constrained to be equal, earlier in
+    /// dominator order.
+    pub(crate) fn remove_redundant_constraints(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            remove_redundant_constraints(function);
+        }
+        self
+    }
+}
+
+/// The side effects condition and the constrained value of a constrain instruction.
+/// A condition of None means side effects have not been disabled.
+type ConstraintKey = (Option<ValueId>, ValueId);
+
+/// The side effects condition and the two values of an equality constraint, ordered so that
+/// `a == b` and `b == a` share a key.
+type EqualityKey = (Option<ValueId>, ValueId, ValueId);
+
+/// The constraints known to hold at the end of a block.
+#[derive(Clone, Default)]
+struct KnownConstraints {
+    side_effects_condition: Option<ValueId>,
+    constrained: HashSet<ConstraintKey>,
+    equalities: HashSet<EqualityKey>,
+}
+
+/// Visits the blocks of the function's dominator tree depth-first, starting each block
+/// with the constraints known at the end of its immediate dominator.
+fn remove_redundant_constraints(function: &mut Function) {
+    let dominator_tree = DominatorTree::with_function(function);
+
+    let mut children: HashMap<BasicBlockId, Vec<BasicBlockId>> = HashMap::new();
+    for block in function.reachable_blocks() {
+        if let Some(dominator) = dominator_tree.immediate_dominator(block) {
+            children.entry(dominator).or_default().push(block);
+        }
+    }
+
+    let mut stack = vec![(function.entry_block(), KnownConstraints::default())];
+    while let Some((block, mut known)) = stack.pop() {
+        let redundant = find_redundant_constraints(function, block, &mut known);
+        if !redundant.is_empty() {
+            function.dfg[block].instructions_mut().retain(|id| !redundant.contains(id));
+        }
+
+        for child in children.get(&block).into_iter().flatten() {
+            stack.push((*child, known.clone()));
+        }
+    }
+}
+
+/// Returns each constrain instruction in the given block which is already known to hold,
+/// adding the constraints of the block to `known`.
+fn find_redundant_constraints(
+    function: &Function,
+    block: BasicBlockId,
+    known: &mut KnownConstraints,
+) -> HashSet<InstructionId> {
+    let dfg = &function.dfg;
+    let mut redundant = HashSet::new();
+
+    for instruction in dfg[block].instructions() {
+        if dfg[*instruction].side_effects() != SideEffects::Constrains {
+            continue;
+        }
+
+        match &dfg[*instruction] {
+            Instruction::Constrain(value) => {
+                let key = (known.side_effects_condition, dfg.resolve(*value));
+                if !known.constrained.insert(key) {
+                    redundant.insert(*instruction);
+                }
+            }
+            Instruction::ConstrainEqual(lhs, rhs) => {
+                let (lhs, rhs) = (dfg.resolve(*lhs), dfg.resolve(*rhs));
+                let key = (known.side_effects_condition, lhs.min(rhs), lhs.max(rhs));
+                if !known.equalities.insert(key) {
+                    redundant.insert(*instruction);
+                }
+            }
+            Instruction::EnableSideEffects { condition } => {
+                known.side_effects_condition = Some(dfg.resolve(*condition));
+            }
+            _ => (),
+        }
+    }
+    redundant
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::{Function, RuntimeType},
+            instruction::{BinaryOp, Instruction},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    fn count_constraints(function: &Function) -> usize {
+        function
+            .reachable_blocks()
+            .into_iter()
+            .flat_map(|block| function.dfg[block].instructions())
+            .filter(|id| {
+                matches!(
+                    function.dfg[**id],
+                    Instruction::Constrain(_) | Instruction::ConstrainEqual(..)
+                )
+            })
+            .count()
+    }
+
+    #[test]
+    fn duplicate_constraint_is_removed() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     v2 = eq v0, v1
+        //     constrain v2
+        //     jmp b1()
+        //   b1():
+        //     constrain v2
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let v2 = builder.insert_binary(v0, BinaryOp::Eq, v1);
+        builder.insert_constrain(v2, None);
+
+        let b1 = builder.insert_block();
+        builder.terminate_with_jmp(b1, vec![]);
+        builder.switch_to_block(b1);
+        builder.insert_constrain(v2, None);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish();
+        assert_eq!(count_constraints(ssa.main()), 2);
+
+        let ssa = ssa.remove_redundant_constraints();
+        let main = ssa.main();
+        assert_eq!(count_constraints(main), 1);
+        assert_eq!(main.dfg[main.entry_block()].instructions().len(), 2);
+    }
+
+    #[test]
+    fn duplicate_equality_constraint_is_removed() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     constrain v0 == v1
+        //     constrain v1 == v0
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        builder.insert_constrain_eq(v0, v1, None);
+        builder.insert_constrain_eq(v1, v0, None);
+        builder.terminate_with_return(vec![]);
+
+        let ssa = builder.finish().remove_redundant_constraints();
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions, &[instructions[0]]);
+        assert_eq!(main.dfg[instructions[0]], Instruction::ConstrainEqual(v0, v1));
+    }
+
+    #[test]
+    fn constraints_in_sibling_branches_are_kept() {
+        // fn main f0 {
+        //   b0(v0: u1, v1: u1):
+        //     jmpif v0 then: b1, else: b2
+        //   b1():
+        //     constrain v1
+        //     jmp b3()
+        //   b2():
+        //     constrain v1
+        //     jmp b3()
+        //   b3():
+        //     return
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::bool());
+        let v1 = builder.add_parameter(Type::bool());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+        builder.terminate_with_jmpif(v0, b1, b2);
+
+        for block in [b1, b2] {
+            builder.switch_to_block(block);
+            builder.insert_constrain(v1, None);
+            builder.terminate_with_jmp(b3, vec![]);
+        }
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![]);
+
+        // Neither branch dominates the other, so both constraints are needed
+        let ssa = builder.finish().remove_redundant_constraints();
+        assert_eq!(count_constraints(ssa.main()), 2);
+    }
+}
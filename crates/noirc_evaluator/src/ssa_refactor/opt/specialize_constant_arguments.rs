@@ -0,0 +1,212 @@
+//! This file contains a pass specializing functions for the constant arguments they are
+//! called with.
+//!
+//! When a function is called with a numeric constant for some of its parameters, such as a
+//! flag selecting between two behaviors, a copy of the function is made with those parameters
+//! replaced by the constants, and removed from its signature. Each call passing the same
+//! constants is redirected to that copy, where the constants can then be folded into the rest
+//! of its body. Calls which pass different constants are given copies of their own.
+//!
+//! Each copy adds the function's instructions to the program again, so a function is only
+//! specialized until its copies would total more than `MAX_SPECIALIZED_INSTRUCTIONS`
+//! instructions. Main, recursive calls, and functions which may be called indirectly are never
+//! specialized.
+use std::collections::HashMap;
+
+use acvm::FieldElement;
+
+use crate::ssa_refactor::{
+    ir::{
+        function::{Function, FunctionId},
+        instruction::{Instruction, InstructionId},
+        unused_parameters::escaping_functions,
+        value::Value,
+    },
+    ssa_gen::Ssa,
+};
+
+/// The maximum number of instructions which may be added to the program by specializing any
+/// one function.
+const MAX_SPECIALIZED_INSTRUCTIONS: usize = 1000;
+
+/// A function along with the constant, if any, passed for each of its parameters.
+type Specialization = (FunctionId, Vec<Option<FieldElement>>);
+
+impl Ssa {
+    /// Redirects each call passing numeric constants to a copy of the called function with
+    /// those constants in place of its parameters.
+    pub(crate) fn specialize_constant_arguments(mut self) -> Ssa {
+        for ((callee, constants), calls) in find_specializations(&self) {
+            let specialized_id = self.next_id.next();
+            let mut specialized = self.functions[&callee].clone_with_id(specialized_id);
+
+            let parameters = specialized.parameters().to_vec();
+            for (parameter, constant) in parameters.into_iter().zip(&constants) {
+                if let Some(constant) = constant {
+                    let typ = specialized.dfg.type_of_value(parameter);
+                    let constant = specialized.dfg.make_constant(*constant, typ);
+                    specialized.dfg.set_value_from_id(parameter, constant);
+                }
+            }
+            self.functions.insert(specialized_id, specialized);
+
+            for (caller, call) in calls {
+                let dfg = &mut self.functions.get_mut(&caller).expect("ICE: Unknown caller").dfg;
+                let function = dfg.import_function(specialized_id);
+                if let Instruction::Call { func, .. } = &mut dfg[call] {
+                    *func = function;
+                }
+            }
+
+            let remaining = constants.iter().enumerate().filter(|(_, constant)| constant.is_none());
+            let remaining: Vec<_> = remaining.map(|(index, _)| index).collect();
+            self.change_parameters(specialized_id, &remaining);
+        }
+        self
+    }
+}
+
+/// Groups each call passing at least one numeric constant by the function it calls and the
+/// constants it passes, in the order the calls are found. Specializations which would exceed
+/// the size limit of their function are left out.
+fn find_specializations(ssa: &Ssa) -> Vec<(Specialization, Vec<(FunctionId, InstructionId)>)> {
+    let escaping = escaping_functions(ssa);
+    let mut specializations: Vec<(Specialization, Vec<_>)> = Vec::new();
+
+    for (caller_id, caller) in &ssa.functions {
+        let dfg = &caller.dfg;
+        for block in caller.blocks_in_dominator_order() {
+            for instruction in dfg[block].instructions() {
+                let (callee, arguments) = match &dfg[*instruction] {
+                    Instruction::Call { func, arguments } => match &dfg[dfg.resolve(*func)] {
+                        Value::Function(callee) => (*callee, arguments),
+                        _ => continue,
+                    },
+                    _ => continue,
+                };
+
+                let is_specializable =
+                    callee != ssa.main_id && callee != *caller_id && !escaping.contains(&callee);
+                let constants: Vec<_> =
+                    arguments.iter().map(|argument| dfg.get_numeric_constant(*argument)).collect();
+                if !is_specializable || constants.iter().all(Option::is_none) {
+                    continue;
+                }
+
+                let specialization = (callee, constants);
+                let call = (*caller_id, *instruction);
+                match specializations.iter_mut().find(|(existing, _)| *existing == specialization) {
+                    Some((_, calls)) => calls.push(call),
+                    None => specializations.push((specialization, vec![call])),
+                }
+            }
+        }
+    }
+
+    let mut added_instructions = HashMap::new();
+    specializations.retain(|((callee, _), _)| {
+        let added = added_instructions.entry(*callee).or_insert(0);
+        *added += instruction_count(&ssa.functions[callee]);
+        *added <= MAX_SPECIALIZED_INSTRUCTIONS
+    });
+    specializations
+}
+
+/// Counts the instructions within the reachable blocks of the given function.
+fn instruction_count(function: &Function) -> usize {
+    let blocks = function.reachable_blocks();
+    blocks.into_iter().map(|block| function.dfg[block].instructions().len()).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{Binary, BinaryOp, Instruction},
+            map::Id,
+            types::Type,
+            value::Value,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn function_called_with_a_constant_flag_is_specialized() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     v3 = call f1(v0, Field 1)
+        //     v4 = call f1(v1, Field 1)
+        //     v5 = call f1(v0, v1)
+        //     return v3, v4, v5
+        // }
+        // fn foo f1 {
+        //   b0(v0: Field, v1: Field):
+        //     v2 = mul v0, v1
+        //     return v2
+        // }
+        let main_id = Id::test_new(0);
+        let foo_id = Id::test_new(1);
+
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let one = builder.field_constant(1u128);
+        let foo = builder.import_function(foo_id);
+        let v3 = builder.insert_call(foo, vec![v0, one], vec![Type::field()])[0];
+        let v4 = builder.insert_call(foo, vec![v1, one], vec![Type::field()])[0];
+        let v5 = builder.insert_call(foo, vec![v0, v1], vec![Type::field()])[0];
+        builder.terminate_with_return(vec![v3, v4, v5]);
+
+        builder.new_function("foo".into(), foo_id);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let v2 = builder.insert_binary(v0, BinaryOp::Mul, v1);
+        builder.terminate_with_return(vec![v2]);
+
+        // After specializing, both calls passing Field 1 call a copy of foo without its flag:
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     v3 = call f2(v0)
+        //     v4 = call f2(v1)
+        //     v5 = call f1(v0, v1)
+        //     return v3, v4, v5
+        // }
+        // fn foo f2 {
+        //   b0(v0: Field):
+        //     v2 = mul v0, Field 1
+        //     return v2
+        // }
+        let ssa = builder.finish().specialize_constant_arguments();
+        assert_eq!(ssa.functions.len(), 3);
+
+        let main = ssa.main();
+        let calls: Vec<_> = main.dfg[main.entry_block()]
+            .instructions()
+            .iter()
+            .map(|instruction| match &main.dfg[*instruction] {
+                Instruction::Call { func, arguments } => (main.dfg[*func].clone(), arguments.len()),
+                other => panic!("Expected a call, found {other:?}"),
+            })
+            .collect();
+        let specialized_id = Id::test_new(2);
+        assert_eq!(
+            calls,
+            vec![
+                (Value::Function(specialized_id), 1),
+                (Value::Function(specialized_id), 1),
+                (Value::Function(foo_id), 2),
+            ]
+        );
+
+        let specialized = &ssa.functions[&specialized_id];
+        assert_eq!(specialized.parameters().len(), 1);
+        let instructions = specialized.dfg[specialized.entry_block()].instructions();
+        match &specialized.dfg[instructions[0]] {
+            Instruction::Binary(Binary { rhs, .. }) => {
+                assert_eq!(specialized.dfg.get_numeric_constant(*rhs), Some(1u128.into()));
+            }
+            other => panic!("Expected a binary instruction, found {other:?}"),
+        }
+    }
+}
@@ -0,0 +1,156 @@
+//! This file contains the strength reduction pass of the SSA IR.
+//!
+//! Brillig executes a left shift more cheaply than a multiplication, so any multiplication
+//! of an unsigned integer by a constant power of two is replaced by a left shift of the
+//! other operand, e.g. `x * 8` becomes `x << 3`. Both operations wrap at the bit size of
+//! the integer type, so this does not change the result of overflowing multiplications.
+//!
+//! ACIR lowers a left shift to a multiplication anyway, so only Brillig functions are changed.
+use crate::ssa_refactor::{
+    ir::{
+        dfg::DataFlowGraph,
+        function::{Function, RuntimeType},
+        instruction::{Binary, BinaryOp, Instruction},
+        types::{NumericType, Type},
+        value::ValueId,
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Replaces each multiplication by a constant power of two within a Brillig function
+    /// with a left shift.
+    pub(crate) fn strength_reduction(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            if function.runtime() == RuntimeType::Brillig {
+                strength_reduction(function);
+            }
+        }
+        self
+    }
+}
+
+fn strength_reduction(function: &mut Function) {
+    for block in function.reachable_blocks() {
+        for instruction_id in function.dfg[block].instructions().to_vec() {
+            let binary = match &function.dfg[instruction_id] {
+                Instruction::Binary(binary) => binary.clone(),
+                _ => continue,
+            };
+            if let Some(shift) = multiplication_to_shift(&mut function.dfg, binary) {
+                function.dfg[instruction_id] = Instruction::Binary(shift);
+            }
+        }
+    }
+}
+
+/// If the given binary instruction multiplies an unsigned integer by a constant power of two,
+/// returns the equivalent left shift.
+fn multiplication_to_shift(dfg: &mut DataFlowGraph, binary: Binary) -> Option<Binary> {
+    if binary.operator != BinaryOp::Mul {
+        return None;
+    }
+
+    let typ = dfg.type_of_value(binary.lhs);
+    let bit_size = match typ {
+        Type::Numeric(NumericType::Unsigned { bit_size }) => bit_size,
+        _ => return None,
+    };
+
+    let (value, exponent) = match (power_of_two(dfg, binary.lhs), power_of_two(dfg, binary.rhs)) {
+        (_, Some(exponent)) => (binary.lhs, exponent),
+        (Some(exponent), None) => (binary.rhs, exponent),
+        (None, None) => return None,
+    };
+
+    // Shifting by zero is a multiplication by one, which is already simplified away.
+    // Shifting by the bit size or more is left alone since some targets do not define it.
+    if exponent == 0 || exponent >= bit_size {
+        return None;
+    }
+
+    let shift = dfg.make_constant(u128::from(exponent).into(), typ);
+    Some(Binary { lhs: value, rhs: shift, operator: BinaryOp::Shl })
+}
+
+/// Returns `n` if the given value is the constant `2^n`.
+fn power_of_two(dfg: &DataFlowGraph, value: ValueId) -> Option<u32> {
+    let constant = dfg.get_numeric_constant(value)?.try_into_u128()?;
+    constant.is_power_of_two().then(|| constant.trailing_zeros())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{Binary, BinaryOp, Instruction},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn multiplication_by_power_of_two_becomes_shift() {
+        // unconstrained fn main f0 {
+        //   b0(v0: u32):
+        //     v1 = mul v0, u32 16
+        //     return v1
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Brillig);
+        let v0 = builder.add_parameter(Type::unsigned(32));
+        let sixteen = builder.numeric_constant(16u128, Type::unsigned(32));
+        let v1 = builder.insert_binary(v0, BinaryOp::Mul, sixteen);
+        builder.terminate_with_return(vec![v1]);
+
+        // Expected output:
+        // unconstrained fn main f0 {
+        //   b0(v0: u32):
+        //     v1 = shl v0, u32 4
+        //     return v1
+        // }
+        let ssa = builder.finish().strength_reduction();
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 1);
+
+        match &main.dfg[instructions[0]] {
+            Instruction::Binary(Binary { lhs, rhs, operator: BinaryOp::Shl }) => {
+                assert_eq!(*lhs, v0);
+                assert_eq!(main.dfg.get_numeric_constant(*rhs), Some(4u128.into()));
+                assert_eq!(main.dfg.type_of_value(*rhs), Type::unsigned(32));
+            }
+            other => panic!("Expected a left shift, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn other_multiplications_are_kept() {
+        // unconstrained fn main f0 {
+        //   b0(v0: u32, v1: Field):
+        //     v2 = mul v0, u32 12
+        //     v3 = mul v1, Field 16
+        //     return v2, v3
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Brillig);
+        let v0 = builder.add_parameter(Type::unsigned(32));
+        let v1 = builder.add_parameter(Type::field());
+        let twelve = builder.numeric_constant(12u128, Type::unsigned(32));
+        let v2 = builder.insert_binary(v0, BinaryOp::Mul, twelve);
+        let sixteen = builder.field_constant(16u128);
+        let v3 = builder.insert_binary(v1, BinaryOp::Mul, sixteen);
+        builder.terminate_with_return(vec![v2, v3]);
+
+        let ssa = builder.finish().strength_reduction();
+        let main = ssa.main();
+        for instruction in main.dfg[main.entry_block()].instructions() {
+            assert!(matches!(
+                main.dfg[*instruction],
+                Instruction::Binary(Binary { operator: BinaryOp::Mul, .. })
+            ));
+        }
+    }
+}
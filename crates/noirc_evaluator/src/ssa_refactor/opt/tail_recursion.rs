@@ -0,0 +1,255 @@
+//! This file contains the pass converting tail-recursive functions into loops.
+//!
+//! A recursive call is in tail position if it is the last instruction of its block and the
+//! block returns exactly the results of the call. Such a call does not need a new stack frame:
+//! it can instead jump back to the start of the function with the call's arguments. To do so,
+//! the body of the entry block is moved into a new loop header block, whose parameters replace
+//! the function's parameters. The entry block then jumps to the header with the function's
+//! parameters, and each tail call jumps to the header with its arguments.
+//!
+//! Only unconstrained functions are converted. Constrained functions must be fully inlined and
+//! unrolled, and the unrolling pass only supports loops with a single induction variable.
+use iter_extended::vecmap;
+
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId,
+        function::{Function, RuntimeType},
+        function_inserter::FunctionInserter,
+        instruction::{Instruction, TerminatorInstruction},
+        value::{Value, ValueId},
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Converts each tail-recursive call within an unconstrained function into a jump
+    /// back to the start of the function.
+    pub(crate) fn convert_tail_recursion(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            if function.runtime() == RuntimeType::Brillig {
+                convert_tail_recursion(function);
+            }
+        }
+        self
+    }
+}
+
+fn convert_tail_recursion(function: &mut Function) {
+    let tail_calls = find_tail_calls(function);
+    if tail_calls.is_empty() {
+        return;
+    }
+
+    let mut blocks = function.reachable_blocks();
+    let entry = function.entry_block();
+    let parameters = function.parameters().to_vec();
+
+    let header = function.append_block();
+    let header_parameters = vecmap(&parameters, |parameter| {
+        let typ = function.dfg.type_of_value(*parameter);
+        function.dfg.add_block_parameter(header, typ)
+    });
+
+    // Move the body of the entry block into the loop header
+    let instructions = std::mem::take(function.dfg[entry].instructions_mut());
+    let terminator = function.dfg[entry].take_terminator();
+    *function.dfg[header].instructions_mut() = instructions;
+    function.dfg[header].set_terminator(terminator);
+    blocks.remove(&entry);
+    blocks.insert(header);
+
+    for (block, arguments) in tail_calls {
+        let block = if block == entry { header } else { block };
+        function.dfg[block].instructions_mut().pop();
+        let jmp = TerminatorInstruction::Jmp { destination: header, arguments };
+        function.dfg[block].set_terminator(jmp);
+    }
+
+    // Each use of the function's parameters now refers to the header's parameters instead
+    let mut inserter = FunctionInserter::new(function);
+    for (parameter, header_parameter) in parameters.iter().zip(header_parameters) {
+        inserter.map_value(*parameter, header_parameter);
+    }
+
+    for block in blocks {
+        for instruction in inserter.function.dfg[block].instructions().to_vec() {
+            let mapped = inserter.map_instruction(instruction);
+            inserter.function.dfg[instruction] = mapped;
+        }
+        let terminator = inserter.function.dfg[block].unwrap_terminator().clone();
+        let terminator = terminator.map_values(|value| inserter.resolve(value));
+        inserter.function.dfg[block].set_terminator(terminator);
+    }
+
+    let jmp = TerminatorInstruction::Jmp { destination: header, arguments: parameters };
+    function.dfg[entry].set_terminator(jmp);
+}
+
+/// Returns each block ending in a call to the function itself whose results are returned
+/// unchanged, along with the arguments of that call.
+fn find_tail_calls(function: &Function) -> Vec<(BasicBlockId, Vec<ValueId>)> {
+    let dfg = &function.dfg;
+    let mut tail_calls = Vec::new();
+
+    for block in function.reachable_blocks() {
+        let last_instruction = match dfg[block].instructions().last() {
+            Some(instruction) => *instruction,
+            None => continue,
+        };
+
+        let (func, arguments) = match &dfg[last_instruction] {
+            Instruction::Call { func, arguments } => (*func, arguments),
+            _ => continue,
+        };
+
+        if !matches!(dfg[func], Value::Function(id) if id == function.id()) {
+            continue;
+        }
+
+        if let TerminatorInstruction::Return { return_values } = dfg[block].unwrap_terminator() {
+            let results = dfg.instruction_results(last_instruction);
+            let returns_results = return_values.len() == results.len()
+                && return_values
+                    .iter()
+                    .zip(results)
+                    .all(|(value, result)| dfg.resolve(*value) == dfg.resolve(*result));
+
+            if returns_results && !permutes_parameters(function, arguments) {
+                tail_calls.push((block, arguments.clone()));
+            }
+        }
+    }
+    tail_calls
+}
+
+/// Returns true if any of the given arguments is a parameter of the function passed to a
+/// different position. Brillig moves jump arguments into the destination's parameters one
+/// at a time, so jumping back to the loop header with e.g. `(v1, v0)` would overwrite `v0`
+/// before it is read.
+fn permutes_parameters(function: &Function, arguments: &[ValueId]) -> bool {
+    let parameters = function.parameters();
+    arguments.iter().enumerate().any(|(position, argument)| {
+        let argument = function.dfg.resolve(*argument);
+        parameters
+            .iter()
+            .position(|parameter| *parameter == argument)
+            .map_or(false, |parameter_position| parameter_position != position)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{BinaryOp, Instruction, TerminatorInstruction},
+            interpreter::{interpret, InterpreterValue},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn tail_recursive_sum_becomes_loop() {
+        // unconstrained fn sum f0 {
+        //   b0(v0: Field, v1: Field):
+        //     v2 = eq v0, Field 0
+        //     jmpif v2 then: b1, else: b2
+        //   b1():
+        //     return v1
+        //   b2():
+        //     v3 = sub v0, Field 1
+        //     v4 = add v1, v0
+        //     v5 = call f0(v3, v4)
+        //     return v5
+        // }
+        let sum_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("sum".into(), sum_id, RuntimeType::Brillig);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+
+        let zero = builder.field_constant(0u128);
+        let v2 = builder.insert_binary(v0, BinaryOp::Eq, zero);
+        builder.terminate_with_jmpif(v2, b1, b2);
+
+        builder.switch_to_block(b1);
+        builder.terminate_with_return(vec![v1]);
+
+        builder.switch_to_block(b2);
+        let one = builder.field_constant(1u128);
+        let v3 = builder.insert_binary(v0, BinaryOp::Sub, one);
+        let v4 = builder.insert_binary(v1, BinaryOp::Add, v0);
+        let sum = builder.import_function(sum_id);
+        let v5 = builder.insert_call(sum, vec![v3, v4], vec![Type::field()]).to_vec();
+        builder.terminate_with_return(v5);
+
+        // Expected output:
+        // unconstrained fn sum f0 {
+        //   b0(v0: Field, v1: Field):
+        //     jmp b3(v0, v1)
+        //   b3(v6: Field, v7: Field):
+        //     v2 = eq v6, Field 0
+        //     jmpif v2 then: b1, else: b2
+        //   b1():
+        //     return v7
+        //   b2():
+        //     v3 = sub v6, Field 1
+        //     v4 = add v7, v6
+        //     jmp b3(v3, v4)
+        // }
+        let ssa = builder.finish().convert_tail_recursion();
+        let main = ssa.main();
+
+        let header = match main.dfg[main.entry_block()].unwrap_terminator() {
+            TerminatorInstruction::Jmp { destination, arguments } => {
+                assert_eq!(arguments, main.parameters());
+                *destination
+            }
+            other => panic!("Expected a jmp to the loop header, found {other:?}"),
+        };
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+
+        match main.dfg[b2].unwrap_terminator() {
+            TerminatorInstruction::Jmp { destination, .. } => assert_eq!(*destination, header),
+            other => panic!("Expected a jmp back to the loop header, found {other:?}"),
+        }
+
+        for block in main.reachable_blocks() {
+            for instruction in main.dfg[block].instructions() {
+                assert!(!matches!(main.dfg[*instruction], Instruction::Call { .. }));
+            }
+        }
+
+        let field = InterpreterValue::numeric;
+        assert_eq!(interpret(main, vec![field(4), field(0)]), Ok(vec![field(10)]));
+        assert_eq!(interpret(main, vec![field(0), field(7)]), Ok(vec![field(7)]));
+    }
+
+    #[test]
+    fn non_tail_recursion_is_kept() {
+        // unconstrained fn f f0 {
+        //   b0(v0: Field):
+        //     v1 = call f0(v0)
+        //     v2 = add v1, Field 1
+        //     return v2
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("f".into(), func_id, RuntimeType::Brillig);
+        let v0 = builder.add_parameter(Type::field());
+        let func = builder.import_function(func_id);
+        let v1 = builder.insert_call(func, vec![v0], vec![Type::field()])[0];
+        let one = builder.field_constant(1u128);
+        let v2 = builder.insert_binary(v1, BinaryOp::Add, one);
+        builder.terminate_with_return(vec![v2]);
+
+        let ssa = builder.finish().convert_tail_recursion();
+        let main = ssa.main();
+        assert_eq!(main.reachable_blocks().len(), 1);
+        assert_eq!(main.dfg[main.entry_block()].instructions().len(), 2);
+    }
+}
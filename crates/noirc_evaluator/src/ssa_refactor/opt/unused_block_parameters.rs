@@ -0,0 +1,191 @@
+//! This file contains a pass removing the unused parameters of each block within a function.
+//!
+//! Once dead instruction elimination removes the consumers of a value passed between blocks,
+//! the block parameter receiving it is left unused, along with the argument passed for it by
+//! each predecessor. Removing that argument may in turn leave a parameter of the predecessor
+//! unused if the argument was only forwarded from it, so parameters are removed repeatedly
+//! until none are left unused. A parameter whose only use is being passed back to itself, such
+//! as a loop-carried value which is never read, is also considered unused.
+//!
+//! The parameters of the entry block are the function's own parameters, so they are never
+//! removed by this pass. See `UnusedParameters` for those.
+use std::collections::{HashMap, HashSet};
+
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId, function::Function, instruction::TerminatorInstruction,
+        value::ValueId,
+    },
+    ssa_gen::Ssa,
+};
+
+impl Ssa {
+    /// Removes each unused parameter of a block other than an entry block, along with the
+    /// arguments passed for it by the block's predecessors.
+    pub(crate) fn remove_unused_block_parameters(mut self) -> Ssa {
+        for function in self.functions.values_mut() {
+            while remove_unused_block_parameters(function) {}
+        }
+        self
+    }
+}
+
+/// Removes the block parameters which are currently unused within the given function, returning
+/// true if any were removed.
+fn remove_unused_block_parameters(function: &mut Function) -> bool {
+    let blocks = function.reachable_blocks();
+    let used = used_values(function, &blocks);
+
+    let mut unused: HashMap<BasicBlockId, Vec<usize>> = HashMap::new();
+    for block in &blocks {
+        if *block == function.entry_block() {
+            continue;
+        }
+        for (index, parameter) in function.dfg.block_parameters(*block).iter().enumerate() {
+            if !used.contains(parameter) {
+                unused.entry(*block).or_default().push(index);
+            }
+        }
+    }
+    if unused.is_empty() {
+        return false;
+    }
+
+    for block in &blocks {
+        if let TerminatorInstruction::Jmp { destination, arguments } =
+            function.dfg[*block].unwrap_terminator_mut()
+        {
+            if let Some(removed) = unused.get(destination) {
+                *arguments = keep_remaining(std::mem::take(arguments), removed);
+            }
+        }
+    }
+    for (block, removed) in &unused {
+        let parameters = function.dfg[*block].take_parameters();
+        function.dfg[*block].set_parameters(keep_remaining(parameters, removed));
+    }
+    true
+}
+
+/// Collects every value used within the given blocks. An argument passed by a jmp counts as a
+/// use unless it is the very parameter it is passed for.
+fn used_values(function: &Function, blocks: &HashSet<BasicBlockId>) -> HashSet<ValueId> {
+    let dfg = &function.dfg;
+    let mut used = HashSet::new();
+
+    for block in blocks {
+        let mut insert = |value: ValueId| used.insert(dfg.resolve(value));
+        for instruction in dfg[*block].instructions() {
+            dfg[*instruction].for_each_value(&mut insert);
+        }
+
+        match dfg[*block].unwrap_terminator() {
+            TerminatorInstruction::Jmp { destination, arguments } => {
+                let parameters = dfg.block_parameters(*destination);
+                for (argument, parameter) in arguments.iter().zip(parameters) {
+                    if dfg.resolve(*argument) != *parameter {
+                        insert(*argument);
+                    }
+                }
+            }
+            terminator => terminator.for_each_value(&mut insert),
+        }
+    }
+    used
+}
+
+/// Returns the given values without those at the `removed` indices.
+fn keep_remaining(values: Vec<ValueId>, removed: &[usize]) -> Vec<ValueId> {
+    let values = values.into_iter().enumerate();
+    values.filter(|(index, _)| !removed.contains(index)).map(|(_, value)| value).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{BinaryOp, TerminatorInstruction},
+            map::Id,
+            types::Type,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn dead_loop_carried_parameter_is_removed() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     jmp b1(Field 0, v0)
+        //   b1(v1: Field, v2: Field):
+        //     v3 = lt v1, Field 4
+        //     jmpif v3 then: b2, else: b3
+        //   b2():
+        //     v4 = add v1, Field 1
+        //     jmp b1(v4, v2)
+        //   b3():
+        //     jmp b4(v2)
+        //   b4(v5: Field):
+        //     return v1
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+        let b4 = builder.insert_block();
+        let v1 = builder.add_block_parameter(b1, Type::field());
+        let v2 = builder.add_block_parameter(b1, Type::field());
+        builder.add_block_parameter(b4, Type::field());
+
+        let zero = builder.field_constant(0u128);
+        builder.terminate_with_jmp(b1, vec![zero, v0]);
+
+        builder.switch_to_block(b1);
+        let four = builder.field_constant(4u128);
+        let v3 = builder.insert_binary(v1, BinaryOp::Lt, four);
+        builder.terminate_with_jmpif(v3, b2, b3);
+
+        builder.switch_to_block(b2);
+        let one = builder.field_constant(1u128);
+        let v4 = builder.insert_binary(v1, BinaryOp::Add, one);
+        builder.terminate_with_jmp(b1, vec![v4, v2]);
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_jmp(b4, vec![v2]);
+
+        builder.switch_to_block(b4);
+        builder.terminate_with_return(vec![v1]);
+
+        // Once v5 and its argument are removed, v2 is only passed back to itself:
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     jmp b1(Field 0)
+        //   b1(v1: Field):
+        //     v3 = lt v1, Field 4
+        //     jmpif v3 then: b2, else: b3
+        //   b2():
+        //     v4 = add v1, Field 1
+        //     jmp b1(v4)
+        //   b3():
+        //     jmp b4()
+        //   b4():
+        //     return v1
+        // }
+        let ssa = builder.finish().remove_unused_block_parameters();
+        let main = ssa.main();
+        assert_eq!(main.parameters(), &[v0]);
+        assert_eq!(main.dfg.block_parameters(b1), &[v1]);
+        assert!(main.dfg.block_parameters(b4).is_empty());
+
+        let jump_arguments = |block| match main.dfg[block].unwrap_terminator() {
+            TerminatorInstruction::Jmp { arguments, .. } => arguments.clone(),
+            other => panic!("Expected a jmp, found {other:?}"),
+        };
+        assert_eq!(jump_arguments(main.entry_block()), vec![zero]);
+        assert_eq!(jump_arguments(b2), vec![v4]);
+        assert!(jump_arguments(b3).is_empty());
+    }
+}
@@ -0,0 +1,222 @@
+//! This file contains an analysis over the SSA IR to find element-wise operations on arrays.
+//!
+//! After unrolling, a loop such as `for i in 0..3 { c[i] = a[i] + b[i]; }` becomes a sequence
+//! of structurally identical instructions, one for each index of the arrays. A backend which
+//! supports vector operations can execute such a group as a single batched operation.
+//!
+//! An instruction is part of a group if it is a binary operation whose operands are each either
+//! an `array_get` from an array at a constant index, or a value shared by the whole group. Each
+//! array operand must be read at the same index within a single instruction, and each
+//! instruction of a group must be for a different index.
+use std::collections::HashMap;
+
+use crate::ssa_refactor::{
+    ir::{
+        basic_block::BasicBlockId,
+        dfg::DataFlowGraph,
+        function::{Function, FunctionId},
+        instruction::{BinaryOp, Instruction, InstructionId},
+        value::{Value, ValueId},
+    },
+    ssa_gen::Ssa,
+};
+
+/// An operand of a vector operation.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum VectorOperand {
+    /// Each element of the operation reads this array at its own index.
+    Array(ValueId),
+
+    /// Each element of the operation uses this same value.
+    Scalar(ValueId),
+}
+
+/// A group of binary instructions within a single block applying the same operation to
+/// different elements of the same arrays.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VectorOperation {
+    pub(crate) function: FunctionId,
+    pub(crate) block: BasicBlockId,
+    pub(crate) operator: BinaryOp,
+    pub(crate) lhs: VectorOperand,
+    pub(crate) rhs: VectorOperand,
+
+    /// The index of each element and the instruction computing it, ordered by index.
+    pub(crate) elements: Vec<(u128, InstructionId)>,
+}
+
+impl Ssa {
+    /// Find each group of binary instructions which apply the same operation to each element
+    /// of one or more arrays.
+    ///
+    /// This does not modify the SSA, it only reports operations which could be batched.
+    pub(crate) fn find_vector_operations(&self) -> Vec<VectorOperation> {
+        self.functions.values().flat_map(find_vector_operations_in_function).collect()
+    }
+}
+
+fn find_vector_operations_in_function(function: &Function) -> Vec<VectorOperation> {
+    let mut blocks: Vec<_> = function.reachable_blocks().into_iter().collect();
+    blocks.sort();
+
+    let mut operations = Vec::new();
+    for block in blocks {
+        // Groups are kept in the order their first instruction appears in the block
+        let mut groups: Vec<VectorOperation> = Vec::new();
+        let mut group_indices = HashMap::new();
+
+        for instruction in function.dfg[block].instructions() {
+            let (operator, lhs, rhs, index) = match vector_element(&function.dfg, *instruction) {
+                Some(element) => element,
+                None => continue,
+            };
+
+            let group = *group_indices.entry((operator, lhs, rhs)).or_insert_with(|| {
+                groups.push(VectorOperation {
+                    function: function.id(),
+                    block,
+                    operator,
+                    lhs,
+                    rhs,
+                    elements: Vec::new(),
+                });
+                groups.len() - 1
+            });
+
+            let elements = &mut groups[group].elements;
+            if elements.iter().all(|(existing, _)| *existing != index) {
+                elements.push((index, *instruction));
+            }
+        }
+
+        for mut group in groups {
+            if group.elements.len() > 1 {
+                group.elements.sort();
+                operations.push(group);
+            }
+        }
+    }
+    operations
+}
+
+/// If the given instruction is a binary operation on elements of arrays at a single constant
+/// index, returns its operator, operands, and that index.
+fn vector_element(
+    dfg: &DataFlowGraph,
+    instruction: InstructionId,
+) -> Option<(BinaryOp, VectorOperand, VectorOperand, u128)> {
+    let binary = match &dfg[instruction] {
+        Instruction::Binary(binary) => binary,
+        _ => return None,
+    };
+
+    let (lhs, lhs_index) = vector_operand(dfg, binary.lhs);
+    let (rhs, rhs_index) = vector_operand(dfg, binary.rhs);
+
+    let index = match (lhs_index, rhs_index) {
+        (Some(lhs_index), Some(rhs_index)) if lhs_index == rhs_index => lhs_index,
+        (Some(_), Some(_)) => return None,
+        (Some(index), None) | (None, Some(index)) => index,
+        (None, None) => return None,
+    };
+    Some((binary.operator, lhs, rhs, index))
+}
+
+/// Returns the array and constant index the given value was read from, if any.
+/// Otherwise the value is treated as a scalar.
+fn vector_operand(dfg: &DataFlowGraph, value: ValueId) -> (VectorOperand, Option<u128>) {
+    let value = dfg.resolve(value);
+    if let Value::Instruction { instruction, .. } = &dfg[value] {
+        if let Instruction::ArrayGet { array, index } = &dfg[*instruction] {
+            if let Some(index) = dfg.get_numeric_constant(*index) {
+                return (VectorOperand::Array(dfg.resolve(*array)), Some(index.to_u128()));
+            }
+        }
+    }
+    (VectorOperand::Scalar(value), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use crate::ssa_refactor::{
+        ir::{function::RuntimeType, instruction::BinaryOp, map::Id, types::Type},
+        ssa_builder::FunctionBuilder,
+    };
+
+    use super::VectorOperand;
+
+    #[test]
+    fn unrolled_element_wise_add_is_a_vector_operation() {
+        // fn main f0 {
+        //   b0(v0: [Field; 3], v1: [Field; 3], v2: Field):
+        //     v3 = array_get v0, index Field 0
+        //     v4 = array_get v1, index Field 0
+        //     v5 = add v3, v4
+        //     v6 = array_get v0, index Field 1
+        //     v7 = array_get v1, index Field 1
+        //     v8 = add v6, v7
+        //     v9 = array_get v0, index Field 2
+        //     v10 = array_get v1, index Field 2
+        //     v11 = add v9, v10
+        //     v12 = mul v5, v2
+        //     return v8, v11, v12
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let array_type = Type::Array(Rc::new(vec![Type::field()]), 3);
+        let v0 = builder.add_parameter(array_type.clone());
+        let v1 = builder.add_parameter(array_type);
+        let v2 = builder.add_parameter(Type::field());
+
+        let mut sums = Vec::new();
+        for i in 0..3u128 {
+            let index = builder.field_constant(i);
+            let lhs = builder.insert_array_get(v0, index, Type::field());
+            let rhs = builder.insert_array_get(v1, index, Type::field());
+            sums.push(builder.insert_binary(lhs, BinaryOp::Add, rhs));
+        }
+        let v12 = builder.insert_binary(sums[0], BinaryOp::Mul, v2);
+        builder.terminate_with_return(vec![sums[1], sums[2], v12]);
+
+        let ssa = builder.finish();
+        let operations = ssa.find_vector_operations();
+        assert_eq!(operations.len(), 1);
+
+        let operation = &operations[0];
+        assert_eq!(operation.operator, BinaryOp::Add);
+        assert_eq!(operation.lhs, VectorOperand::Array(v0));
+        assert_eq!(operation.rhs, VectorOperand::Array(v1));
+
+        let indices: Vec<_> = operation.elements.iter().map(|(index, _)| *index).collect();
+        assert_eq!(indices, vec![0, 1, 2]);
+
+        let main = ssa.main();
+        for ((_, instruction), sum) in operation.elements.iter().zip(sums) {
+            assert_eq!(main.dfg.instruction_results(*instruction), &[sum]);
+        }
+    }
+
+    #[test]
+    fn mismatched_indices_are_not_vectorized() {
+        // fn main f0 {
+        //   b0(v0: [Field; 2]):
+        //     v2 = array_get v0, index Field 0
+        //     v4 = array_get v0, index Field 1
+        //     v5 = add v2, v4
+        //     return v5
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::Array(Rc::new(vec![Type::field()]), 2));
+        let zero = builder.field_constant(0u128);
+        let v2 = builder.insert_array_get(v0, zero, Type::field());
+        let one = builder.field_constant(1u128);
+        let v4 = builder.insert_array_get(v0, one, Type::field());
+        let v5 = builder.insert_binary(v2, BinaryOp::Add, v4);
+        builder.terminate_with_return(vec![v5]);
+
+        assert!(builder.finish().find_vector_operations().is_empty());
+    }
+}
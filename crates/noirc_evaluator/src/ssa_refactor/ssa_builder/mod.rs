@@ -1,6 +1,8 @@
 use std::{borrow::Cow, rc::Rc};
 
 use acvm::FieldElement;
+use iter_extended::vecmap;
+use noirc_errors::Location;
 
 use crate::ssa_refactor::ir::{
     basic_block::BasicBlockId,
@@ -94,12 +96,16 @@ impl FunctionBuilder {
     }
 
     /// Insert a numeric constant into the current function
+    ///
+    /// Panics if the value is outside the range of the given type.
     pub(crate) fn numeric_constant(
         &mut self,
         value: impl Into<FieldElement>,
         typ: Type,
     ) -> ValueId {
-        self.current_function.dfg.make_constant(value.into(), typ)
+        let value = value.into();
+        assert!(typ.can_represent(value), "ICE: constant {value} does not fit in type {typ}");
+        self.current_function.dfg.make_constant(value, typ)
     }
 
     /// Insert a numeric constant into the current function of type Field
@@ -144,10 +150,22 @@ impl FunctionBuilder {
         instruction: Instruction,
         ctrl_typevars: Option<Vec<Type>>,
     ) -> InsertInstructionResult {
-        self.current_function.dfg.insert_instruction_and_results(
+        self.insert_instruction_with_location(instruction, ctrl_typevars, None)
+    }
+
+    /// Inserts a new instruction at the end of the current block along with the source
+    /// location it was generated from, and returns its results.
+    pub(crate) fn insert_instruction_with_location(
+        &mut self,
+        instruction: Instruction,
+        ctrl_typevars: Option<Vec<Type>>,
+        location: Option<Location>,
+    ) -> InsertInstructionResult {
+        self.current_function.dfg.insert_instruction_with_location(
             instruction,
             self.current_block,
             ctrl_typevars,
+            location,
         )
     }
 
@@ -190,12 +208,25 @@ impl FunctionBuilder {
 
     /// Insert a binary instruction at the end of the current block.
     /// Returns the result of the binary instruction.
+    ///
+    /// Dividing a field element by a non-zero constant is inserted as a multiplication
+    /// by the constant's precomputed inverse instead, so that no inverse needs to be
+    /// witnessed and constrained later on.
     pub(crate) fn insert_binary(
         &mut self,
         lhs: ValueId,
-        operator: BinaryOp,
-        rhs: ValueId,
+        mut operator: BinaryOp,
+        mut rhs: ValueId,
     ) -> ValueId {
+        if operator == BinaryOp::Div && self.type_of_value(lhs) == Type::field() {
+            if let Some(divisor) = self.current_function.dfg.get_numeric_constant(rhs) {
+                if !divisor.is_zero() {
+                    operator = BinaryOp::Mul;
+                    rhs = self.field_constant(divisor.inverse());
+                }
+            }
+        }
+
         let instruction = Instruction::Binary(Binary { lhs, rhs, operator });
         self.insert_instruction(instruction, None).first()
     }
@@ -224,9 +255,34 @@ impl FunctionBuilder {
             .first()
     }
 
-    /// Insert a constrain instruction at the end of the current block.
-    pub(crate) fn insert_constrain(&mut self, boolean: ValueId) {
-        self.insert_instruction(Instruction::Constrain(boolean), None);
+    /// Insert a constrain instruction at the end of the current block, recording the location
+    /// of the source code it was generated from if there is one.
+    pub(crate) fn insert_constrain(&mut self, boolean: ValueId, location: Option<Location>) {
+        self.insert_instruction_with_location(Instruction::Constrain(boolean), None, location);
+    }
+
+    /// Insert an instruction constraining `lhs` and `rhs` to be equal at the end of the
+    /// current block, recording the location of the source code it was generated from.
+    pub(crate) fn insert_constrain_eq(
+        &mut self,
+        lhs: ValueId,
+        rhs: ValueId,
+        location: Option<Location>,
+    ) {
+        let instruction = Instruction::ConstrainEqual(lhs, rhs);
+        self.insert_instruction_with_location(instruction, None, location);
+    }
+
+    /// Insert an instruction constraining `lhs` and `rhs` to differ at the end of the
+    /// current block, recording the location of the source code it was generated from.
+    pub(crate) fn insert_constrain_ne(
+        &mut self,
+        lhs: ValueId,
+        rhs: ValueId,
+        location: Option<Location>,
+    ) {
+        let instruction = Instruction::ConstrainNotEqual(lhs, rhs);
+        self.insert_instruction_with_location(instruction, None, location);
     }
 
     /// Insert a call instruction at the end of the current block and return
@@ -276,6 +332,32 @@ impl FunctionBuilder {
         self.terminate_block_with(TerminatorInstruction::Jmp { destination, arguments });
     }
 
+    /// Terminate the current block with a jmp instruction to the given block, first casting
+    /// each numeric argument whose type differs from the corresponding block parameter's.
+    ///
+    /// This lets blocks merging values from several predecessors, such as the end of an `if`,
+    /// receive values of different integer widths as their common type.
+    pub(crate) fn terminate_with_jmp_and_casts(
+        &mut self,
+        destination: BasicBlockId,
+        arguments: Vec<ValueId>,
+    ) {
+        let parameter_types =
+            vecmap(self.block_parameters(destination), |parameter| self.type_of_value(*parameter));
+        assert_eq!(arguments.len(), parameter_types.len());
+
+        let arguments = vecmap(arguments.into_iter().zip(parameter_types), |(argument, typ)| {
+            let argument_type = self.type_of_value(argument);
+            match (&argument_type, &typ) {
+                (Type::Numeric(_), Type::Numeric(_)) if argument_type != typ => {
+                    self.insert_cast(argument, typ)
+                }
+                _ => argument,
+            }
+        });
+        self.terminate_with_jmp(destination, arguments);
+    }
+
     /// Terminate the current block with a jmpif instruction to jmp with the given arguments
     /// block with the given arguments.
     pub(crate) fn terminate_with_jmpif(
@@ -357,7 +439,8 @@ mod tests {
 
     use crate::ssa_refactor::ir::{
         function::RuntimeType,
-        instruction::{Endian, Intrinsic},
+        instruction::{Binary, BinaryOp, Endian, Instruction, Intrinsic},
+        interpreter::{interpret, InterpreterValue},
         map::Id,
         types::Type,
         value::Value,
@@ -365,6 +448,23 @@ mod tests {
 
     use super::FunctionBuilder;
 
+    #[test]
+    fn constants_within_their_type_are_accepted() {
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        builder.numeric_constant(255u128, Type::unsigned(8));
+        builder.numeric_constant(255u128, Type::signed(8));
+        builder.numeric_constant(-FieldElement::one(), Type::field());
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit in type u8")]
+    fn constant_outside_its_type_is_rejected() {
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        builder.numeric_constant(300u128, Type::unsigned(8));
+    }
+
     #[test]
     fn insert_constant_call() {
         // `bits` should be an array of constants [1, 1, 1, 0...]:
@@ -390,4 +490,178 @@ mod tests {
         assert_eq!(array[2], one);
         assert_eq!(array[3], zero);
     }
+
+    #[test]
+    fn field_division_by_constant_uses_inverse() {
+        // fn func(v0: Field) {
+        //   b0(v0: Field):
+        //     v1 = div v0, Field 4
+        //     return v1
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let four = builder.field_constant(4u128);
+        let v1 = builder.insert_binary(v0, BinaryOp::Div, four);
+
+        let instruction = match &builder.current_function.dfg[v1] {
+            Value::Instruction { instruction, .. } => *instruction,
+            other => panic!("Expected an instruction result, found {other:?}"),
+        };
+
+        // Expected:
+        //     v1 = mul v0, Field 4^-1
+        match &builder.current_function.dfg[instruction] {
+            Instruction::Binary(Binary { lhs, operator, rhs }) => {
+                assert_eq!(*lhs, v0);
+                assert_eq!(*operator, BinaryOp::Mul);
+                let inverse = builder.current_function.dfg.get_numeric_constant(*rhs);
+                assert_eq!(inverse, Some(FieldElement::from(4u128).inverse()));
+            }
+            other => panic!("Expected a binary instruction, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn binary_operations_on_constants_are_folded() {
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+
+        let mut fold = |lhs: FieldElement, operator, rhs: FieldElement, typ: Type| {
+            let lhs = builder.numeric_constant(lhs, typ.clone());
+            let rhs = builder.numeric_constant(rhs, typ);
+            let result = builder.insert_binary(lhs, operator, rhs);
+            builder.current_function.dfg.get_numeric_constant(result)
+        };
+        let u32_type = Type::unsigned(32);
+
+        // 2 + 3 == 5
+        let (two, three) = (FieldElement::from(2u128), FieldElement::from(3u128));
+        assert_eq!(fold(two, BinaryOp::Add, three, u32_type.clone()), Some(5u128.into()));
+
+        // Field multiplication wraps around the modulus: (p - 1) * 2 == p - 2
+        let minus_one = -FieldElement::one();
+        assert_eq!(fold(minus_one, BinaryOp::Mul, two, Type::field()), Some(-two));
+
+        // Integer division rounds down rather than multiplying by the inverse
+        let seven = FieldElement::from(7u128);
+        assert_eq!(fold(seven, BinaryOp::Div, two, u32_type.clone()), Some(three));
+        assert_eq!(fold(seven, BinaryOp::Mod, two, u32_type.clone()), Some(FieldElement::one()));
+        assert_eq!(fold(two, BinaryOp::Lt, three, u32_type.clone()), Some(FieldElement::one()));
+
+        // Division by zero fails at runtime, so it is left in place
+        let zero = FieldElement::zero();
+        assert_eq!(fold(seven, BinaryOp::Div, zero, u32_type.clone()), None);
+        assert_eq!(fold(seven, BinaryOp::Mod, zero, u32_type), None);
+        assert_eq!(fold(seven, BinaryOp::Div, zero, Type::field()), None);
+
+        // The bit pattern of -8i8 is 248, which must not be divided as an unsigned integer
+        let minus_eight = FieldElement::from(248u128);
+        assert_eq!(fold(minus_eight, BinaryOp::Div, two, Type::signed(8)), None);
+        assert_eq!(fold(minus_eight, BinaryOp::Mod, three, Type::signed(8)), None);
+
+        // Only the divisions by zero and the signed divisions were inserted as instructions
+        assert_eq!(builder.current_function.dfg[builder.current_block].instructions().len(), 5);
+    }
+
+    #[test]
+    fn modulo_by_constant_larger_than_type_is_removed() {
+        // fn func(v0: u8) {
+        //   b0(v0: u8):
+        //     v1 = mod v0, u8 256
+        //     v2 = mod v0, u8 255
+        //     return v1, v2
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(8));
+        // The builder rejects 256 as a u8, but such constants still arise from folding
+        let modulus = builder.current_function.dfg.make_constant(256u128.into(), Type::unsigned(8));
+        let v1 = builder.insert_binary(v0, BinaryOp::Mod, modulus);
+        let max = builder.numeric_constant(255u128, Type::unsigned(8));
+        let v2 = builder.insert_binary(v0, BinaryOp::Mod, max);
+
+        // Every u8 is already reduced modulo 256, but not modulo 255
+        assert_eq!(v1, v0);
+        assert_ne!(v2, v0);
+        assert_eq!(builder.current_function.dfg[builder.current_block].instructions().len(), 1);
+    }
+
+    #[test]
+    fn branches_of_different_widths_are_cast_at_merge() {
+        // fn main f0 {
+        //   b0(v0: u1, v1: u8, v2: u16):
+        //     jmpif v0 then: b1, else: b2
+        //   b1():
+        //     v4 = cast v1 as u16
+        //     jmp b3(v4)
+        //   b2():
+        //     jmp b3(v2)
+        //   b3(v3: u16):
+        //     return v3
+        // }
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::bool());
+        let v1 = builder.add_parameter(Type::unsigned(8));
+        let v2 = builder.add_parameter(Type::unsigned(16));
+
+        let b1 = builder.insert_block();
+        let b2 = builder.insert_block();
+        let b3 = builder.insert_block();
+        let v3 = builder.add_block_parameter(b3, Type::unsigned(16));
+        builder.terminate_with_jmpif(v0, b1, b2);
+
+        builder.switch_to_block(b1);
+        builder.terminate_with_jmp_and_casts(b3, vec![v1]);
+
+        builder.switch_to_block(b2);
+        builder.terminate_with_jmp_and_casts(b3, vec![v2]);
+
+        builder.switch_to_block(b3);
+        builder.terminate_with_return(vec![v3]);
+
+        let ssa = builder.finish();
+        let main = ssa.main();
+
+        let instructions = main.dfg[b1].instructions();
+        assert_eq!(instructions.len(), 1);
+        assert!(matches!(
+            &main.dfg[instructions[0]],
+            Instruction::Cast(value, typ) if *value == v1 && *typ == Type::unsigned(16)
+        ));
+        assert!(main.dfg[b2].instructions().is_empty());
+
+        let number = InterpreterValue::numeric;
+        let arguments = vec![number(1), number(200), number(1000)];
+        assert_eq!(interpret(main, arguments), Ok(vec![number(200)]));
+    }
+
+    #[test]
+    fn sort_of_constant_array_is_folded() {
+        // fn func() -> [u32; 3] {
+        //   [3, 1, 2].sort()
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let elements =
+            [3u128, 1, 2].map(|element| builder.numeric_constant(element, Type::unsigned(32)));
+        let element_type = Rc::new(vec![Type::unsigned(32)]);
+        let array = builder.array_constant(elements.into_iter().collect(), element_type.clone());
+        let sort = builder.import_intrinsic_id(Intrinsic::Sort);
+        let array_type = Type::Array(element_type, 3);
+        let sorted = builder.insert_call(sort, vec![array], vec![array_type])[0];
+        builder.terminate_with_return(vec![sorted]);
+
+        let ssa = builder.finish();
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+
+        let (elements, _) = main.dfg.get_array_constant(sorted).expect("Expected a constant array");
+        let elements: Vec<_> = elements
+            .iter()
+            .map(|element| main.dfg.get_numeric_constant(*element).unwrap().to_u128())
+            .collect();
+        assert_eq!(elements, vec![1, 2, 3]);
+    }
 }
@@ -4,16 +4,18 @@ use std::sync::{Mutex, RwLock};
 
 use acvm::FieldElement;
 use iter_extended::vecmap;
+use noirc_errors::Location;
 use noirc_frontend::monomorphization::ast::{self, LocalId, Parameters};
 use noirc_frontend::monomorphization::ast::{FuncId, Program};
 use noirc_frontend::Signedness;
 
+use crate::errors::{RuntimeError, RuntimeErrorKind};
 use crate::ssa_refactor::ir::dfg::DataFlowGraph;
 use crate::ssa_refactor::ir::function::FunctionId as IrFunctionId;
 use crate::ssa_refactor::ir::function::{Function, RuntimeType};
 use crate::ssa_refactor::ir::instruction::BinaryOp;
 use crate::ssa_refactor::ir::map::AtomicCounter;
-use crate::ssa_refactor::ir::types::{NumericType, Type};
+use crate::ssa_refactor::ir::types::{CompositeType, NumericType, Type};
 use crate::ssa_refactor::ir::value::ValueId;
 use crate::ssa_refactor::ssa_builder::FunctionBuilder;
 
@@ -35,6 +37,10 @@ pub(super) struct FunctionContext<'a> {
 
     pub(super) builder: FunctionBuilder,
     shared_context: &'a SharedContext,
+
+    /// Errors found during codegen, such as an `assert_constant` on a value that is not
+    /// known at compile-time. These are reported once codegen finishes.
+    pub(super) errors: Vec<RuntimeError>,
 }
 
 /// Shared context for all functions during ssa codegen. This is the only
@@ -93,7 +99,8 @@ impl<'a> FunctionContext<'a> {
             .1;
 
         let builder = FunctionBuilder::new(function_name, function_id, runtime);
-        let mut this = Self { definitions: HashMap::new(), builder, shared_context };
+        let mut this =
+            Self { definitions: HashMap::new(), builder, shared_context, errors: Vec::new() };
         this.add_parameters_to_scope(parameters);
         this
     }
@@ -191,7 +198,7 @@ impl<'a> FunctionContext<'a> {
         match typ {
             ast::Type::Field => Type::field(),
             ast::Type::Array(len, element) => {
-                let element_types = Self::convert_type(element).flatten();
+                let element_types = Self::convert_element_types(element);
                 Type::Array(Rc::new(element_types), *len as usize)
             }
             ast::Type::Integer(Signedness::Signed, bits) => Type::signed(*bits),
@@ -209,11 +216,48 @@ impl<'a> FunctionContext<'a> {
         }
     }
 
+    /// Returns the flattened types of a single element of an array with the given element type.
+    ///
+    /// Strings within an array are stored as their bytes so that each string is laid out
+    /// contiguously, with a stride equal to the length of the string.
+    pub(super) fn convert_element_types(element: &ast::Type) -> CompositeType {
+        match element {
+            ast::Type::String(len) => vec![Type::char(); *len as usize],
+            other => Self::convert_type(other).flatten(),
+        }
+    }
+
     /// Returns the unit value, represented as an empty tree of values
     pub(super) fn unit_value() -> Values {
         Values::empty()
     }
 
+    /// Returns a value of the given type with each numeric value within it set to zero,
+    /// preserving the structure of any tuples within.
+    pub(super) fn default_value(&mut self, typ: &ast::Type) -> Values {
+        Self::convert_type(typ).map(|typ| self.default_non_tuple_value(typ).into())
+    }
+
+    /// Returns a zeroed value of the given non-tuple type. Arrays are filled with a zeroed
+    /// value for each of their flattened elements.
+    fn default_non_tuple_value(&mut self, typ: Type) -> ValueId {
+        match typ {
+            Type::Numeric(_) => self.builder.numeric_constant(0u128, typ),
+            Type::Array(element_types, length) => {
+                let mut elements = im::Vector::new();
+                for _ in 0..length {
+                    for element_type in element_types.iter() {
+                        elements.push_back(self.default_non_tuple_value(element_type.clone()));
+                    }
+                }
+                self.builder.array_constant(elements, element_types)
+            }
+            Type::Reference | Type::Function => {
+                panic!("ICE: Cannot create a zeroed value of type {typ}")
+            }
+        }
+    }
+
     /// Insert a binary instruction at the end of the current block.
     /// Converts the form of the binary instruction as necessary
     /// (e.g. swapping arguments, inserting a not) to represent it in the IR.
@@ -259,6 +303,313 @@ impl<'a> FunctionContext<'a> {
         result.into()
     }
 
+    /// Insert a binary instruction as with `insert_binary`, additionally constraining an `Add`,
+    /// `Sub`, or `Mul` on integers not to overflow the range of its type, and the divisor of an
+    /// integer `Div` or `Mod` not to be zero.
+    ///
+    /// Fields legitimately wrap around their modulus and are left unchecked. Field division is
+    /// a multiplication by the inverse of the divisor, which already fails for a zero divisor
+    /// since it has no inverse.
+    pub(super) fn insert_checked_binary(
+        &mut self,
+        lhs: ValueId,
+        operator: noirc_frontend::BinaryOpKind,
+        rhs: ValueId,
+    ) -> Values {
+        use noirc_frontend::BinaryOpKind::*;
+
+        match (self.builder.type_of_value(lhs), operator) {
+            (
+                Type::Numeric(NumericType::Unsigned { .. } | NumericType::Signed { .. }),
+                Add | Subtract | Multiply,
+            ) => self.insert_overflow_checked_binary(lhs, operator, rhs),
+            (Type::Numeric(NumericType::Unsigned { .. }), Divide | Modulo) => {
+                self.constrain_non_zero(rhs);
+                self.insert_binary(lhs, operator, rhs)
+            }
+            (Type::Numeric(NumericType::Signed { bit_size }), Divide | Modulo) => {
+                self.constrain_non_zero(rhs);
+                self.insert_signed_division(lhs, operator, rhs, bit_size)
+            }
+            (Type::Numeric(NumericType::Signed { bit_size }), ShiftRight) => {
+                self.insert_signed_shift_right(lhs, rhs, bit_size)
+            }
+            (Type::Numeric(NumericType::Unsigned { bit_size }), ShiftLeft | ShiftRight) => {
+                self.insert_shift(lhs, operator, rhs, bit_size)
+            }
+            _ => self.insert_binary(lhs, operator, rhs),
+        }
+    }
+
+    /// Insert an `Add`, `Sub`, or `Mul` on integers, constraining its result not to overflow.
+    ///
+    /// The result is computed as usual, wrapping around the bit size of its type. The same
+    /// operation is then computed on the field elements equal to each operand, which cannot
+    /// wrap for operands within the range of an integer type. The two agree exactly when the
+    /// operation did not overflow. Comparing against a field computation rather than against a
+    /// truncation of the result keeps the check meaningful in Brillig, where the integer
+    /// operation itself already wraps.
+    fn insert_overflow_checked_binary(
+        &mut self,
+        lhs: ValueId,
+        operator: noirc_frontend::BinaryOpKind,
+        rhs: ValueId,
+    ) -> Values {
+        let result = self.insert_binary(lhs, operator, rhs).into_leaf().eval(self);
+
+        let lhs = self.insert_integer_to_field(lhs);
+        let rhs = self.insert_integer_to_field(rhs);
+        let expected = self.builder.insert_binary(lhs, convert_operator(operator), rhs);
+        let actual = self.insert_integer_to_field(result);
+        self.builder.insert_constrain_eq(actual, expected, None);
+        result.into()
+    }
+
+    /// Returns the field element equal to the given integer. A negative signed integer is
+    /// stored as its two's complement bit pattern, so `2^bit_size` is subtracted from it,
+    /// giving the field element congruent to the negative value.
+    fn insert_integer_to_field(&mut self, value: ValueId) -> ValueId {
+        let bit_size = match self.builder.type_of_value(value) {
+            Type::Numeric(NumericType::Signed { bit_size }) => bit_size,
+            _ => return self.builder.insert_cast(value, Type::field()),
+        };
+        let unsigned_type = Type::unsigned(bit_size);
+        let unsigned_value = self.builder.insert_cast(value, unsigned_type.clone());
+
+        let max_positive =
+            self.builder.numeric_constant(2u128.pow(bit_size - 1) - 1, unsigned_type);
+        let negative = self.builder.insert_binary(max_positive, BinaryOp::Lt, unsigned_value);
+        let negative = self.builder.insert_cast(negative, Type::field());
+
+        let modulus = self.builder.field_constant(2u128.pow(bit_size));
+        let offset = self.builder.insert_binary(negative, BinaryOp::Mul, modulus);
+        let value = self.builder.insert_cast(unsigned_value, Type::field());
+        self.builder.insert_binary(value, BinaryOp::Sub, offset)
+    }
+
+    /// Shifts the given unsigned integer left or right, with the high bits of a left shift
+    /// truncated to the bit size of its type.
+    ///
+    /// ACIR can only shift by a constant amount, so in ACIR functions a shift by an amount only
+    /// known at runtime is instead lowered to a multiplication or division by `2^rhs`. Shifting
+    /// by the bit size of the type or more results in zero.
+    fn insert_shift(
+        &mut self,
+        lhs: ValueId,
+        operator: noirc_frontend::BinaryOpKind,
+        rhs: ValueId,
+        bit_size: u32,
+    ) -> Values {
+        let is_constant = self.builder.current_function.dfg.is_constant(rhs);
+        if is_constant || self.builder.current_function.runtime() != RuntimeType::Acir {
+            return self.insert_binary(lhs, operator, rhs);
+        }
+
+        let typ = Type::unsigned(bit_size);
+        let power_of_two = self.insert_power_of_two(rhs, bit_size);
+        match operator {
+            noirc_frontend::BinaryOpKind::ShiftLeft => {
+                self.insert_binary(lhs, noirc_frontend::BinaryOpKind::Multiply, power_of_two)
+            }
+            noirc_frontend::BinaryOpKind::ShiftRight => {
+                // Divide by one rather than zero when shifting out every bit, then zero the result
+                let zero = self.builder.numeric_constant(0u128, typ.clone());
+                let out_of_range = self.builder.insert_binary(power_of_two, BinaryOp::Eq, zero);
+                let in_range = self.builder.insert_not(out_of_range);
+                let out_of_range = self.builder.insert_cast(out_of_range, typ.clone());
+                let in_range = self.builder.insert_cast(in_range, typ);
+
+                let divisor = self.builder.insert_binary(power_of_two, BinaryOp::Add, out_of_range);
+                let quotient = self.builder.insert_binary(lhs, BinaryOp::Div, divisor);
+                self.builder.insert_binary(quotient, BinaryOp::Mul, in_range).into()
+            }
+            other => unreachable!("ICE: {other} is not a shift"),
+        }
+    }
+
+    /// Returns `2^shift` as an unsigned integer of the given bit size, or zero if `shift` is at
+    /// least `bit_size`. This is computed without branching as the sum of `(shift == i) * 2^i`
+    /// for each `i` below `bit_size` that the type of `shift` can represent.
+    fn insert_power_of_two(&mut self, shift: ValueId, bit_size: u32) -> ValueId {
+        let typ = Type::unsigned(bit_size);
+        let shift_type = self.builder.type_of_value(shift);
+
+        let mut power_of_two = self.builder.numeric_constant(0u128, typ.clone());
+        for i in 0..bit_size {
+            if !shift_type.can_represent(FieldElement::from(i as u128)) {
+                break;
+            }
+            let i_value = self.builder.numeric_constant(i as u128, shift_type.clone());
+            let is_i = self.builder.insert_binary(shift, BinaryOp::Eq, i_value);
+            let is_i = self.builder.insert_cast(is_i, typ.clone());
+
+            let power = self.builder.numeric_constant(2u128.pow(i), typ.clone());
+            let term = self.builder.insert_binary(is_i, BinaryOp::Mul, power);
+            power_of_two = self.builder.insert_binary(power_of_two, BinaryOp::Add, term);
+        }
+        power_of_two
+    }
+
+    /// Shifting a signed integer right sign-extends it, so that `-8 >> 1 == -4`.
+    ///
+    /// For a negative integer this is computed as `!(!lhs >> rhs)`, where the inner shift is
+    /// an unsigned one. Both negations are a xor with a mask which is all ones if `lhs` is
+    /// negative and zero otherwise, so that no branch is required.
+    fn insert_signed_shift_right(&mut self, lhs: ValueId, rhs: ValueId, bit_size: u32) -> Values {
+        let unsigned_type = Type::unsigned(bit_size);
+        let lhs = self.builder.insert_cast(lhs, unsigned_type.clone());
+
+        let max_positive =
+            self.builder.numeric_constant(2u128.pow(bit_size - 1) - 1, unsigned_type.clone());
+        let negative = self.builder.insert_binary(max_positive, BinaryOp::Lt, lhs);
+        let negative = self.builder.insert_cast(negative, unsigned_type.clone());
+        let max = self.builder.numeric_constant(u128::MAX >> (128 - bit_size), unsigned_type);
+        let mask = self.builder.insert_binary(negative, BinaryOp::Mul, max);
+
+        let flipped = self.builder.insert_binary(lhs, BinaryOp::Xor, mask);
+        let shifted =
+            self.insert_shift(flipped, noirc_frontend::BinaryOpKind::ShiftRight, rhs, bit_size);
+        let shifted = shifted.into_leaf().eval(self);
+        let result = self.builder.insert_binary(shifted, BinaryOp::Xor, mask);
+        self.builder.insert_cast(result, Type::signed(bit_size)).into()
+    }
+
+    /// Constrains the given integer not to be zero.
+    fn constrain_non_zero(&mut self, value: ValueId) {
+        let zero = self.builder.numeric_constant(0u128, self.builder.type_of_value(value));
+        let is_zero = self.builder.insert_binary(value, BinaryOp::Eq, zero);
+        let is_non_zero = self.builder.insert_not(is_zero);
+        self.builder.insert_constrain(is_non_zero, None);
+    }
+
+    /// Constrains the given index to be less than the length of the given array.
+    ///
+    /// The length of an array is always known statically from its type, so a constant index
+    /// which is negative or out of range is reported as an error rather than constrained. No
+    /// constraint is inserted for a constant index within the array, or for an index whose type
+    /// cannot represent the length, since every such index is in range. A signed index is
+    /// compared as unsigned so that negative indices fail the constraint.
+    pub(super) fn constrain_index_in_bounds(
+        &mut self,
+        array: ValueId,
+        index: ValueId,
+        location: Location,
+    ) {
+        let length = match self.builder.type_of_value(array) {
+            Type::Array(_, length) => length as u128,
+            other => unreachable!("ICE: Expected an array to index, found {other}"),
+        };
+
+        let mut index_type = self.builder.type_of_value(index);
+        let dfg = &self.builder.current_function.dfg;
+        if let Some(constant) = dfg.get_numeric_constant(index) {
+            let in_range =
+                constant.try_to_u64().map_or(false, |constant| (constant as u128) < length);
+            let message = match index_type {
+                Type::Numeric(NumericType::Signed { bit_size })
+                    if constant.num_bits() == bit_size =>
+                {
+                    let max = u128::MAX >> (128 - bit_size);
+                    let magnitude = max - constant.to_u128() + 1;
+                    format!("Index -{magnitude} is negative")
+                }
+                _ if in_range => return,
+                _ => format!("Index {constant} is out of bounds for an array of length {length}"),
+            };
+            let kind = RuntimeErrorKind::UnstructuredError { message };
+            self.errors.push(RuntimeError::new(kind, Some(location)));
+            return;
+        }
+
+        let mut index = index;
+        if let Type::Numeric(NumericType::Signed { bit_size }) = index_type {
+            index_type = Type::unsigned(bit_size);
+            index = self.builder.insert_cast(index, index_type.clone());
+        }
+
+        if !index_type.can_represent(length.into()) {
+            return;
+        }
+
+        // Brillig has no ordering comparison of fields, so field indices are left unchecked there
+        let is_brillig = self.builder.current_function.runtime() == RuntimeType::Brillig;
+        if is_brillig && index_type == Type::field() {
+            return;
+        }
+
+        let length = self.builder.numeric_constant(length, index_type);
+        let in_bounds = self.builder.insert_binary(index, BinaryOp::Lt, length);
+        self.builder.insert_constrain(in_bounds, Some(location));
+    }
+
+    /// Signed division truncates toward zero, so `-7 / 2 == -3` and `-7 % 2 == -1`.
+    ///
+    /// Both operands are split into their sign and magnitude, and the magnitudes are divided as
+    /// unsigned integers. The quotient is negative if exactly one operand is, while the
+    /// remainder takes the sign of `lhs`.
+    fn insert_signed_division(
+        &mut self,
+        lhs: ValueId,
+        operator: noirc_frontend::BinaryOpKind,
+        rhs: ValueId,
+        bit_size: u32,
+    ) -> Values {
+        let (lhs_negative, lhs_magnitude) = self.insert_sign_and_magnitude(lhs, bit_size);
+        let (rhs_negative, rhs_magnitude) = self.insert_sign_and_magnitude(rhs, bit_size);
+
+        let (result, negative) = match operator {
+            noirc_frontend::BinaryOpKind::Divide => {
+                let quotient =
+                    self.builder.insert_binary(lhs_magnitude, BinaryOp::Div, rhs_magnitude);
+                let negative =
+                    self.builder.insert_binary(lhs_negative, BinaryOp::Xor, rhs_negative);
+                (quotient, negative)
+            }
+            noirc_frontend::BinaryOpKind::Modulo => {
+                let remainder =
+                    self.builder.insert_binary(lhs_magnitude, BinaryOp::Mod, rhs_magnitude);
+                (remainder, lhs_negative)
+            }
+            other => unreachable!("ICE: {other} is not a division"),
+        };
+
+        let result = self.insert_conditional_negation(result, negative, bit_size);
+        self.builder.insert_cast(result, Type::signed(bit_size)).into()
+    }
+
+    /// Returns whether the given signed integer is negative, along with its magnitude as an
+    /// unsigned integer of the same bit size.
+    fn insert_sign_and_magnitude(&mut self, value: ValueId, bit_size: u32) -> (ValueId, ValueId) {
+        let unsigned_type = Type::unsigned(bit_size);
+        let value = self.builder.insert_cast(value, unsigned_type.clone());
+
+        let max_positive =
+            self.builder.numeric_constant(2u128.pow(bit_size - 1) - 1, unsigned_type);
+        let negative = self.builder.insert_binary(max_positive, BinaryOp::Lt, value);
+        let magnitude = self.insert_conditional_negation(value, negative, bit_size);
+        (negative, magnitude)
+    }
+
+    /// Returns the two's complement negation of the given unsigned integer if `negate` is true,
+    /// or the integer itself otherwise. This is computed without branching as
+    /// `value + negate * (2^bit_size - 2 * value)`, reduced modulo `2^bit_size`.
+    fn insert_conditional_negation(
+        &mut self,
+        value: ValueId,
+        negate: ValueId,
+        bit_size: u32,
+    ) -> ValueId {
+        let value = self.builder.insert_cast(value, Type::field());
+        let negate = self.builder.insert_cast(negate, Type::field());
+
+        let modulus = self.builder.field_constant(2u128.pow(bit_size));
+        let twice = self.builder.insert_binary(value, BinaryOp::Add, value);
+        let difference = self.builder.insert_binary(modulus, BinaryOp::Sub, twice);
+        let adjustment = self.builder.insert_binary(negate, BinaryOp::Mul, difference);
+        let result = self.builder.insert_binary(value, BinaryOp::Add, adjustment);
+        self.builder.insert_cast(result, Type::unsigned(bit_size))
+    }
+
     /// The frontend claims to support equality (==) on arrays, so we must support it in SSA here.
     /// The actual BinaryOp::Eq in SSA is meant only for primitive numeric types so we encode an
     /// entire equality loop on each array element. The generated IR is as follows:
@@ -281,6 +632,9 @@ impl<'a> FunctionContext<'a> {
     ///   jmp loop_start(v6)
     /// loop_end():
     ///   result = load result_alloc
+    ///
+    /// If both arrays are constant, such as two string literals, or their lengths differ,
+    /// the comparison is instead folded to a constant.
     fn insert_array_equality(
         &mut self,
         lhs: ValueId,
@@ -300,12 +654,18 @@ impl<'a> FunctionContext<'a> {
                     "== is unimplemented for arrays of structs"
                 );
                 assert_eq!(lhs_composite_type[0], rhs_composite_type[0]);
-                assert_eq!(lhs_length, rhs_length, "Expected two arrays of equal length");
+                if lhs_length != rhs_length {
+                    return self.array_equality_constant(false, operator);
+                }
                 (lhs_length, lhs_composite_type[0].clone())
             }
             _ => unreachable!("Expected two array values"),
         };
 
+        if let Some(equal) = self.constant_arrays_equal(lhs, rhs) {
+            return self.array_equality_constant(equal, operator);
+        }
+
         let loop_start = self.builder.insert_block();
         let loop_body = self.builder.insert_block();
         let loop_end = self.builder.insert_block();
@@ -346,20 +706,58 @@ impl<'a> FunctionContext<'a> {
         result.into()
     }
 
+    /// Compares two arrays of equal length at compile-time.
+    /// Returns None if any element of either array is not a numeric constant.
+    fn constant_arrays_equal(&self, lhs: ValueId, rhs: ValueId) -> Option<bool> {
+        let dfg = &self.builder.current_function.dfg;
+        let (lhs, _) = dfg.get_array_constant(lhs)?;
+        let (rhs, _) = dfg.get_array_constant(rhs)?;
+
+        let mut equal = true;
+        for (lhs, rhs) in lhs.iter().zip(rhs.iter()) {
+            equal &= dfg.get_numeric_constant(*lhs)? == dfg.get_numeric_constant(*rhs)?;
+        }
+        Some(equal)
+    }
+
+    /// Returns the result of an array comparison known at compile-time, which is `equal`
+    /// for `==` and its negation for `!=`.
+    fn array_equality_constant(
+        &mut self,
+        equal: bool,
+        operator: noirc_frontend::BinaryOpKind,
+    ) -> Values {
+        let result = equal != operator_requires_not(operator);
+        self.builder.numeric_constant(result as u128, Type::bool()).into()
+    }
+
     /// Inserts a call instruction at the end of the current block and returns the results
     /// of the call.
     ///
     /// Compared to self.builder.insert_call, this version will reshape the returned Vec<ValueId>
     /// back into a Values tree of the proper shape.
+    ///
+    /// Calls to the `array_get_or`, `saturating_add`, `saturating_sub`, `bit_length`, `abs`, and
+    /// `cswap` intrinsics are expanded into other instructions instead of being inserted as a
+    /// call.
     pub(super) fn insert_call(
         &mut self,
         function: ValueId,
         arguments: Vec<ValueId>,
         result_type: &ast::Type,
     ) -> Values {
+        if let Some(results) = self.expand_intrinsic_call(function, &arguments) {
+            return Self::reshape_results(result_type, &results);
+        }
         let result_types = Self::convert_type(result_type).flatten();
         let results = self.builder.insert_call(function, arguments, result_types);
+        Self::reshape_results(result_type, &results)
+    }
 
+    /// Rebuilds the tree of values described by `result_type` from the flattened results
+    /// of a call. This is needed for any call returning a tuple, including intrinsics such
+    /// as divmod whose results are a (quotient, remainder) pair.
+    pub(super) fn reshape_results(result_type: &ast::Type, results: &[ValueId]) -> Values {
         let mut i = 0;
         let reshaped_return_values = Self::map_type(result_type, |_| {
             let result = results[i].into();
@@ -467,7 +865,9 @@ impl<'a> FunctionContext<'a> {
     pub(super) fn extract_current_value(&mut self, lvalue: &ast::LValue) -> LValue {
         match lvalue {
             ast::LValue::Ident(ident) => LValue::Ident(self.ident_lvalue(ident)),
-            ast::LValue::Index { array, index, .. } => self.index_lvalue(array, index).2,
+            ast::LValue::Index { array, index, location, .. } => {
+                self.index_lvalue(array, index, *location).2
+            }
             ast::LValue::MemberAccess { object, field_index } => {
                 let (old_object, object_lvalue) = self.extract_current_value_recursive(object);
                 let object_lvalue = Box::new(object_lvalue);
@@ -491,11 +891,13 @@ impl<'a> FunctionContext<'a> {
         &mut self,
         array: &ast::LValue,
         index: &ast::Expression,
+        location: Location,
     ) -> (ValueId, ValueId, LValue) {
         let (old_array, array_lvalue) = self.extract_current_value_recursive(array);
         let old_array = old_array.into_leaf().eval(self);
         let array_lvalue = Box::new(array_lvalue);
         let index = self.codegen_non_tuple_expression(index);
+        self.constrain_index_in_bounds(old_array, index, location);
         (old_array, index, LValue::Index { old_array, index, array_lvalue })
     }
 
@@ -505,8 +907,8 @@ impl<'a> FunctionContext<'a> {
                 let variable = self.ident_lvalue(ident);
                 (variable.clone(), LValue::Ident(variable))
             }
-            ast::LValue::Index { array, index, element_type, location: _ } => {
-                let (old_array, index, index_lvalue) = self.index_lvalue(array, index);
+            ast::LValue::Index { array, index, element_type, location } => {
+                let (old_array, index, index_lvalue) = self.index_lvalue(array, index, *location);
                 let element = self.codegen_array_index(old_array, index, element_type);
                 (element, index_lvalue)
             }
@@ -683,23 +1085,23 @@ impl SharedContext {
     /// Return the matching id for the given function if known. If it is not known this
     /// will add the function to the queue of functions to compile, assign it a new id,
     /// and return this new id.
+    ///
+    /// A function is reserved an id on its first reference, before its body is generated, so
+    /// recursive and mutually recursive calls reuse that id and each function is queued once.
     pub(super) fn get_or_queue_function(&self, id: ast::FuncId) -> IrFunctionId {
-        // Start a new block to guarantee the destructor for the map lock is released
-        // before map needs to be aquired again in self.functions.write() below
-        {
-            let map = self.functions.read().expect("Failed to read self.functions");
-            if let Some(existing_id) = map.get(&id) {
-                return *existing_id;
-            }
+        // Hold the write lock while checking for an existing id so that no two callers can
+        // both find the function missing and queue it twice.
+        let mut map = self.functions.write().expect("Failed to write to self.functions");
+        if let Some(existing_id) = map.get(&id) {
+            return *existing_id;
         }
 
         let next_id = self.function_counter.next();
+        map.insert(id, next_id);
 
         let mut queue = self.function_queue.lock().expect("Failed to lock function queue");
         queue.push((id, next_id));
 
-        self.functions.write().expect("Failed to write to self.functions").insert(id, next_id);
-
         next_id
     }
 }
@@ -710,3 +1112,625 @@ pub(super) enum LValue {
     Index { old_array: ValueId, index: ValueId, array_lvalue: Box<LValue> },
     MemberAccess { old_object: Values, index: usize, object_lvalue: Box<LValue> },
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use acvm::FieldElement;
+    use iter_extended::vecmap;
+    use noirc_frontend::{monomorphization::ast, BinaryOpKind};
+
+    use crate::{
+        brillig::brillig_gen::convert_ssa_function,
+        errors::RuntimeErrorKind,
+        ssa_refactor::{
+            ir::{
+                function::RuntimeType,
+                instruction::{Binary, BinaryOp, Instruction, Intrinsic},
+                interpreter::{interpret, InterpreterError, InterpreterValue},
+                map::Id,
+                types::Type,
+                value::{Value as IrValue, ValueId},
+            },
+            ssa_builder::FunctionBuilder,
+            ssa_gen::{
+                test_utils::{
+                    binary, codegen_main, empty_shared_context, field, function, function_call,
+                    function_ident, generate_functions, literal, local_ident, location,
+                    main_context, parameter, signed, unsigned,
+                },
+                Ssa,
+            },
+        },
+    };
+
+    use super::{FunctionContext, Tree, Value};
+
+    fn string_literal(context: &mut FunctionContext, string: &str) -> ValueId {
+        let elements = string
+            .bytes()
+            .map(|byte| context.builder.numeric_constant(byte as u128, Type::field()))
+            .collect();
+        context.builder.array_constant(elements, Rc::new(vec![Type::char()]))
+    }
+
+    #[test]
+    fn divmod_results_are_a_tuple() {
+        // fn func(v0: u32, v1: u32) {
+        //   b0(v0: u32, v1: u32):
+        //     v2, v3 = call divmod(v0, v1)
+        // }
+        let func_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("func".into(), func_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::unsigned(32));
+        let v1 = builder.add_parameter(Type::unsigned(32));
+
+        let divmod = builder.import_intrinsic_id(Intrinsic::DivMod);
+        let result_types = vec![Type::unsigned(32), Type::unsigned(32)];
+        let results = builder.insert_call(divmod, vec![v0, v1], result_types).to_vec();
+        assert_eq!(results.len(), 2);
+
+        let return_type = ast::Type::Tuple(vec![unsigned(32), unsigned(32)]);
+        let fields = match FunctionContext::reshape_results(&return_type, &results) {
+            Tree::Branch(fields) => fields,
+            Tree::Leaf(_) => panic!("Expected the results of divmod to be a tuple"),
+        };
+        assert_eq!(fields.len(), 2);
+        for (field, result) in fields.into_iter().zip(results) {
+            match field {
+                Tree::Leaf(Value::Normal(value)) => assert_eq!(value, result),
+                other => panic!("Expected a single normal value, found {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn constant_string_equality_is_folded() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let abc = string_literal(&mut context, "abc");
+        let other_abc = string_literal(&mut context, "abc");
+        let abd = string_literal(&mut context, "abd");
+        let abcd = string_literal(&mut context, "abcd");
+
+        let mut compare = |lhs, operator, rhs| {
+            let result = context.insert_binary(lhs, operator, rhs).into_leaf().eval(&mut context);
+            context.builder.current_function.dfg.get_numeric_constant(result)
+        };
+
+        assert_eq!(compare(abc, BinaryOpKind::Equal, other_abc), Some(FieldElement::one()));
+        assert_eq!(compare(abc, BinaryOpKind::Equal, abd), Some(FieldElement::zero()));
+        assert_eq!(compare(abc, BinaryOpKind::NotEqual, abd), Some(FieldElement::one()));
+        assert_eq!(compare(abc, BinaryOpKind::Equal, abcd), Some(FieldElement::zero()));
+
+        // No comparison loop should have been generated
+        let function = &context.builder.current_function;
+        assert_eq!(function.reachable_blocks().len(), 1);
+    }
+
+    #[test]
+    fn comparing_a_value_with_itself_is_folded() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let x = context.builder.add_parameter(Type::unsigned(32));
+
+        let mut compare = |operator| {
+            let result = context.insert_binary(x, operator, x).into_leaf().eval(&mut context);
+            context.builder.current_function.dfg.get_numeric_constant(result)
+        };
+
+        assert_eq!(compare(BinaryOpKind::Equal), Some(FieldElement::one()));
+        assert_eq!(compare(BinaryOpKind::NotEqual), Some(FieldElement::zero()));
+
+        let block = context.builder.current_block();
+        assert!(context.builder.current_function.dfg[block].instructions().is_empty());
+    }
+
+    #[test]
+    fn runtime_string_equality_compares_bytes() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let string_type = Type::Array(Rc::new(vec![Type::char()]), 3);
+        let v0 = context.builder.add_parameter(string_type);
+        let abc = string_literal(&mut context, "abc");
+
+        let result =
+            context.insert_binary(v0, BinaryOpKind::Equal, abc).into_leaf().eval(&mut context);
+        let function = &context.builder.current_function;
+        assert_eq!(function.dfg.get_numeric_constant(result), None);
+
+        // The entry block, loop header, loop body, and loop exit
+        assert_eq!(function.reachable_blocks().len(), 4);
+    }
+
+    #[test]
+    fn tuple_parameter_resolves_to_each_field() {
+        let x = ast::LocalId(0);
+        let tuple_type = ast::Type::Tuple(vec![ast::Type::Field, ast::Type::Bool]);
+        let shared_context = empty_shared_context();
+        let mut context =
+            main_context(&vec![parameter(x, "x", tuple_type.clone())], &shared_context);
+
+        let fields = match context.codegen_expression(&local_ident(x, "x", tuple_type)) {
+            Tree::Branch(fields) => fields,
+            Tree::Leaf(_) => panic!("Expected a tuple parameter to resolve to a tuple"),
+        };
+
+        let function = &context.builder.current_function;
+        let parameters = function.dfg.block_parameters(function.entry_block());
+        assert_eq!(fields.len(), 2);
+        for (field, parameter) in fields.into_iter().zip(parameters) {
+            match field {
+                Tree::Leaf(Value::Normal(value)) => assert_eq!(value, *parameter),
+                other => panic!("Expected a single normal value, found {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn function_ident_resolves_to_a_function_value() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let definition = ast::Definition::Function(ast::FuncId(1));
+        let ident = function_ident(definition, "foo", Vec::new(), ast::Type::Unit);
+        let function = context.codegen_non_tuple_expression(&ident);
+        let dfg = &context.builder.current_function.dfg;
+        assert!(matches!(dfg[function], IrValue::Function(_)));
+
+        // The function is queued to be compiled once referenced
+        assert!(shared_context.pop_next_function_in_queue().is_some());
+    }
+
+    #[test]
+    fn generated_functions_are_ordered_by_discovery() {
+        // fn main() {
+        //     second();
+        //     first()
+        // }
+        // fn first() {}
+        // fn second() {}
+        let empty_function = |id, name| {
+            function(id, name, Vec::new(), ast::Expression::Block(Vec::new()), ast::Type::Unit)
+        };
+        let main_body = ast::Expression::Block(vec![
+            ast::Expression::Semi(Box::new(function_call(2, Vec::new(), ast::Type::Unit))),
+            function_call(1, Vec::new(), ast::Type::Unit),
+        ]);
+        let main = function(0, "main", Vec::new(), main_body, ast::Type::Unit);
+        let ssa =
+            generate_functions(vec![main, empty_function(1, "first"), empty_function(2, "second")]);
+
+        // Main is generated first, and every other function is numbered in the order its
+        // first use was found rather than by its id in the monomorphized program.
+        assert_eq!(ssa.main_id, Id::test_new(0));
+        let names = vecmap(ssa.functions.values(), |function| function.name().to_owned());
+        assert_eq!(names, vec!["main", "second", "first"]);
+    }
+
+    #[test]
+    fn calls_target_the_function_reserved_for_them() {
+        // fn main() {
+        //     first();
+        //     second()
+        // }
+        // fn first() { third() }
+        // fn second() {}
+        // fn third() {}
+        let call = |id| function_call(id, Vec::new(), ast::Type::Unit);
+        let unit_function = |id, name, body| function(id, name, Vec::new(), body, ast::Type::Unit);
+        let ssa = generate_functions(vec![
+            unit_function(
+                0,
+                "main",
+                ast::Expression::Block(vec![ast::Expression::Semi(Box::new(call(1))), call(2)]),
+            ),
+            unit_function(1, "first", call(3)),
+            unit_function(2, "second", ast::Expression::Block(Vec::new())),
+            unit_function(3, "third", ast::Expression::Block(Vec::new())),
+        ]);
+
+        // Each function is built with the id handed out when it was queued,
+        // so every call resolves to the function it was made to.
+        let mut calls = Vec::new();
+        for (id, function) in &ssa.functions {
+            assert_eq!(function.id(), *id);
+            let dfg = &function.dfg;
+            for instruction in dfg[function.entry_block()].instructions() {
+                if let Instruction::Call { func, .. } = &dfg[*instruction] {
+                    let callee = match &dfg[*func] {
+                        IrValue::Function(callee) => callee,
+                        other => panic!("Expected a call to a known function, found {other:?}"),
+                    };
+                    calls.push((
+                        function.name().to_owned(),
+                        ssa.functions[callee].name().to_owned(),
+                    ));
+                }
+            }
+        }
+        let expected = [("main", "first"), ("main", "second"), ("first", "third")];
+        assert_eq!(
+            calls,
+            vecmap(expected, |(caller, callee)| (caller.to_owned(), callee.to_owned()))
+        );
+    }
+
+    #[test]
+    fn recursive_functions_are_generated_once() {
+        // fn main() { helper() }
+        // fn helper() { main(); helper() }
+        let call = |id| function_call(id, Vec::new(), ast::Type::Unit);
+        let helper_body =
+            ast::Expression::Block(vec![ast::Expression::Semi(Box::new(call(0))), call(1)]);
+        let ssa = generate_functions(vec![
+            function(0, "main", Vec::new(), call(1), ast::Type::Unit),
+            function(1, "helper", Vec::new(), helper_body, ast::Type::Unit),
+        ]);
+        assert_eq!(ssa.functions.len(), 2);
+
+        // main is always f0, and helper is reserved f1 when main first calls it. Its call
+        // back to main and its call to itself reuse those ids.
+        let names = vecmap(&ssa.functions, |(id, function)| (*id, function.name().to_owned()));
+        assert_eq!(
+            names,
+            vec![(Id::test_new(0), "main".to_owned()), (Id::test_new(1), "helper".to_owned())]
+        );
+
+        let helper = &ssa.functions[&Id::test_new(1)];
+        let callees = vecmap(helper.dfg[helper.entry_block()].instructions(), |instruction| {
+            match &helper.dfg[*instruction] {
+                Instruction::Call { func, .. } => helper.dfg[*func].clone(),
+                other => panic!("Expected only calls, found {other:?}"),
+            }
+        });
+        assert_eq!(
+            callees,
+            vec![IrValue::Function(Id::test_new(0)), IrValue::Function(Id::test_new(1))]
+        );
+    }
+
+    #[test]
+    fn generic_array_lengths_are_concrete_per_instantiation() {
+        // fn main() {
+        //     ignore([1, 2]);
+        //     ignore([1, 2, 3])
+        // }
+        // fn ignore<N>(_array: [Field; N]) {}
+        //
+        // Monomorphization produces one copy of `ignore` for each length.
+        let array = |len: u128| {
+            let contents = vecmap(1..=len, field);
+            ast::Expression::Literal(ast::Literal::Array(ast::ArrayLiteral {
+                contents,
+                element_type: ast::Type::Field,
+            }))
+        };
+        let ignore = |id, len| {
+            let array_type = ast::Type::Array(len, Box::new(ast::Type::Field));
+            let parameters = vec![parameter(ast::LocalId(0), "_array", array_type)];
+            let body = ast::Expression::Block(Vec::new());
+            function(id, &format!("ignore${len}"), parameters, body, ast::Type::Unit)
+        };
+        let main_body = ast::Expression::Block(vec![
+            ast::Expression::Semi(Box::new(function_call(1, vec![array(2)], ast::Type::Unit))),
+            function_call(2, vec![array(3)], ast::Type::Unit),
+        ]);
+        let main = function(0, "main", Vec::new(), main_body, ast::Type::Unit);
+        let ssa = generate_functions(vec![main, ignore(1, 2), ignore(2, 3)]);
+
+        for (id, len) in [(1, 2), (2, 3)] {
+            let function = &ssa.functions[&Id::test_new(id)];
+            assert_eq!(function.name(), format!("ignore${len}"));
+
+            let parameter_types =
+                vecmap(function.parameters(), |parameter| function.dfg.type_of_value(*parameter));
+            assert_eq!(
+                parameter_types,
+                vec![Type::Array(Rc::new(vec![Type::field()]), len as usize)]
+            );
+        }
+    }
+
+    /// Codegens a function returning `a <operator> b` for two parameters of the given type.
+    fn codegen_binary_of_parameters(operator: BinaryOpKind, typ: ast::Type) -> Ssa {
+        let (a, b) = (ast::LocalId(0), ast::LocalId(1));
+        let parameters = vec![parameter(a, "a", typ.clone()), parameter(b, "b", typ.clone())];
+        let body = binary(local_ident(a, "a", typ.clone()), operator, local_ident(b, "b", typ));
+        codegen_main(&parameters, &body).0
+    }
+
+    fn interpret_unsigned_binary(
+        operator: BinaryOpKind,
+        bit_size: u32,
+        a: u128,
+        b: u128,
+    ) -> Result<Vec<InterpreterValue>, InterpreterError> {
+        let ssa = codegen_binary_of_parameters(operator, unsigned(bit_size));
+        interpret(ssa.main(), vec![InterpreterValue::numeric(a), InterpreterValue::numeric(b)])
+    }
+
+    /// Interprets `a <operator> b` on two i8 parameters, which are passed and returned as their
+    /// two's complement bit patterns.
+    fn interpret_signed_binary(
+        operator: BinaryOpKind,
+        a: i8,
+        b: i8,
+    ) -> Result<Vec<InterpreterValue>, InterpreterError> {
+        let ssa = codegen_binary_of_parameters(operator, signed(8));
+        let arguments = vec![
+            InterpreterValue::numeric(a as u8 as u128),
+            InterpreterValue::numeric(b as u8 as u128),
+        ];
+        interpret(ssa.main(), arguments)
+    }
+
+    fn unsigned_result(value: u128) -> Result<Vec<InterpreterValue>, InterpreterError> {
+        Ok(vec![InterpreterValue::numeric(value)])
+    }
+
+    fn signed_result(value: i8) -> Result<Vec<InterpreterValue>, InterpreterError> {
+        Ok(vec![InterpreterValue::numeric(value as u8 as u128)])
+    }
+
+    #[test]
+    fn unsigned_arithmetic_is_checked_for_overflow() {
+        use BinaryOpKind::*;
+        let overflow = Err(InterpreterError::ConstraintFailed);
+
+        assert_eq!(interpret_unsigned_binary(Add, 8, 200, 55), unsigned_result(255));
+        assert_eq!(interpret_unsigned_binary(Add, 8, 200, 56), overflow);
+
+        assert_eq!(interpret_unsigned_binary(Subtract, 8, 2, 2), unsigned_result(0));
+        assert_eq!(interpret_unsigned_binary(Subtract, 8, 2, 3), overflow);
+
+        assert_eq!(interpret_unsigned_binary(Multiply, 8, 15, 17), unsigned_result(255));
+        assert_eq!(interpret_unsigned_binary(Multiply, 8, 16, 16), overflow);
+
+        // Booleans overflow as soon as both operands are set
+        assert_eq!(interpret_unsigned_binary(Add, 1, 1, 0), unsigned_result(1));
+        assert_eq!(interpret_unsigned_binary(Add, 1, 1, 1), overflow);
+        assert_eq!(interpret_unsigned_binary(Subtract, 1, 0, 1), overflow);
+        assert_eq!(interpret_unsigned_binary(Multiply, 1, 1, 1), unsigned_result(1));
+    }
+
+    #[test]
+    fn signed_arithmetic_is_checked_for_overflow() {
+        use BinaryOpKind::*;
+        let overflow = Err(InterpreterError::ConstraintFailed);
+
+        assert_eq!(interpret_signed_binary(Add, 100, 27), signed_result(127));
+        assert_eq!(interpret_signed_binary(Add, 100, 28), overflow);
+        assert_eq!(interpret_signed_binary(Add, -100, -28), signed_result(-128));
+        assert_eq!(interpret_signed_binary(Add, -100, -29), overflow);
+
+        assert_eq!(interpret_signed_binary(Subtract, -100, 28), signed_result(-128));
+        assert_eq!(interpret_signed_binary(Subtract, -100, 29), overflow);
+        assert_eq!(interpret_signed_binary(Subtract, 27, -100), signed_result(127));
+        assert_eq!(interpret_signed_binary(Subtract, 28, -100), overflow);
+
+        assert_eq!(interpret_signed_binary(Multiply, -16, 8), signed_result(-128));
+        assert_eq!(interpret_signed_binary(Multiply, 16, 8), overflow);
+        assert_eq!(interpret_signed_binary(Multiply, -1, -1), signed_result(1));
+        assert_eq!(interpret_signed_binary(Multiply, -128, -1), overflow);
+    }
+
+    #[test]
+    fn brillig_overflow_checks_compare_against_field_arithmetic() {
+        // Brillig wraps integer arithmetic itself, so comparing the result with its truncation
+        // would always succeed. Check that `a + b` on booleans compares two field elements.
+        let (a, b) = (ast::LocalId(0), ast::LocalId(1));
+        let parameters = vec![parameter(a, "a", unsigned(1)), parameter(b, "b", unsigned(1))];
+
+        let shared_context = empty_shared_context();
+        let mut context =
+            FunctionContext::new("main".into(), &parameters, RuntimeType::Brillig, &shared_context);
+        let sum = binary(
+            local_ident(a, "a", unsigned(1)),
+            BinaryOpKind::Add,
+            local_ident(b, "b", unsigned(1)),
+        );
+        context.codegen_function_body(&sum);
+        let ssa = context.builder.finish();
+
+        let main = ssa.main();
+        let constraints: Vec<_> = main.dfg[main.entry_block()]
+            .instructions()
+            .iter()
+            .filter_map(|instruction| match main.dfg[*instruction] {
+                Instruction::ConstrainEqual(lhs, rhs) => Some((lhs, rhs)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(constraints.len(), 1);
+        let (actual, expected) = constraints[0];
+        assert_eq!(main.dfg.type_of_value(actual), Type::field());
+        assert_eq!(main.dfg.type_of_value(expected), Type::field());
+
+        let numeric = InterpreterValue::numeric;
+        assert_eq!(interpret(main, vec![numeric(0), numeric(1)]), unsigned_result(1));
+        let overflow = Err(InterpreterError::ConstraintFailed);
+        assert_eq!(interpret(main, vec![numeric(1), numeric(1)]), overflow);
+    }
+
+    #[test]
+    fn field_arithmetic_is_not_checked_for_overflow() {
+        let ssa = codegen_binary_of_parameters(BinaryOpKind::Add, ast::Type::Field);
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 1);
+        assert!(matches!(main.dfg[instructions[0]], Instruction::Binary(_)));
+    }
+
+    #[test]
+    fn integer_division_by_zero_fails() {
+        use BinaryOpKind::*;
+        let failed = Err(InterpreterError::ConstraintFailed);
+
+        assert_eq!(interpret_unsigned_binary(Divide, 32, 7, 2), unsigned_result(3));
+        assert_eq!(interpret_unsigned_binary(Divide, 32, 7, 0), failed);
+        assert_eq!(interpret_unsigned_binary(Modulo, 32, 7, 2), unsigned_result(1));
+        assert_eq!(interpret_unsigned_binary(Modulo, 32, 7, 0), failed);
+        assert_eq!(interpret_signed_binary(Divide, -7, 0), failed);
+    }
+
+    #[test]
+    fn signed_division_truncates_toward_zero() {
+        use BinaryOpKind::*;
+        assert_eq!(interpret_signed_binary(Divide, -7, 2), signed_result(-3));
+        assert_eq!(interpret_signed_binary(Divide, 7, -2), signed_result(-3));
+        assert_eq!(interpret_signed_binary(Divide, -7, -2), signed_result(3));
+        assert_eq!(interpret_signed_binary(Modulo, -7, 2), signed_result(-1));
+        assert_eq!(interpret_signed_binary(Modulo, 7, -2), signed_result(1));
+        assert_eq!(interpret_signed_binary(Divide, -128, 2), signed_result(-64));
+    }
+
+    #[test]
+    fn constant_shifts_fold_by_type() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        // 1u32 << 3 == 8, and 255u8 << 1 == 254 since the high bit is truncated. The bit
+        // pattern 248 is -8i8, and -8i8 >> 1 == -4i8, whose bit pattern is 252.
+        let cases = [
+            (1u128, BinaryOpKind::ShiftLeft, 3u128, unsigned(32), 8u128),
+            (255, BinaryOpKind::ShiftLeft, 1, unsigned(8), 254),
+            (248, BinaryOpKind::ShiftRight, 1, signed(8), 252),
+            (248, BinaryOpKind::ShiftRight, 1, unsigned(8), 124),
+        ];
+        for (value, operator, amount, typ, expected) in cases {
+            let shift = binary(literal(value, typ.clone()), operator, literal(amount, typ));
+            let result = context.codegen_expression(&shift).into_leaf().eval(&mut context);
+            let dfg = &context.builder.current_function.dfg;
+            assert_eq!(dfg.get_numeric_constant(result), Some(FieldElement::from(expected)));
+        }
+        let block = context.builder.current_block();
+        assert!(context.builder.current_function.dfg[block].instructions().is_empty());
+    }
+
+    #[test]
+    fn dynamic_shifts_are_lowered_for_acir() {
+        use BinaryOpKind::*;
+        assert_eq!(interpret_unsigned_binary(ShiftLeft, 32, 1, 3), unsigned_result(8));
+        assert_eq!(interpret_unsigned_binary(ShiftLeft, 8, 255, 1), unsigned_result(254));
+        assert_eq!(interpret_unsigned_binary(ShiftLeft, 8, 1, 8), unsigned_result(0));
+        assert_eq!(interpret_unsigned_binary(ShiftRight, 8, 200, 3), unsigned_result(25));
+        assert_eq!(interpret_unsigned_binary(ShiftRight, 8, 200, 9), unsigned_result(0));
+
+        // Signed values are sign extended as they are shifted right
+        assert_eq!(interpret_signed_binary(ShiftRight, -8, 1), signed_result(-4));
+        assert_eq!(interpret_signed_binary(ShiftRight, -1, 7), signed_result(-1));
+        assert_eq!(interpret_signed_binary(ShiftRight, 8, 1), signed_result(4));
+        assert_eq!(interpret_signed_binary(ShiftRight, -128, 7), signed_result(-1));
+
+        // ACIR has no instruction for shifting by an amount only known at runtime
+        let ssa = codegen_binary_of_parameters(ShiftLeft, unsigned(8));
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert!(instructions.iter().all(|instruction| !matches!(
+            main.dfg[*instruction],
+            Instruction::Binary(Binary { operator: BinaryOp::Shl, .. })
+        )));
+    }
+
+    /// Codegens `array[index]` within main, which takes `array: [Field; 3]` and `i: u32`,
+    /// returning the function along with the message of each error reported while generating it.
+    fn index_array_parameter(index: ast::Expression) -> (Ssa, Vec<String>) {
+        let array = ast::LocalId(0);
+        let array_type = ast::Type::Array(3, Box::new(ast::Type::Field));
+        let parameters = vec![
+            parameter(array, "array", array_type.clone()),
+            parameter(ast::LocalId(1), "i", unsigned(32)),
+        ];
+        let body = ast::Expression::Index(ast::Index {
+            collection: Box::new(local_ident(array, "array", array_type)),
+            index: Box::new(index),
+            element_type: ast::Type::Field,
+            location: location(),
+        });
+
+        let (ssa, errors) = codegen_main(&parameters, &body);
+        let errors = vecmap(errors, |error| match error.kind {
+            RuntimeErrorKind::UnstructuredError { message } => message,
+            other => other.to_string(),
+        });
+        (ssa, errors)
+    }
+
+    fn interpret_index(ssa: &Ssa, i: u128) -> Result<Vec<InterpreterValue>, InterpreterError> {
+        let array = vecmap(1..=3u128, InterpreterValue::numeric);
+        interpret(ssa.main(), vec![InterpreterValue::Array(array), InterpreterValue::numeric(i)])
+    }
+
+    fn count_constraints(ssa: &Ssa) -> usize {
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        instructions.iter().filter(|id| matches!(main.dfg[**id], Instruction::Constrain(_))).count()
+    }
+
+    #[test]
+    fn constant_index_out_of_range_is_rejected() {
+        // An index known to be in range needs no constraint
+        let (ssa, errors) = index_array_parameter(literal(2u128, unsigned(32)));
+        assert!(errors.is_empty());
+        assert_eq!(count_constraints(&ssa), 0);
+        assert_eq!(interpret_index(&ssa, 0), unsigned_result(3));
+
+        let (ssa, errors) = index_array_parameter(literal(3u128, unsigned(32)));
+        assert_eq!(errors, vec!["Index 3 is out of bounds for an array of length 3".to_owned()]);
+        assert_eq!(count_constraints(&ssa), 0);
+
+        // Signed integers are stored as their two's complement bit pattern, so -1 as an i32 is
+        // 2^32 - 1, which must not be mistaken for a large positive index
+        let (_, errors) = index_array_parameter(literal(u32::MAX as u128, signed(32)));
+        assert_eq!(errors, vec!["Index -1 is negative".to_owned()]);
+    }
+
+    #[test]
+    fn field_index_in_brillig_is_not_compared() {
+        // unconstrained fn main(array: [Field; 3], i: Field) -> Field {
+        //     array[i]
+        // }
+        let (array, i) = (ast::LocalId(0), ast::LocalId(1));
+        let array_type = ast::Type::Array(3, Box::new(ast::Type::Field));
+        let parameters = vec![
+            parameter(array, "array", array_type.clone()),
+            parameter(i, "i", ast::Type::Field),
+        ];
+        let body = ast::Expression::Index(ast::Index {
+            collection: Box::new(local_ident(array, "array", array_type)),
+            index: Box::new(local_ident(i, "i", ast::Type::Field)),
+            element_type: ast::Type::Field,
+            location: location(),
+        });
+
+        let shared_context = empty_shared_context();
+        let mut context =
+            FunctionContext::new("main".into(), &parameters, RuntimeType::Brillig, &shared_context);
+        context.codegen_function_body(&body);
+        assert!(context.errors.is_empty());
+        let ssa = context.builder.finish();
+
+        // The function still compiles to Brillig, which cannot compare fields
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        let is_comparison = |id: &_| {
+            matches!(main.dfg[*id], Instruction::Binary(Binary { operator: BinaryOp::Lt, .. }))
+        };
+        assert!(!instructions.iter().any(is_comparison));
+        convert_ssa_function(main);
+    }
+
+    #[test]
+    fn dynamic_index_is_bounds_checked() {
+        let (ssa, errors) = index_array_parameter(local_ident(ast::LocalId(1), "i", unsigned(32)));
+        assert!(errors.is_empty());
+        assert_eq!(count_constraints(&ssa), 1);
+
+        assert_eq!(interpret_index(&ssa, 2), unsigned_result(3));
+        assert_eq!(interpret_index(&ssa, 3), Err(InterpreterError::ConstraintFailed));
+    }
+}
@@ -0,0 +1,598 @@
+//! Lowerings of the intrinsics which are implemented in terms of other instructions rather
+//! than by the backend. Calls to these are expanded as they are generated, so no call to them
+//! remains in the SSA.
+use std::rc::Rc;
+
+use acvm::FieldElement;
+use iter_extended::vecmap;
+
+use crate::ssa_refactor::ir::{
+    instruction::{BinaryOp, Endian, Intrinsic},
+    types::{NumericType, Type},
+    value::{Value, ValueId},
+};
+
+use super::context::FunctionContext;
+
+impl<'a> FunctionContext<'a> {
+    /// Expands a call to an intrinsic which is implemented in terms of other instructions,
+    /// returning the results of the call. Returns None if `function` is any other value.
+    pub(super) fn expand_intrinsic_call(
+        &mut self,
+        function: ValueId,
+        arguments: &[ValueId],
+    ) -> Option<Vec<ValueId>> {
+        let intrinsic = match self.builder.current_function.dfg[function] {
+            Value::Intrinsic(intrinsic) => intrinsic,
+            _ => return None,
+        };
+        match intrinsic {
+            Intrinsic::ArrayGetOr => {
+                Some(self.insert_array_get_or(arguments[0], arguments[1], &arguments[2..]))
+            }
+            Intrinsic::SaturatingAdd => {
+                Some(vec![self.insert_saturating_add(arguments[0], arguments[1])])
+            }
+            Intrinsic::SaturatingSub => {
+                Some(vec![self.insert_saturating_sub(arguments[0], arguments[1])])
+            }
+            Intrinsic::BitLength => Some(vec![self.insert_bit_length(arguments[0], arguments[1])]),
+            Intrinsic::Abs => Some(vec![self.insert_abs(arguments[0])]),
+            Intrinsic::ConditionalSwap => {
+                Some(self.insert_conditional_swap(arguments[0], arguments[1], arguments[2]))
+            }
+            _ => None,
+        }
+    }
+
+    /// Insert the instructions for `array.get_or(index, default)`, returning the element at
+    /// `index` if it is within bounds or `default` otherwise. `default` holds one value for
+    /// each field of the array's (flattened) element type.
+    ///
+    /// Rather than constraining the index to be in bounds, this selects between the element
+    /// and the default, so an out of bounds index never fails the circuit. Since the index
+    /// may not be known at compile-time, it is compared against every position:
+    ///   in_bounds = sum(index == i)
+    ///   selected  = sum((index == i) * array[i])
+    ///   result    = default + in_bounds * (selected - default)
+    fn insert_array_get_or(
+        &mut self,
+        array: ValueId,
+        index: ValueId,
+        default: &[ValueId],
+    ) -> Vec<ValueId> {
+        let (element_types, length) = match self.builder.type_of_value(array) {
+            Type::Array(element_types, length) => (element_types, length),
+            other => unreachable!("Expected an array for array_get_or, found {other}"),
+        };
+        assert_eq!(element_types.len(), default.len());
+
+        // Elements are selected by multiplying them with the comparison against their position,
+        // which only numeric values support. Calls on arrays of anything else are rejected as
+        // errors before reaching here.
+        assert!(
+            element_types.iter().all(|element_type| matches!(element_type, Type::Numeric(_))),
+            "ICE: array_get_or requires numeric elements, found {element_types:?}"
+        );
+
+        let index_type = self.builder.type_of_value(index);
+        let mut in_bounds = self.builder.numeric_constant(FieldElement::zero(), Type::bool());
+        let mut selected = vecmap(element_types.iter(), |element_type| {
+            self.builder.numeric_constant(FieldElement::zero(), element_type.clone())
+        });
+
+        for position in 0..length {
+            // An index can never equal a position outside the range of its type
+            if !index_type.can_represent(FieldElement::from(position as u128)) {
+                break;
+            }
+            let position_constant =
+                self.builder.numeric_constant(position as u128, index_type.clone());
+            let is_position = self.builder.insert_binary(index, BinaryOp::Eq, position_constant);
+            in_bounds = self.builder.insert_binary(in_bounds, BinaryOp::Add, is_position);
+
+            for (field, element_type) in element_types.iter().enumerate() {
+                let flattened_index = position * element_types.len() + field;
+                let flattened_index = self.builder.field_constant(flattened_index as u128);
+                let element =
+                    self.builder.insert_array_get(array, flattened_index, element_type.clone());
+
+                let is_position = self.builder.insert_cast(is_position, element_type.clone());
+                let element = self.builder.insert_binary(is_position, BinaryOp::Mul, element);
+                selected[field] =
+                    self.builder.insert_binary(selected[field], BinaryOp::Add, element);
+            }
+        }
+
+        vecmap(
+            element_types.iter().zip(default).zip(selected),
+            |((element_type, default), selected)| {
+                let in_bounds = self.builder.insert_cast(in_bounds, element_type.clone());
+                let difference = self.builder.insert_binary(selected, BinaryOp::Sub, *default);
+                let difference = self.builder.insert_binary(in_bounds, BinaryOp::Mul, difference);
+                self.builder.insert_binary(*default, BinaryOp::Add, difference)
+            },
+        )
+    }
+
+    /// Returns the bit size of the given unsigned value.
+    /// Panics if the value is not an unsigned integer, since only those can saturate.
+    fn unsigned_bit_size(&self, value: ValueId, intrinsic: Intrinsic) -> u32 {
+        match self.builder.type_of_value(value) {
+            Type::Numeric(NumericType::Unsigned { bit_size }) => bit_size,
+            other => panic!("{intrinsic} is only supported on unsigned integers, found {other}"),
+        }
+    }
+
+    /// Insert the instructions for `saturating_add(lhs, rhs)` on unsigned integers, which
+    /// returns the maximum value of the type instead of overflowing.
+    ///
+    /// The addition is performed with one more bit than the operands so the raw sum cannot
+    /// wrap, then compared against the maximum value of the type to select between the two:
+    ///   sum      = lhs + rhs
+    ///   overflow = max < sum
+    ///   result   = sum + overflow * (max - sum)
+    fn insert_saturating_add(&mut self, lhs: ValueId, rhs: ValueId) -> ValueId {
+        let bit_size = self.unsigned_bit_size(lhs, Intrinsic::SaturatingAdd);
+        let typ = Type::unsigned(bit_size);
+        let wide_type = Type::unsigned(bit_size + 1);
+
+        let lhs = self.builder.insert_cast(lhs, wide_type.clone());
+        let rhs = self.builder.insert_cast(rhs, wide_type.clone());
+        let sum = self.builder.insert_binary(lhs, BinaryOp::Add, rhs);
+
+        let max = self.builder.numeric_constant(2u128.pow(bit_size) - 1, wide_type.clone());
+        let overflow = self.builder.insert_binary(max, BinaryOp::Lt, sum);
+        let overflow = self.builder.insert_cast(overflow, wide_type);
+
+        let difference = self.builder.insert_binary(max, BinaryOp::Sub, sum);
+        let difference = self.builder.insert_binary(overflow, BinaryOp::Mul, difference);
+        let result = self.builder.insert_binary(sum, BinaryOp::Add, difference);
+        self.builder.insert_cast(result, typ)
+    }
+
+    /// Insert the instructions for `saturating_sub(lhs, rhs)` on unsigned integers, which
+    /// returns zero instead of underflowing:
+    ///   underflow = lhs < rhs
+    ///   result    = (1 - underflow) * (lhs - rhs)
+    fn insert_saturating_sub(&mut self, lhs: ValueId, rhs: ValueId) -> ValueId {
+        let bit_size = self.unsigned_bit_size(lhs, Intrinsic::SaturatingSub);
+        let typ = Type::unsigned(bit_size);
+
+        let underflow = self.builder.insert_binary(lhs, BinaryOp::Lt, rhs);
+        let no_underflow = self.builder.insert_not(underflow);
+        let no_underflow = self.builder.insert_cast(no_underflow, typ);
+
+        let difference = self.builder.insert_binary(lhs, BinaryOp::Sub, rhs);
+        self.builder.insert_binary(no_underflow, BinaryOp::Mul, difference)
+    }
+
+    /// Insert the instructions for `x.bit_length(bit_size)`, returning the position of the
+    /// highest set bit of `x`, or zero if `x` is zero.
+    ///
+    /// `x` is decomposed into `bit_size` bits, which also constrains it to fit within them.
+    /// The bits are then scanned from the most significant, adding the position of the first
+    /// one which is set:
+    ///   first  = bits[i] * (1 - seen)
+    ///   length = length + first * (i + 1)
+    ///   seen   = seen + first
+    fn insert_bit_length(&mut self, value: ValueId, bit_size: ValueId) -> ValueId {
+        let bit_count = self
+            .builder
+            .current_function
+            .dfg
+            .get_numeric_constant(bit_size)
+            .and_then(|bit_size| bit_size.try_to_u64())
+            .expect("bit_length requires a bit size known at compile-time")
+            as usize;
+
+        let to_bits = self.builder.import_intrinsic_id(Intrinsic::ToBits(Endian::Little));
+        let bits_type = Type::Array(Rc::new(vec![Type::bool()]), bit_count);
+        let bits = self.builder.insert_call(to_bits, vec![value, bit_size], vec![bits_type])[0];
+
+        let one = self.builder.field_constant(FieldElement::one());
+        let mut seen = self.builder.field_constant(FieldElement::zero());
+        let mut length = seen;
+
+        for i in (0..bit_count).rev() {
+            let index = self.builder.field_constant(i as u128);
+            let bit = self.builder.insert_array_get(bits, index, Type::bool());
+            let bit = self.builder.insert_cast(bit, Type::field());
+
+            let unseen = self.builder.insert_binary(one, BinaryOp::Sub, seen);
+            let first = self.builder.insert_binary(bit, BinaryOp::Mul, unseen);
+            let position = self.builder.field_constant((i + 1) as u128);
+            let position = self.builder.insert_binary(first, BinaryOp::Mul, position);
+            length = self.builder.insert_binary(length, BinaryOp::Add, position);
+            seen = self.builder.insert_binary(seen, BinaryOp::Add, first);
+        }
+        length
+    }
+
+    /// Insert the instructions constraining `value` to fit within `bit_size` bits.
+    ///
+    /// The IR has no dedicated range check, so `value` is decomposed into `bit_size` bits,
+    /// which constrains it to fit within them. The bits themselves are left unused.
+    pub(super) fn insert_range_check(&mut self, value: ValueId, bit_size: u32) {
+        let to_bits = self.builder.import_intrinsic_id(Intrinsic::ToBits(Endian::Little));
+        let bit_count = self.builder.numeric_constant(bit_size as u128, Type::unsigned(32));
+        let bits_type = Type::Array(Rc::new(vec![Type::bool()]), bit_size as usize);
+        self.builder.insert_call(to_bits, vec![value, bit_count], vec![bits_type]);
+    }
+
+    /// Insert the instructions for `cswap(condition, a, b)`, returning `[b, a]` if `condition`
+    /// is true and `[a, b]` otherwise. Both results are selected arithmetically rather than by
+    /// branching, sharing the product of the condition and the difference of the values:
+    ///   first  = a + condition * (b - a)
+    ///   second = b - condition * (b - a)
+    fn insert_conditional_swap(
+        &mut self,
+        condition: ValueId,
+        a: ValueId,
+        b: ValueId,
+    ) -> Vec<ValueId> {
+        let condition = self.builder.insert_cast(condition, self.builder.type_of_value(a));
+        let difference = self.builder.insert_binary(b, BinaryOp::Sub, a);
+        let difference = self.builder.insert_binary(condition, BinaryOp::Mul, difference);
+        let first = self.builder.insert_binary(a, BinaryOp::Add, difference);
+        let second = self.builder.insert_binary(b, BinaryOp::Sub, difference);
+        vec![first, second]
+    }
+
+    /// Insert the instructions packing the given fields into a single unsigned integer of
+    /// `bit_size` bits, each field paired with its width in bits. The first field occupies the
+    /// lowest bits of the result, and each later field the bits above the field before it.
+    ///
+    /// The mask and offset of each field are known from the widths alone, so they are computed
+    /// here and only the masking, shifting, and combining of the fields is left to the IR:
+    ///   packed = (f0 & mask0) | ((f1 & mask1) << width0) | ((f2 & mask2) << (width0 + width1)) ...
+    pub(super) fn insert_pack_bit_fields(
+        &mut self,
+        fields: &[(ValueId, u32)],
+        bit_size: u32,
+    ) -> ValueId {
+        let total_width: u32 = fields.iter().map(|(_, width)| width).sum();
+        assert!(total_width <= bit_size, "{total_width} bits of fields do not fit in u{bit_size}");
+
+        let typ = Type::unsigned(bit_size);
+        let mut packed = self.builder.numeric_constant(0u128, typ.clone());
+        let mut offset = 0;
+        for (field, width) in fields {
+            let field = self.builder.insert_cast(*field, typ.clone());
+            let mask = self.builder.numeric_constant(u128::MAX >> (128 - width), typ.clone());
+            let field = self.builder.insert_binary(field, BinaryOp::And, mask);
+            let shift = self.builder.numeric_constant(offset as u128, typ.clone());
+            let field = self.builder.insert_binary(field, BinaryOp::Shl, shift);
+            packed = self.builder.insert_binary(packed, BinaryOp::Or, field);
+            offset += width;
+        }
+        packed
+    }
+
+    /// Insert the instructions unpacking fields of the given widths from an unsigned integer
+    /// packed by `insert_pack_bit_fields`, returning each field as an unsigned integer of its
+    /// own width.
+    ///
+    /// The packed value is first constrained to fit within the total width of the fields, so
+    /// no bits above the last field may be set. Each field is then shifted down by its offset
+    /// and masked to its width.
+    pub(super) fn insert_unpack_bit_fields(
+        &mut self,
+        packed: ValueId,
+        widths: &[u32],
+    ) -> Vec<ValueId> {
+        let typ = self.builder.type_of_value(packed);
+        let bit_size = match &typ {
+            Type::Numeric(NumericType::Unsigned { bit_size }) => *bit_size,
+            other => {
+                panic!("Bit fields can only be unpacked from unsigned integers, found {other}")
+            }
+        };
+        let total_width: u32 = widths.iter().sum();
+        assert!(total_width <= bit_size, "{total_width} bits of fields do not fit in u{bit_size}");
+
+        let truncated = self.builder.insert_truncate(packed, total_width, bit_size);
+        self.builder.insert_constrain_eq(packed, truncated, None);
+
+        let mut offset = 0;
+        vecmap(widths, |width| {
+            let shift = self.builder.numeric_constant(offset as u128, typ.clone());
+            let field = self.builder.insert_binary(packed, BinaryOp::Shr, shift);
+            let mask = self.builder.numeric_constant(u128::MAX >> (128 - width), typ.clone());
+            let field = self.builder.insert_binary(field, BinaryOp::And, mask);
+            offset += width;
+            self.builder.insert_cast(field, Type::unsigned(*width))
+        })
+    }
+
+    /// Insert the instructions for `abs(x)` on signed integers. Taking the absolute value of
+    /// the minimum value of the type would overflow, so `x` is constrained not to be it.
+    ///
+    /// Signed integers are stored in two's complement, so `x` is negative if its unsigned
+    /// representation is above the maximum positive value, in which case it is negated by
+    /// subtracting it from `2^bit_size`. The two are selected between without branching:
+    ///   is_negative = max < x
+    ///   result      = x + is_negative * ((2^bit_size - x) - x)
+    fn insert_abs(&mut self, value: ValueId) -> ValueId {
+        let typ = self.builder.type_of_value(value);
+        let bit_size = match &typ {
+            Type::Numeric(NumericType::Signed { bit_size }) => *bit_size,
+            other => panic!("abs is only supported on signed integers, found {other}"),
+        };
+        let unsigned_type = Type::unsigned(bit_size);
+        let unsigned_value = self.builder.insert_cast(value, unsigned_type.clone());
+
+        let min = self.builder.numeric_constant(2u128.pow(bit_size - 1), unsigned_type.clone());
+        let is_min = self.builder.insert_binary(unsigned_value, BinaryOp::Eq, min);
+        let is_not_min = self.builder.insert_not(is_min);
+        self.builder.insert_constrain(is_not_min, None);
+
+        let max = self.builder.numeric_constant(2u128.pow(bit_size - 1) - 1, unsigned_type);
+        let is_negative = self.builder.insert_binary(max, BinaryOp::Lt, unsigned_value);
+        let is_negative = self.builder.insert_cast(is_negative, Type::field());
+
+        let value = self.builder.insert_cast(value, Type::field());
+        let modulus = self.builder.field_constant(2u128.pow(bit_size));
+        let negated = self.builder.insert_binary(modulus, BinaryOp::Sub, value);
+        let difference = self.builder.insert_binary(negated, BinaryOp::Sub, value);
+        let difference = self.builder.insert_binary(is_negative, BinaryOp::Mul, difference);
+        let result = self.builder.insert_binary(value, BinaryOp::Add, difference);
+        self.builder.insert_cast(result, typ)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use acvm::FieldElement;
+    use iter_extended::vecmap;
+    use noirc_frontend::monomorphization::ast;
+
+    use crate::ssa_refactor::{
+        ir::{
+            instruction::{Binary, BinaryOp, Instruction, Intrinsic},
+            interpreter::{interpret, InterpreterError, InterpreterValue},
+            types::Type,
+        },
+        ssa_gen::{
+            test_utils::{
+                builtin, call, codegen_main, empty_shared_context, field, local_ident,
+                main_context, parameter, signed, unsigned,
+            },
+            Ssa,
+        },
+    };
+
+    /// Builds `main` with parameters of the given types, returning the results of calling the
+    /// given intrinsic with each parameter as an argument.
+    fn call_intrinsic(
+        intrinsic: Intrinsic,
+        parameter_types: Vec<Type>,
+        return_type: ast::Type,
+    ) -> Ssa {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let arguments = vecmap(parameter_types, |typ| context.builder.add_parameter(typ));
+
+        let intrinsic = context.builder.import_intrinsic_id(intrinsic);
+        let results = context.insert_call(intrinsic, arguments, &return_type);
+        let results = results.into_value_list(&mut context);
+        context.builder.terminate_with_return(results);
+        let ssa = context.builder.finish();
+
+        // The call is expanded rather than inserted
+        let main = ssa.main();
+        for instruction in main.dfg[main.entry_block()].instructions() {
+            assert!(!matches!(main.dfg[*instruction], Instruction::Call { .. }));
+        }
+        ssa
+    }
+
+    #[test]
+    fn bit_length_of_constant_is_folded() {
+        // let length = 0b1000.bit_length(8);
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let bit_length = context.builder.import_intrinsic_id(Intrinsic::BitLength);
+        let bit_size = context.builder.numeric_constant(8u128, Type::unsigned(32));
+
+        let mut length_of = |value: u128| {
+            let input = context.builder.field_constant(value);
+            let arguments = vec![input, bit_size];
+            let length = context.insert_call(bit_length, arguments, &ast::Type::Field);
+            let length = length.into_leaf().eval(&mut context);
+            context.builder.current_function.dfg.get_numeric_constant(length)
+        };
+
+        assert_eq!(length_of(0b1000), Some(FieldElement::from(4u128)));
+        assert_eq!(length_of(0b1011_0001), Some(FieldElement::from(8u128)));
+        assert_eq!(length_of(1), Some(FieldElement::one()));
+        assert_eq!(length_of(0), Some(FieldElement::zero()));
+    }
+
+    #[test]
+    fn array_get_or_out_of_bounds_returns_default() {
+        // fn main(v0: Field) -> Field {
+        //     [10, 20, 30].get_or(v0, 99)
+        // }
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let v0 = context.builder.add_parameter(Type::field());
+
+        let elements = [10u128, 20, 30].map(|element| context.builder.field_constant(element));
+        let element_types = Rc::new(vec![Type::field()]);
+        let array = context.builder.array_constant(elements.into_iter().collect(), element_types);
+        let default = context.builder.field_constant(99u128);
+
+        let get_or = context.builder.import_intrinsic_id(Intrinsic::ArrayGetOr);
+        let arguments = vec![array, v0, default];
+        let result = context.insert_call(get_or, arguments, &ast::Type::Field);
+        let result = result.into_leaf().eval(&mut context);
+        context.builder.terminate_with_return(vec![result]);
+
+        // The call is expanded into a select, without any constraint on the index
+        let ssa = context.builder.finish();
+        let main = ssa.main();
+        for instruction in main.dfg[main.entry_block()].instructions() {
+            let instruction = &main.dfg[*instruction];
+            assert!(!matches!(instruction, Instruction::Call { .. } | Instruction::Constrain(_)));
+        }
+
+        let field = InterpreterValue::numeric;
+        assert_eq!(interpret(main, vec![field(1)]), Ok(vec![field(20)]));
+        assert_eq!(interpret(main, vec![field(2)]), Ok(vec![field(30)]));
+        assert_eq!(interpret(main, vec![field(3)]), Ok(vec![field(99)]));
+        assert_eq!(interpret(main, vec![field(1000)]), Ok(vec![field(99)]));
+    }
+
+    #[test]
+    fn array_get_or_skips_positions_outside_the_index_type() {
+        // fn main(v0: u8) -> Field {
+        //     [0, 1, ..., 299].get_or(v0, 999)
+        // }
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let v0 = context.builder.add_parameter(Type::unsigned(8));
+
+        let elements = (0..300u128).map(|element| context.builder.field_constant(element));
+        let elements = elements.collect();
+        let array = context.builder.array_constant(elements, Rc::new(vec![Type::field()]));
+        let default = context.builder.field_constant(999u128);
+
+        let get_or = context.builder.import_intrinsic_id(Intrinsic::ArrayGetOr);
+        let result = context.insert_call(get_or, vec![array, v0, default], &ast::Type::Field);
+        let result = result.into_leaf().eval(&mut context);
+        context.builder.terminate_with_return(vec![result]);
+
+        // Only the 256 positions a u8 can hold are compared against the index
+        let ssa = context.builder.finish();
+        let main = ssa.main();
+        let comparisons = main.dfg[main.entry_block()].instructions().iter().filter(|id| {
+            matches!(&main.dfg[**id], Instruction::Binary(Binary { operator: BinaryOp::Eq, .. }))
+        });
+        assert_eq!(comparisons.count(), 256);
+
+        let numeric = InterpreterValue::numeric;
+        assert_eq!(interpret(main, vec![numeric(255)]), Ok(vec![numeric(255)]));
+    }
+
+    #[test]
+    fn array_get_or_of_non_numeric_elements_is_an_error() {
+        // fn main(array: [[Field; 1]; 2], default: [Field; 1]) -> [Field; 1] {
+        //     array.get_or(0, default)
+        // }
+        let element_type = ast::Type::Array(1, Box::new(ast::Type::Field));
+        let array_type = ast::Type::Array(2, Box::new(element_type.clone()));
+        let (array, default) = (ast::LocalId(0), ast::LocalId(1));
+        let parameters = vec![
+            parameter(array, "array", array_type.clone()),
+            parameter(default, "default", element_type.clone()),
+        ];
+
+        let parameter_types = vec![array_type.clone(), ast::Type::Field, element_type.clone()];
+        let get_or = builtin("array_get_or", parameter_types, element_type.clone());
+        let arguments = vec![
+            local_ident(array, "array", array_type),
+            field(0),
+            local_ident(default, "default", element_type.clone()),
+        ];
+        let (_, errors) = codegen_main(&parameters, &call(get_or, arguments, element_type));
+        assert_eq!(errors.len(), 1);
+    }
+
+    /// Interprets the given intrinsic applied to two u8 parameters.
+    fn interpret_u8_intrinsic(intrinsic: Intrinsic, lhs: u128, rhs: u128) -> InterpreterValue {
+        let parameter_types = vec![Type::unsigned(8), Type::unsigned(8)];
+        let ssa = call_intrinsic(intrinsic, parameter_types, unsigned(8));
+        let arguments = vec![InterpreterValue::numeric(lhs), InterpreterValue::numeric(rhs)];
+        let mut results = interpret(ssa.main(), arguments).unwrap();
+        assert_eq!(results.len(), 1);
+        results.remove(0)
+    }
+
+    #[test]
+    fn saturating_arithmetic_clamps_to_the_range_of_the_type() {
+        let add = |lhs, rhs| interpret_u8_intrinsic(Intrinsic::SaturatingAdd, lhs, rhs);
+        assert_eq!(add(250, 10), InterpreterValue::numeric(255));
+        assert_eq!(add(255, 255), InterpreterValue::numeric(255));
+        assert_eq!(add(200, 55), InterpreterValue::numeric(255));
+        assert_eq!(add(1, 2), InterpreterValue::numeric(3));
+
+        let sub = |lhs, rhs| interpret_u8_intrinsic(Intrinsic::SaturatingSub, lhs, rhs);
+        assert_eq!(sub(3, 5), InterpreterValue::numeric(0));
+        assert_eq!(sub(5, 5), InterpreterValue::numeric(0));
+        assert_eq!(sub(5, 3), InterpreterValue::numeric(2));
+    }
+
+    #[test]
+    fn abs_negates_negative_integers() {
+        let ssa = call_intrinsic(Intrinsic::Abs, vec![Type::signed(32)], signed(32));
+        // Arguments are given in their two's complement representation
+        let abs = |value: i32| {
+            interpret(ssa.main(), vec![InterpreterValue::numeric(value as u32 as u128)])
+        };
+
+        let five = InterpreterValue::numeric(5);
+        assert_eq!(abs(-5), Ok(vec![five.clone()]));
+        assert_eq!(abs(5), Ok(vec![five]));
+        assert_eq!(abs(0), Ok(vec![InterpreterValue::numeric(0)]));
+
+        // The absolute value of i32::MIN does not fit in an i32
+        assert_eq!(abs(i32::MIN), Err(InterpreterError::ConstraintFailed));
+        assert_eq!(abs(-i32::MAX), Ok(vec![InterpreterValue::numeric(i32::MAX as u128)]));
+    }
+
+    #[test]
+    fn conditional_swap_is_branchless() {
+        // fn main(v0: u1, v1: u8, v2: u8) -> (u8, u8) {
+        //     cswap(v0, v1, v2)
+        // }
+        let parameter_types = vec![Type::bool(), Type::unsigned(8), Type::unsigned(8)];
+        let return_type = ast::Type::Tuple(vec![unsigned(8), unsigned(8)]);
+        let ssa = call_intrinsic(Intrinsic::ConditionalSwap, parameter_types, return_type);
+        let main = ssa.main();
+        assert_eq!(main.reachable_blocks().len(), 1);
+
+        let numeric = InterpreterValue::numeric;
+        let swapped = interpret(main, vec![numeric(1), numeric(3), numeric(200)]);
+        assert_eq!(swapped, Ok(vec![numeric(200), numeric(3)]));
+        let unchanged = interpret(main, vec![numeric(0), numeric(3), numeric(200)]);
+        assert_eq!(unchanged, Ok(vec![numeric(3), numeric(200)]));
+    }
+
+    #[test]
+    fn bit_fields_are_packed_and_unpacked() {
+        // fn main(v0: u4, v1: u4, v2: u4) -> (u12, u4, u4, u4) {
+        //     let packed = pack_bit_fields([(v0, 4), (v1, 4), (v2, 4)]) as u12;
+        //     (packed, unpack_bit_fields(packed, [4, 4, 4]))
+        // }
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let v0 = context.builder.add_parameter(Type::unsigned(4));
+        let v1 = context.builder.add_parameter(Type::unsigned(4));
+        let v2 = context.builder.add_parameter(Type::unsigned(4));
+
+        let packed = context.insert_pack_bit_fields(&[(v0, 4), (v1, 4), (v2, 4)], 12);
+        let mut results = vec![packed];
+        results.extend(context.insert_unpack_bit_fields(packed, &[4, 4, 4]));
+        context.builder.terminate_with_return(results);
+
+        let ssa = context.builder.finish();
+        let numeric = InterpreterValue::numeric;
+        let arguments = vec![numeric(0x3), numeric(0xa), numeric(0xf)];
+        let expected = vec![numeric(0xfa3), numeric(0x3), numeric(0xa), numeric(0xf)];
+        assert_eq!(interpret(ssa.main(), arguments), Ok(expected));
+
+        // Every mask and shift amount is a constant computed during codegen
+        let main = ssa.main();
+        let dfg = &main.dfg;
+        for instruction in dfg[main.entry_block()].instructions() {
+            if let Instruction::Binary(Binary { operator, rhs, .. }) = &dfg[*instruction] {
+                if matches!(operator, BinaryOp::And | BinaryOp::Shl | BinaryOp::Shr) {
+                    assert!(dfg.get_numeric_constant(*rhs).is_some());
+                }
+            }
+        }
+    }
+}
@@ -1,15 +1,24 @@
 mod context;
+mod intrinsics;
 mod program;
+#[cfg(test)]
+pub(crate) mod test_utils;
 mod value;
 
 use std::rc::Rc;
 
 pub(crate) use program::Ssa;
 
+use acvm::FieldElement;
 use context::SharedContext;
 use iter_extended::vecmap;
 use noirc_errors::Location;
-use noirc_frontend::monomorphization::ast::{self, Expression, Program};
+use noirc_frontend::{
+    monomorphization::ast::{self, Expression, Program},
+    BinaryOpKind,
+};
+
+use crate::errors::{RuntimeError, RuntimeErrorKind};
 
 use self::{
     context::FunctionContext,
@@ -18,15 +27,16 @@ use self::{
 
 use super::ir::{
     function::RuntimeType,
-    instruction::BinaryOp,
-    types::{CompositeType, Type},
+    instruction::{BinaryOp, Endian, Intrinsic},
+    types::{CompositeType, NumericType, Type},
     value::ValueId,
 };
 
 /// Generates SSA for the given monomorphized program.
 ///
 /// This function will generate the SSA but does not perform any optimizations on it.
-pub(crate) fn generate_ssa(program: Program) -> Ssa {
+/// If any error was found during codegen, the first one is returned instead.
+pub(crate) fn generate_ssa(program: Program) -> Result<Ssa, RuntimeError> {
     let context = SharedContext::new(program);
 
     let main_id = Program::main_id();
@@ -49,7 +59,10 @@ pub(crate) fn generate_ssa(program: Program) -> Ssa {
         function_context.codegen_function_body(&function.body);
     }
 
-    function_context.builder.finish()
+    match function_context.errors.into_iter().next() {
+        Some(error) => Err(error),
+        None => Ok(function_context.builder.finish()),
+    }
 }
 
 impl<'a> FunctionContext<'a> {
@@ -110,12 +123,24 @@ impl<'a> FunctionContext<'a> {
     fn codegen_literal(&mut self, literal: &ast::Literal) -> Values {
         match literal {
             ast::Literal::Array(array) => {
-                let elements = vecmap(&array.contents, |element| self.codegen_expression(element));
-                let element_types = Self::convert_type(&array.element_type).flatten();
+                let mut elements =
+                    vecmap(&array.contents, |element| self.codegen_expression(element));
+                if let ast::Type::String(len) = &array.element_type {
+                    elements = vecmap(elements, |string| self.codegen_string_bytes(string, *len));
+                }
+                let element_types = Self::convert_element_types(&array.element_type);
                 self.codegen_array(elements, element_types)
             }
             ast::Literal::Integer(value, typ) => {
                 let typ = Self::convert_non_tuple_type(typ);
+                if !typ.can_represent(*value) {
+                    let message = format!("Integer literal {value} does not fit in type {typ}");
+                    let kind = RuntimeErrorKind::UnstructuredError { message };
+                    self.errors.push(RuntimeError::new(kind, None));
+
+                    // The error aborts SSA generation, so any value of the right type will do
+                    return self.builder.numeric_constant(0u128, typ).into();
+                }
                 self.builder.numeric_constant(*value, typ).into()
             }
             ast::Literal::Bool(value) => {
@@ -123,26 +148,28 @@ impl<'a> FunctionContext<'a> {
             }
             ast::Literal::Str(string) => {
                 let elements = vecmap(string.as_bytes(), |byte| {
-                    self.builder.numeric_constant(*byte as u128, Type::field()).into()
+                    self.builder.numeric_constant(*byte as u128, Type::char()).into()
                 });
                 self.codegen_array(elements, vec![Type::char()])
             }
         }
     }
 
-    /// Codegen an array by allocating enough space for each element and inserting separate
-    /// store instructions until each element is stored. The store instructions will be separated
-    /// by add instructions to calculate the new offset address to store to next.
+    /// Codegen an array value from the given elements.
     ///
     /// In the case of arrays of structs, the structs are flattened such that each field will be
-    /// stored next to the other fields in memory. So an array such as [(1, 2), (3, 4)] is
-    /// stored the same as the array [1, 2, 3, 4].
-    ///
-    /// The value returned from this function is always that of the allocate instruction.
+    /// stored next to the other fields in the array. So an array such as [(1, 2), (3, 4)] is
+    /// stored the same as the array [1, 2, 3, 4]. The type of the array is derived from its
+    /// flattened length, so each element must flatten to exactly one value per element type.
     fn codegen_array(&mut self, elements: Vec<Values>, element_types: CompositeType) -> Values {
         let mut array = im::Vector::new();
 
         for element in elements {
+            assert_eq!(
+                element.count_leaves(),
+                element_types.len(),
+                "ICE: Array element does not match the flattened element type of its array"
+            );
             element.for_each(|element| {
                 let element = element.eval(self);
                 array.push_back(element);
@@ -152,6 +179,15 @@ impl<'a> FunctionContext<'a> {
         self.builder.array_constant(array, Rc::new(element_types)).into()
     }
 
+    /// Reads each byte of the given string so that it can be stored within an array of strings.
+    fn codegen_string_bytes(&mut self, string: Values, len: u64) -> Values {
+        let string = string.into_leaf().eval(self);
+        Tree::Branch(vecmap(0..len as u128, |i| {
+            let index = self.builder.field_constant(i);
+            self.builder.insert_array_get(string, index, Type::char()).into()
+        }))
+    }
+
     fn codegen_block(&mut self, block: &[Expression]) -> Values {
         let mut result = Self::unit_value();
         for expr in block {
@@ -163,11 +199,32 @@ impl<'a> FunctionContext<'a> {
     fn codegen_unary(&mut self, unary: &ast::Unary) -> Values {
         let rhs = self.codegen_non_tuple_expression(&unary.rhs);
         match unary.operator {
-            noirc_frontend::UnaryOp::Not => self.builder.insert_not(rhs).into(),
+            noirc_frontend::UnaryOp::Not => match self.builder.type_of_value(rhs) {
+                // A not instruction flips each of the bit_size bits of its operand by computing
+                // (2^bit_size - 1) - rhs, which for a bool (u1) is the logical negation 1 - rhs.
+                Type::Numeric(NumericType::Unsigned { .. } | NumericType::Signed { .. }) => {
+                    self.builder.insert_not(rhs).into()
+                }
+                other => {
+                    let message = format!("Bitwise not is not defined on {other}");
+                    let kind = RuntimeErrorKind::UnstructuredError { message };
+                    self.errors.push(RuntimeError::new(kind, None));
+                    rhs.into()
+                }
+            },
             noirc_frontend::UnaryOp::Minus => {
                 let typ = self.builder.type_of_value(rhs);
-                let zero = self.builder.numeric_constant(0u128, typ);
-                self.builder.insert_binary(zero, BinaryOp::Sub, rhs).into()
+                let zero = self.builder.numeric_constant(0u128, typ.clone());
+                let negated = self.builder.insert_binary(zero, BinaryOp::Sub, rhs);
+                match typ {
+                    // Negating an integer wraps to its two's complement, 2^bit_size - rhs, which
+                    // truncating the subtraction computes since the integer modulus is added
+                    // to it first. For unsigned integers this deliberately wraps as well.
+                    Type::Numeric(
+                        NumericType::Signed { bit_size } | NumericType::Unsigned { bit_size },
+                    ) => self.builder.insert_truncate(negated, bit_size, bit_size + 1).into(),
+                    _ => negated.into(),
+                }
             }
         }
     }
@@ -175,12 +232,13 @@ impl<'a> FunctionContext<'a> {
     fn codegen_binary(&mut self, binary: &ast::Binary) -> Values {
         let lhs = self.codegen_non_tuple_expression(&binary.lhs);
         let rhs = self.codegen_non_tuple_expression(&binary.rhs);
-        self.insert_binary(lhs, binary.operator, rhs)
+        self.insert_checked_binary(lhs, binary.operator, rhs)
     }
 
     fn codegen_index(&mut self, index: &ast::Index) -> Values {
         let array = self.codegen_non_tuple_expression(&index.collection);
         let index_value = self.codegen_non_tuple_expression(&index.index);
+        self.constrain_index_in_bounds(array, index_value, index.location);
         self.codegen_array_index(array, index_value, &index.element_type)
     }
 
@@ -196,6 +254,10 @@ impl<'a> FunctionContext<'a> {
         index: super::ir::value::ValueId,
         element_type: &ast::Type,
     ) -> Values {
+        if let ast::Type::String(len) = element_type {
+            return self.codegen_string_index(array, index, *len);
+        }
+
         // base_index = index * type_size
         let type_size = Self::convert_type(element_type).size_of_type();
         let type_size = self.builder.field_constant(type_size as u128);
@@ -209,6 +271,21 @@ impl<'a> FunctionContext<'a> {
         })
     }
 
+    /// Strings within an array are stored as their bytes, so indexing an array of strings
+    /// reads `len` bytes starting at `index * len` and collects them into a new string.
+    fn codegen_string_index(&mut self, array: ValueId, index: ValueId, len: u64) -> Values {
+        let len_value = self.builder.field_constant(len as u128);
+        let base_index = self.builder.insert_binary(index, BinaryOp::Mul, len_value);
+
+        let bytes = (0..len as u128)
+            .map(|i| {
+                let offset = self.make_offset(base_index, i);
+                self.builder.insert_array_get(array, offset, Type::char())
+            })
+            .collect();
+        self.builder.array_constant(bytes, Rc::new(vec![Type::char()])).into()
+    }
+
     fn codegen_cast(&mut self, cast: &ast::Cast) -> Values {
         let lhs = self.codegen_non_tuple_expression(&cast.lhs);
         let typ = Self::convert_non_tuple_type(&cast.r#type);
@@ -302,13 +379,17 @@ impl<'a> FunctionContext<'a> {
 
         if let Some(alternative) = &if_expr.alternative {
             let end_block = self.builder.insert_block();
-            let then_values = then_value.into_value_list(self);
-            self.builder.terminate_with_jmp(end_block, then_values);
 
-            self.builder.switch_to_block(else_block);
-            let else_value = self.codegen_expression(alternative);
-            let else_values = else_value.into_value_list(self);
-            self.builder.terminate_with_jmp(end_block, else_values);
+            // An if used as a statement has no value to pass to the end block, so both
+            // branches jump to it without arguments.
+            if if_expr.typ == ast::Type::Unit {
+                self.builder.terminate_with_jmp(end_block, vec![]);
+                self.builder.switch_to_block(else_block);
+                self.codegen_expression(alternative);
+                self.builder.terminate_with_jmp(end_block, vec![]);
+                self.builder.switch_to_block(end_block);
+                return result;
+            }
 
             // Create block arguments for the end block as needed to branch to
             // with our then and else value.
@@ -316,7 +397,15 @@ impl<'a> FunctionContext<'a> {
                 self.builder.add_block_parameter(end_block, typ).into()
             });
 
-            // Must also set the then block to jmp to the end now
+            // Each branch is cast to the type of the if expression if its own type differs
+            let then_values = then_value.into_value_list(self);
+            self.builder.terminate_with_jmp_and_casts(end_block, then_values);
+
+            self.builder.switch_to_block(else_block);
+            let else_value = self.codegen_expression(alternative);
+            let else_values = else_value.into_value_list(self);
+            self.builder.terminate_with_jmp_and_casts(end_block, else_values);
+
             self.builder.switch_to_block(end_block);
         } else {
             // In the case we have no 'else', the 'else' block is actually the end block.
@@ -339,16 +428,399 @@ impl<'a> FunctionContext<'a> {
     /// Generate SSA for a function call. Note that calls to built-in functions
     /// and intrinsics are also represented by the function call instruction.
     fn codegen_call(&mut self, call: &ast::Call) -> Values {
-        let arguments = call
+        if let Expression::Ident(ident) = call.func.as_ref() {
+            if let ast::Definition::Builtin(name) = &ident.definition {
+                match name.as_str() {
+                    "assert_constant" => return self.codegen_assert_constant(call),
+                    "assert_max_bit_size" => return self.codegen_assert_max_bit_size(call),
+                    "array_from_fn" => return self.codegen_array_from_fn(call),
+                    "array_any" => return self.codegen_array_predicate(call, BinaryOp::Or),
+                    "array_all" => return self.codegen_array_predicate(call, BinaryOp::And),
+                    "field_to_bytes" => return self.codegen_field_to_bytes(call),
+                    "bytes_to_field" => return self.codegen_bytes_to_field(call),
+                    "option_is_some" => return self.codegen_option_is_some(call),
+                    "option_unwrap_or" => return self.codegen_option_unwrap_or(call),
+                    "option_map" => return self.codegen_option_map(call),
+                    "zeroed" => return self.default_value(&call.return_type),
+                    "into" | "from" => return self.codegen_conversion(call),
+                    "wrapping_add" => return self.codegen_wrapping(call, BinaryOpKind::Add),
+                    "wrapping_sub" => return self.codegen_wrapping(call, BinaryOpKind::Subtract),
+                    "wrapping_mul" => return self.codegen_wrapping(call, BinaryOpKind::Multiply),
+                    "modulus_num_bits" | "modulus_le_bits" | "modulus_be_bits"
+                    | "modulus_le_bytes" | "modulus_be_bytes" => {
+                        return self.codegen_field_modulus(name)
+                    }
+                    _ => (),
+                }
+            }
+        }
+
+        let arguments: Vec<_> = call
             .arguments
             .iter()
             .flat_map(|argument| self.codegen_expression(argument).into_value_list(self))
             .collect();
 
+        if let Expression::Ident(ident) = call.func.as_ref() {
+            match &ident.definition {
+                ast::Definition::LowLevel(name) if name == "sha256" || name == "blake2s" => {
+                    self.check_hash_input(name, &arguments, call.location);
+                }
+                ast::Definition::Builtin(name) if name == "array_get_or" => {
+                    if !self.check_array_get_or_elements(&arguments, call.location) {
+                        // The default stands in for the result so codegen can continue
+                        return Self::reshape_results(&call.return_type, &arguments[2..]);
+                    }
+                }
+                _ => (),
+            }
+        }
+
         let function = self.codegen_non_tuple_expression(&call.func);
         self.insert_call(function, arguments, &call.return_type)
     }
 
+    /// The sha256 and blake2s hashes are lowered to black box functions which are implemented
+    /// natively by each backend. These operate on bytes, so an error is recorded if their input
+    /// is anything other than an array of `u8`.
+    fn check_hash_input(&mut self, name: &str, arguments: &[ValueId], location: Location) {
+        let is_byte_array = match arguments {
+            [input] => match self.builder.type_of_value(*input) {
+                Type::Array(element_types, _) => *element_types == vec![Type::unsigned(8)],
+                _ => false,
+            },
+            _ => false,
+        };
+
+        if !is_byte_array {
+            let message = format!("The input to {name} must be an array of u8");
+            let kind = RuntimeErrorKind::UnstructuredError { message };
+            self.errors.push(RuntimeError::new(kind, Some(location)));
+        }
+    }
+
+    /// The `array_get_or` builtin selects the element arithmetically rather than by branching,
+    /// which is only possible for arrays of numeric values. An error is recorded for an array of
+    /// anything else, such as nested arrays or references, and false is returned.
+    fn check_array_get_or_elements(&mut self, arguments: &[ValueId], location: Location) -> bool {
+        let element_types = match self.builder.type_of_value(arguments[0]) {
+            Type::Array(element_types, _) => element_types,
+            other => unreachable!("ICE: Expected an array for array_get_or, found {other}"),
+        };
+
+        let is_numeric = element_types.iter().all(|typ| matches!(typ, Type::Numeric(_)));
+        if !is_numeric {
+            let message = "array_get_or is only supported on arrays of numeric values";
+            let kind = RuntimeErrorKind::UnstructuredError { message: message.to_owned() };
+            self.errors.push(RuntimeError::new(kind, Some(location)));
+        }
+        is_numeric
+    }
+
+    /// Codegen for the `assert_constant` builtin, which requires each of its arguments to be
+    /// known at compile-time. No instructions are inserted for the call itself, an error is
+    /// recorded for each argument which is not a constant instead.
+    fn codegen_assert_constant(&mut self, call: &ast::Call) -> Values {
+        for argument in &call.arguments {
+            let values = self.codegen_expression(argument).into_value_list(self);
+            let dfg = &self.builder.current_function.dfg;
+
+            if !values.iter().all(|value| dfg.is_constant(*value)) {
+                let message = "Argument to assert_constant is not a compile-time constant";
+                let kind = RuntimeErrorKind::UnstructuredError { message: message.to_owned() };
+                self.errors.push(RuntimeError::new(kind, Some(call.location)));
+            }
+        }
+        Self::unit_value()
+    }
+
+    /// Codegen for the `assert_max_bit_size` builtin, which constrains a field element to fit
+    /// within a bit size known at compile-time. A constant which does not fit is reported as an
+    /// error rather than inserting a range check which would always fail.
+    fn codegen_assert_max_bit_size(&mut self, call: &ast::Call) -> Values {
+        let value = self.codegen_non_tuple_expression(&call.arguments[0]);
+        let bit_size = self.codegen_non_tuple_expression(&call.arguments[1]);
+        let dfg = &self.builder.current_function.dfg;
+
+        let bit_size = match dfg.get_numeric_constant(bit_size).and_then(|b| b.try_to_u64()) {
+            Some(bit_size) => bit_size as u32,
+            None => {
+                let message = "The bit size of assert_max_bit_size must be known at compile-time";
+                let kind = RuntimeErrorKind::UnstructuredError { message: message.to_owned() };
+                self.errors.push(RuntimeError::new(kind, Some(call.location)));
+                return Self::unit_value();
+            }
+        };
+
+        match dfg.get_numeric_constant(value) {
+            Some(constant) if constant.num_bits() > bit_size => {
+                let message = format!("{constant} does not fit within {bit_size} bits");
+                let kind = RuntimeErrorKind::UnstructuredError { message };
+                self.errors.push(RuntimeError::new(kind, Some(call.location)));
+            }
+            Some(_) => (),
+            None => self.insert_range_check(value, bit_size),
+        }
+        Self::unit_value()
+    }
+
+    /// Codegen for the `wrapping_add`, `wrapping_sub`, and `wrapping_mul` builtins. These are
+    /// inserted as ordinary binary operations, whose integer results are truncated to the bit
+    /// size of their type, so they wrap around without any overflow check.
+    fn codegen_wrapping(&mut self, call: &ast::Call, operator: BinaryOpKind) -> Values {
+        let lhs = self.codegen_non_tuple_expression(&call.arguments[0]);
+        let rhs = self.codegen_non_tuple_expression(&call.arguments[1]);
+        self.insert_binary(lhs, operator, rhs)
+    }
+
+    /// Codegen for the `into` and `from` conversion builtins. The converted value is flattened
+    /// and rebuilt in the shape of the return type, so a numeric value is cast to the returned
+    /// type, and a struct is rearranged into another with the same number of fields, casting
+    /// each numeric field to the type of its counterpart.
+    fn codegen_conversion(&mut self, call: &ast::Call) -> Values {
+        let values = self.codegen_expression(&call.arguments[0]).into_value_list(self);
+        let target = Self::convert_type(&call.return_type);
+
+        let types = target.clone().flatten();
+        let convertible = values.len() == types.len()
+            && values.iter().zip(&types).all(|(value, typ)| {
+                match (self.builder.type_of_value(*value), typ) {
+                    (Type::Numeric(_), Type::Numeric(_)) => true,
+                    (value_type, typ) => value_type == *typ,
+                }
+            });
+
+        if !convertible {
+            let message = format!("Cannot convert this value into {}", call.return_type);
+            let kind = RuntimeErrorKind::UnstructuredError { message };
+            self.errors.push(RuntimeError::new(kind, Some(call.location)));
+            return Self::unit_value();
+        }
+
+        let mut values = values.into_iter();
+        target.map(|typ| {
+            let value = values.next().expect("ICE: Conversion should have one value per field");
+            if self.builder.type_of_value(value) == typ {
+                value.into()
+            } else {
+                self.builder.insert_cast(value, typ).into()
+            }
+        })
+    }
+
+    /// Codegen for the builtins describing the modulus of the native field, which fold to the
+    /// number of bits in the modulus, or to an array of its bits or bytes in either endianness.
+    /// Direct calls to these are usually already evaluated during monomorphization.
+    fn codegen_field_modulus(&mut self, name: &str) -> Values {
+        let modulus = FieldElement::modulus();
+        let (digits, element_type) = match name {
+            "modulus_num_bits" => {
+                let num_bits = FieldElement::max_num_bits() as u128;
+                return self.builder.field_constant(num_bits).into();
+            }
+            "modulus_le_bits" => (modulus.to_radix_le(2), Type::bool()),
+            "modulus_be_bits" => (modulus.to_radix_be(2), Type::bool()),
+            "modulus_le_bytes" => (modulus.to_bytes_le(), Type::unsigned(8)),
+            "modulus_be_bytes" => (modulus.to_bytes_be(), Type::unsigned(8)),
+            other => unreachable!("ICE: {other} is not a field modulus builtin"),
+        };
+
+        let elements = digits
+            .into_iter()
+            .map(|digit| self.builder.numeric_constant(digit as u128, element_type.clone()))
+            .collect();
+        self.builder.array_constant(elements, Rc::new(vec![element_type])).into()
+    }
+
+    /// Codegen for the `array_from_fn` builtin. The length of the returned array is known at
+    /// compile-time, so the call is unrolled into a call to the given function for each constant
+    /// index, and the results are collected into a new array.
+    fn codegen_array_from_fn(&mut self, call: &ast::Call) -> Values {
+        let (length, element_type) = match &call.return_type {
+            ast::Type::Array(length, element_type) => (*length, element_type.as_ref()),
+            other => unreachable!("ICE: array_from_fn should return an array, found {other}"),
+        };
+
+        let function = self.codegen_non_tuple_expression(&call.arguments[0]);
+        let mut elements = vecmap(0..length as u128, |i| {
+            let index = self.builder.field_constant(i);
+            self.insert_call(function, vec![index], element_type)
+        });
+
+        if let ast::Type::String(len) = element_type {
+            elements = vecmap(elements, |string| self.codegen_string_bytes(string, *len));
+        }
+        let element_types = Self::convert_element_types(element_type);
+        self.codegen_array(elements, element_types)
+    }
+
+    /// Codegen for the `array_any` and `array_all` builtins. The length of the array is known at
+    /// compile-time, so the predicate is called on each element in turn and the results are
+    /// combined with `operator`: Or for `any`, And for `all`. An empty array gives the identity
+    /// of the operator, false for `any` and true for `all`.
+    fn codegen_array_predicate(&mut self, call: &ast::Call, operator: BinaryOp) -> Values {
+        let (length, element_type) = match call.func.as_ref() {
+            Expression::Ident(ast::Ident { typ: ast::Type::Function(parameters, _), .. }) => {
+                match &parameters[0] {
+                    ast::Type::Array(length, element_type) => (*length, element_type.as_ref()),
+                    other => unreachable!("ICE: Expected an array to search, found {other}"),
+                }
+            }
+            other => unreachable!("ICE: Expected a builtin identifier, found {other:?}"),
+        };
+
+        let array = self.codegen_non_tuple_expression(&call.arguments[0]);
+        let predicate = self.codegen_non_tuple_expression(&call.arguments[1]);
+
+        let mut result = None;
+        for i in 0..length as u128 {
+            let index = self.builder.field_constant(i);
+            let element = self.codegen_array_index(array, index, element_type);
+            let arguments = element.into_value_list(self);
+            let satisfied = self.insert_call(predicate, arguments, &ast::Type::Bool);
+            let satisfied = satisfied.into_leaf().eval(self);
+            result = Some(match result {
+                Some(result) => self.builder.insert_binary(result, operator, satisfied),
+                None => satisfied,
+            });
+        }
+
+        let identity = (operator == BinaryOp::And) as u128;
+        result.unwrap_or_else(|| self.builder.numeric_constant(identity, Type::bool())).into()
+    }
+
+    /// Codegen for the `field_to_bytes` builtin, which decomposes a field into its 32 little
+    /// endian bytes. The bytes are produced by the `to_le_radix` intrinsic, and are additionally
+    /// constrained to recompose into the original field. Both fold away for a constant field.
+    fn codegen_field_to_bytes(&mut self, call: &ast::Call) -> Values {
+        let field = self.codegen_non_tuple_expression(&call.arguments[0]);
+
+        let to_radix = self.builder.import_intrinsic_id(Intrinsic::ToRadix(Endian::Little));
+        let radix = self.builder.numeric_constant(256u128, Type::unsigned(32));
+        let limb_count = self.builder.numeric_constant(32u128, Type::unsigned(32));
+        let bytes_type = Type::Array(Rc::new(vec![Type::unsigned(8)]), 32);
+        let arguments = vec![field, radix, limb_count];
+        let bytes = self.builder.insert_call(to_radix, arguments, vec![bytes_type])[0];
+
+        let elements = self.byte_elements(bytes, 32);
+        let recomposed = self.recompose_bytes(&elements);
+        self.builder.insert_constrain_eq(recomposed, field, Some(call.location));
+        bytes.into()
+    }
+
+    /// Codegen for the `bytes_to_field` builtin, which recomposes an array of at most 32 little
+    /// endian bytes into a field. Each byte is range checked to fit within 8 bits first.
+    fn codegen_bytes_to_field(&mut self, call: &ast::Call) -> Values {
+        let bytes = self.codegen_non_tuple_expression(&call.arguments[0]);
+        let length = match self.builder.type_of_value(bytes) {
+            Type::Array(_, length) => length,
+            other => unreachable!("ICE: Expected an array of bytes, found {other}"),
+        };
+
+        if length > 32 {
+            let message = format!("bytes_to_field takes at most 32 bytes, found {length}");
+            let kind = RuntimeErrorKind::UnstructuredError { message };
+            self.errors.push(RuntimeError::new(kind, Some(call.location)));
+        }
+
+        let elements = self.byte_elements(bytes, length);
+        for element in &elements {
+            self.insert_range_check(*element, 8);
+        }
+        self.recompose_bytes(&elements).into()
+    }
+
+    /// Reads each of the `length` bytes of the given array.
+    fn byte_elements(&mut self, bytes: ValueId, length: usize) -> Vec<ValueId> {
+        vecmap(0..length as u128, |i| {
+            let index = self.builder.field_constant(i);
+            self.builder.insert_array_get(bytes, index, Type::unsigned(8))
+        })
+    }
+
+    /// Recomposes the given little endian bytes into a field: the sum of `byte_i * 256^i`.
+    fn recompose_bytes(&mut self, bytes: &[ValueId]) -> ValueId {
+        let mut result = self.builder.field_constant(0u128);
+        let mut power = FieldElement::one();
+        for byte in bytes {
+            let byte = self.builder.insert_cast(*byte, Type::field());
+            let coefficient = self.builder.field_constant(power);
+            let term = self.builder.insert_binary(byte, BinaryOp::Mul, coefficient);
+            result = self.builder.insert_binary(result, BinaryOp::Add, term);
+            power = power * FieldElement::from(256u128);
+        }
+        result
+    }
+
+    /// Codegen for the `option_is_some` builtin. An Option is a tuple of its tag followed by
+    /// its payload, so this is just the tag.
+    fn codegen_option_is_some(&mut self, call: &ast::Call) -> Values {
+        let option = self.codegen_expression(&call.arguments[0]);
+        Self::get_field(option, 0)
+    }
+
+    /// Codegen for the `option_unwrap_or` builtin, which selects the payload of the Option if
+    /// its tag is set, and the given default otherwise.
+    fn codegen_option_unwrap_or(&mut self, call: &ast::Call) -> Values {
+        let option = self.codegen_expression(&call.arguments[0]);
+        let default = self.codegen_expression(&call.arguments[1]);
+        let tag = Self::get_field_ref(&option, 0).clone().into_leaf().eval(self);
+        let payload = Self::get_field(option, 1);
+        self.codegen_select(tag, &call.return_type, |_| payload, |_| default)
+    }
+
+    /// Codegen for the `option_map` builtin. The function is only called on the payload if the
+    /// tag of the Option is set, otherwise the returned payload is zeroed. The tag is unchanged.
+    fn codegen_option_map(&mut self, call: &ast::Call) -> Values {
+        let payload_type = match &call.return_type {
+            ast::Type::Tuple(fields) => &fields[1],
+            other => unreachable!("ICE: Expected option_map to return an Option, found {other}"),
+        };
+
+        let option = self.codegen_expression(&call.arguments[0]);
+        let function = self.codegen_non_tuple_expression(&call.arguments[1]);
+        let tag = Self::get_field_ref(&option, 0).clone();
+        let condition = tag.clone().into_leaf().eval(self);
+        let payload = Self::get_field(option, 1);
+
+        let mapped = self.codegen_select(
+            condition,
+            payload_type,
+            |this| {
+                let arguments = payload.into_value_list(this);
+                this.insert_call(function, arguments, payload_type)
+            },
+            |this| this.default_value(payload_type),
+        );
+        Tree::Branch(vec![tag, mapped])
+    }
+
+    /// Branches on `condition`, generating the value of type `typ` with `then_branch` if it is
+    /// true and with `else_branch` otherwise, and returns the value of whichever branch ran.
+    fn codegen_select(
+        &mut self,
+        condition: ValueId,
+        typ: &ast::Type,
+        then_branch: impl FnOnce(&mut Self) -> Values,
+        else_branch: impl FnOnce(&mut Self) -> Values,
+    ) -> Values {
+        let then_block = self.builder.insert_block();
+        let else_block = self.builder.insert_block();
+        let end_block = self.builder.insert_block();
+        self.builder.terminate_with_jmpif(condition, then_block, else_block);
+
+        self.builder.switch_to_block(then_block);
+        let then_values = then_branch(self).into_value_list(self);
+        self.builder.terminate_with_jmp(end_block, then_values);
+
+        self.builder.switch_to_block(else_block);
+        let else_values = else_branch(self).into_value_list(self);
+        self.builder.terminate_with_jmp(end_block, else_values);
+
+        self.builder.switch_to_block(end_block);
+        Self::map_type(typ, |typ| self.builder.add_block_parameter(end_block, typ).into())
+    }
+
     /// Generate SSA for the given variable.
     /// If the variable is immutable, no special handling is necessary and we can return the given
     /// ValueId directly. If it is mutable, we'll need to allocate space for the value and store
@@ -367,16 +839,61 @@ impl<'a> FunctionContext<'a> {
         Self::unit_value()
     }
 
-    fn codegen_constrain(&mut self, expr: &Expression, _location: Location) -> Values {
+    /// Codegens a constrain. The operands of `assert(a == b)` are constrained to be equal, and
+    /// those of `assert(a != b)` to differ, directly rather than by constraining the result of
+    /// comparing them.
+    fn codegen_constrain(&mut self, expr: &Expression, location: Location) -> Values {
+        if let Expression::Binary(binary) = expr {
+            if binary.operator == BinaryOpKind::Equal {
+                self.codegen_constrain_equal(&binary.lhs, &binary.rhs, location);
+                return Self::unit_value();
+            }
+            if binary.operator == BinaryOpKind::NotEqual {
+                let lhs = self.codegen_non_tuple_expression(&binary.lhs);
+                let rhs = self.codegen_non_tuple_expression(&binary.rhs);
+
+                // Arrays are compared element-wise, so they still constrain a boolean
+                if matches!(self.builder.type_of_value(lhs), Type::Array(..)) {
+                    let not_equal = self.insert_binary(lhs, binary.operator, rhs);
+                    let not_equal = not_equal.into_leaf().eval(self);
+                    self.builder.insert_constrain(not_equal, Some(location));
+                } else {
+                    self.builder.insert_constrain_ne(lhs, rhs, Some(location));
+                }
+                return Self::unit_value();
+            }
+        }
+
         let boolean = self.codegen_non_tuple_expression(expr);
-        self.builder.insert_constrain(boolean);
+        self.builder.insert_constrain(boolean, Some(location));
         Self::unit_value()
     }
 
+    /// Constrains each leaf value of `lhs` to equal the corresponding leaf of `rhs`.
+    /// Arrays are compared element-wise, so their equality is still constrained as a boolean.
+    fn codegen_constrain_equal(&mut self, lhs: &Expression, rhs: &Expression, location: Location) {
+        let lhs = self.codegen_expression(lhs).into_value_list(self);
+        let rhs = self.codegen_expression(rhs).into_value_list(self);
+
+        for (lhs, rhs) in lhs.into_iter().zip(rhs) {
+            if matches!(self.builder.type_of_value(lhs), Type::Array(..)) {
+                let equal = self.insert_binary(lhs, BinaryOpKind::Equal, rhs);
+                let equal = equal.into_leaf().eval(self);
+                self.builder.insert_constrain(equal, Some(location));
+            } else {
+                self.builder.insert_constrain_eq(lhs, rhs, Some(location));
+            }
+        }
+    }
+
     fn codegen_assign(&mut self, assign: &ast::Assign) -> Values {
         let lhs = self.extract_current_value(&assign.lvalue);
         let rhs = self.codegen_expression(&assign.expression);
 
+        // Evaluate every value of the rhs before storing any of them, so that an assignment
+        // such as `t = (t.1, t.0)` reads both fields before either is overwritten.
+        let rhs = rhs.map(|value| value.eval(self).into());
+
         self.assign_new_value(lhs, rhs);
         Self::unit_value()
     }
@@ -386,3 +903,956 @@ impl<'a> FunctionContext<'a> {
         Self::unit_value()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::rc::Rc;
+
+    use acvm::{acir::BlackBoxFunc, FieldElement};
+    use iter_extended::vecmap;
+    use noirc_frontend::{monomorphization::ast, BinaryOpKind, UnaryOp};
+
+    use crate::ssa_refactor::ir::{
+        instruction::{
+            BinaryOp, Endian, Instruction, InstructionId, Intrinsic, TerminatorInstruction,
+        },
+        interpreter::{interpret, InterpreterError, InterpreterValue},
+        types::Type,
+        value::Value as IrValue,
+    };
+
+    use super::{
+        test_utils::{
+            array_literal, binary, builtin, call, codegen_main, empty_shared_context, field,
+            function, function_call, function_ident, generate_functions, literal, local_ident,
+            location, main_context, mutable_ident, parameter, signed, unsigned,
+        },
+        value::Value,
+    };
+
+    fn into_call(
+        argument: ast::Expression,
+        argument_type: ast::Type,
+        return_type: ast::Type,
+    ) -> ast::Expression {
+        let into = builtin("into", vec![argument_type], return_type.clone());
+        call(into, vec![argument], return_type)
+    }
+
+    #[test]
+    fn numeric_into_is_a_cast() {
+        let x = ast::LocalId(0);
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&vec![parameter(x, "x", unsigned(8))], &shared_context);
+
+        let call = into_call(local_ident(x, "x", unsigned(8)), unsigned(8), ast::Type::Field);
+        let result = context.codegen_expression(&call).into_leaf().eval(&mut context);
+        assert!(context.errors.is_empty());
+
+        let block = context.builder.current_block();
+        let dfg = &context.builder.current_function.dfg;
+        let instructions = dfg[block].instructions();
+        assert_eq!(instructions.len(), 1);
+        let v0 = context.builder.current_function.parameters()[0];
+        assert_eq!(dfg[instructions[0]], Instruction::Cast(v0, Type::field()));
+        assert_eq!(dfg.instruction_results(instructions[0]), &[result]);
+    }
+
+    #[test]
+    fn into_a_struct_of_a_different_shape_fails() {
+        let x = ast::LocalId(0);
+        let parameters = vec![parameter(x, "x", ast::Type::Field)];
+        let pair = ast::Type::Tuple(vec![ast::Type::Field, ast::Type::Field]);
+        let call = into_call(local_ident(x, "x", ast::Type::Field), ast::Type::Field, pair);
+
+        let (_, errors) = codegen_main(&parameters, &call);
+        assert_eq!(errors.len(), 1);
+    }
+
+    fn assert_max_bit_size_call(value: ast::Expression, bit_size: u128) -> ast::Expression {
+        let parameter_types = vec![ast::Type::Field, unsigned(32)];
+        let func = builtin("assert_max_bit_size", parameter_types, ast::Type::Unit);
+        call(func, vec![value, literal(bit_size, unsigned(32))], ast::Type::Unit)
+    }
+
+    #[test]
+    fn assert_max_bit_size_inserts_a_range_check() {
+        let x = ast::LocalId(0);
+        let parameters = vec![parameter(x, "x", ast::Type::Field)];
+        let call = assert_max_bit_size_call(local_ident(x, "x", ast::Type::Field), 8);
+        let (ssa, errors) = codegen_main(&parameters, &call);
+        assert!(errors.is_empty());
+
+        // x is decomposed into 8 bits, which constrains it to fit within them
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 1);
+        match &main.dfg[instructions[0]] {
+            Instruction::Call { func, arguments } => {
+                assert_eq!(main.dfg[*func], IrValue::Intrinsic(Intrinsic::ToBits(Endian::Little)));
+                assert_eq!(main.dfg.get_numeric_constant(arguments[1]), Some(8u128.into()));
+            }
+            other => panic!("Expected a call to to_le_bits, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn assert_max_bit_size_of_constant_is_checked_at_compile_time() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        context.codegen_expression(&assert_max_bit_size_call(field(255), 8));
+        assert!(context.errors.is_empty());
+
+        context.codegen_expression(&assert_max_bit_size_call(field(256), 8));
+        assert_eq!(context.errors.len(), 1);
+
+        // No range check is inserted for either constant
+        let block = context.builder.current_block();
+        assert!(context.builder.current_function.dfg[block].instructions().is_empty());
+    }
+
+    fn assert_constant_call(argument: ast::Expression) -> ast::Expression {
+        let func = builtin("assert_constant", vec![ast::Type::Field], ast::Type::Unit);
+        call(func, vec![argument], ast::Type::Unit)
+    }
+
+    #[test]
+    fn assert_constant_is_checked_at_compile_time() {
+        let (ssa, errors) = codegen_main(&Vec::new(), &assert_constant_call(field(3)));
+        assert!(errors.is_empty());
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+
+        let x = ast::LocalId(0);
+        let parameters = vec![parameter(x, "x", ast::Type::Field)];
+        let call = assert_constant_call(local_ident(x, "x", ast::Type::Field));
+        let (_, errors) = codegen_main(&parameters, &call);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].location, Some(location()));
+    }
+
+    #[test]
+    fn array_of_tuples_is_flattened() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        // [(1, 2), (3, 4)]
+        let array = array_literal(
+            vec![
+                ast::Expression::Tuple(vec![field(1), field(2)]),
+                ast::Expression::Tuple(vec![field(3), field(4)]),
+            ],
+            ast::Type::Tuple(vec![ast::Type::Field, ast::Type::Field]),
+        );
+        let array = context.codegen_expression(&array).into_leaf().eval(&mut context);
+
+        let dfg = &context.builder.current_function.dfg;
+        assert!(dfg.is_constant_array(array));
+        match &dfg[array] {
+            IrValue::Array { array, .. } => {
+                let elements = vecmap(array, |element| dfg.get_numeric_constant(*element));
+                let expected = vecmap(1..=4u128, |value| Some(value.into()));
+                assert_eq!(elements, expected);
+            }
+            other => panic!("Expected a constant array, found {other:?}"),
+        }
+        assert_eq!(dfg.type_of_value(array), Type::Array(Rc::new(vec![Type::field(); 2]), 2));
+    }
+
+    fn string(value: &str) -> ast::Expression {
+        ast::Expression::Literal(ast::Literal::Str(value.into()))
+    }
+
+    #[test]
+    fn string_literal_is_an_array_of_u8() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let result = context.codegen_expression(&string("hi")).into_leaf().eval(&mut context);
+
+        let dfg = &context.builder.current_function.dfg;
+        assert_eq!(dfg.type_of_value(result), Type::Array(Rc::new(vec![Type::unsigned(8)]), 2));
+
+        let (bytes, element_types) =
+            dfg.get_array_constant(result).expect("Expected a constant string");
+        assert_eq!(*element_types, vec![Type::unsigned(8)]);
+        for byte in bytes {
+            assert_eq!(dfg.type_of_value(byte), Type::unsigned(8));
+        }
+    }
+
+    #[test]
+    fn array_of_strings_is_indexed_by_string_length() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let array = array_literal(vec![string("ab"), string("cd")], ast::Type::String(2));
+        let index = ast::Expression::Index(ast::Index {
+            collection: Box::new(array),
+            index: Box::new(field(1)),
+            element_type: ast::Type::String(2),
+            location: location(),
+        });
+
+        let result = context.codegen_expression(&index).into_leaf().eval(&mut context);
+        let dfg = &context.builder.current_function.dfg;
+        let (bytes, element_types) =
+            dfg.get_array_constant(result).expect("Expected a constant string");
+        assert_eq!(*element_types, vec![Type::char()]);
+
+        let bytes = vecmap(bytes, |byte| dfg.get_numeric_constant(byte).unwrap().to_u128());
+        assert_eq!(bytes, vec![b'c' as u128, b'd' as u128]);
+    }
+
+    fn hash_call(name: &str, element_type: ast::Type) -> ast::Expression {
+        let input = array_literal(
+            vecmap(1..=2u128, |value| literal(value, element_type.clone())),
+            element_type.clone(),
+        );
+        let input_type = ast::Type::Array(2, Box::new(element_type));
+        let output_type = ast::Type::Array(32, Box::new(unsigned(8)));
+        let definition = ast::Definition::LowLevel(name.into());
+        let func = function_ident(definition, name, vec![input_type], output_type.clone());
+        call(func, vec![input], output_type)
+    }
+
+    #[test]
+    fn hashes_are_black_box_calls_of_bytes() {
+        let (ssa, errors) = codegen_main(&Vec::new(), &hash_call("sha256", unsigned(8)));
+        assert!(errors.is_empty());
+
+        let main = ssa.main();
+        let calls: Vec<_> = main.dfg[main.entry_block()]
+            .instructions()
+            .iter()
+            .filter_map(|instruction| match &main.dfg[*instruction] {
+                Instruction::Call { func, .. } => Some(&main.dfg[*func]),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(calls.len(), 1);
+        assert!(matches!(calls[0], IrValue::Intrinsic(Intrinsic::BlackBox(BlackBoxFunc::SHA256))));
+
+        let (_, errors) = codegen_main(&Vec::new(), &hash_call("blake2s", ast::Type::Field));
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn array_from_fn_is_unrolled_with_constant_indices() {
+        // fn main() -> [Field; 3] {
+        //     from_fn(identity)
+        // }
+        // fn identity(i: Field) -> Field {
+        //     i
+        // }
+        let array_type = ast::Type::Array(3, Box::new(ast::Type::Field));
+        let identity = function_ident(
+            ast::Definition::Function(ast::FuncId(1)),
+            "identity",
+            vec![ast::Type::Field],
+            ast::Type::Field,
+        );
+        let from_fn = builtin("array_from_fn", vec![ast::Type::Field], array_type.clone());
+        let body = call(from_fn, vec![identity], array_type.clone());
+        let main = function(0, "main", Vec::new(), body, array_type);
+
+        let i = ast::LocalId(0);
+        let parameters = vec![parameter(i, "i", ast::Type::Field)];
+        let body = local_ident(i, "i", ast::Type::Field);
+        let identity = function(1, "identity", parameters, body, ast::Type::Field);
+
+        let ssa = generate_functions(vec![main, identity]).inline_functions();
+        let expected = vecmap(0..3u128, InterpreterValue::numeric);
+        assert_eq!(interpret(ssa.main(), Vec::new()), Ok(vec![InterpreterValue::Array(expected)]));
+    }
+
+    #[test]
+    fn array_any_ors_the_predicate_of_each_element() {
+        // fn main(array: [Field; 3]) -> bool {
+        //     array.any(is_three)
+        // }
+        // fn is_three(x: Field) -> bool {
+        //     x == 3
+        // }
+        let array_type = ast::Type::Array(3, Box::new(ast::Type::Field));
+        let function_type = ast::Type::Function(vec![ast::Type::Field], Box::new(ast::Type::Bool));
+
+        let array = ast::LocalId(0);
+        let is_three = function_ident(
+            ast::Definition::Function(ast::FuncId(1)),
+            "is_three",
+            vec![ast::Type::Field],
+            ast::Type::Bool,
+        );
+        let any = builtin("array_any", vec![array_type.clone(), function_type], ast::Type::Bool);
+        let arguments = vec![local_ident(array, "array", array_type.clone()), is_three];
+        let parameters = vec![parameter(array, "array", array_type)];
+        let body = call(any, arguments, ast::Type::Bool);
+        let main = function(0, "main", parameters, body, ast::Type::Bool);
+
+        let x = ast::LocalId(1);
+        let parameters = vec![parameter(x, "x", ast::Type::Field)];
+        let body = binary(local_ident(x, "x", ast::Type::Field), BinaryOpKind::Equal, field(3));
+        let is_three = function(1, "is_three", parameters, body, ast::Type::Bool);
+
+        let ssa = generate_functions(vec![main, is_three]).inline_functions();
+
+        // After inlining, main compares each element to 3 and ors the three results together
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        let count = |operator| {
+            let is_operator = |instruction: &&InstructionId| match &main.dfg[**instruction] {
+                Instruction::Binary(binary) => binary.operator == operator,
+                _ => false,
+            };
+            instructions.iter().filter(is_operator).count()
+        };
+        assert_eq!(count(BinaryOp::Eq), 3);
+        assert_eq!(count(BinaryOp::Or), 2);
+
+        let array = |elements: [u128; 3]| {
+            InterpreterValue::Array(elements.into_iter().map(InterpreterValue::numeric).collect())
+        };
+        let one = InterpreterValue::numeric(1);
+        let zero = InterpreterValue::numeric(0);
+        assert_eq!(interpret(main, vec![array([1, 2, 3])]), Ok(vec![one]));
+        assert_eq!(interpret(main, vec![array([1, 2, 4])]), Ok(vec![zero]));
+    }
+
+    #[test]
+    fn for_loop_is_lowered_to_header_body_and_exit_blocks() {
+        // for i in 0 .. 3 { i }
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let i = ast::LocalId(0);
+        let for_expr = ast::Expression::For(ast::For {
+            index_variable: i,
+            index_name: "i".into(),
+            index_type: ast::Type::Field,
+            start_range: Box::new(field(0)),
+            end_range: Box::new(field(3)),
+            block: Box::new(local_ident(i, "i", ast::Type::Field)),
+        });
+
+        // Although the body returns a value, the loop itself is a statement
+        assert_eq!(context.codegen_expression(&for_expr).count_leaves(), 0);
+
+        let loop_end = context.builder.current_block();
+        let dfg = &context.builder.current_function.dfg;
+        let entry = context.builder.current_function.entry_block();
+
+        let loop_entry = match dfg[entry].unwrap_terminator() {
+            TerminatorInstruction::Jmp { destination, arguments } => {
+                assert_eq!(dfg.get_numeric_constant(arguments[0]), Some(FieldElement::zero()));
+                *destination
+            }
+            other => panic!("Expected a jmp to the loop header, found {other:?}"),
+        };
+        assert_eq!(dfg.block_parameters(loop_entry).len(), 1);
+
+        let loop_body = match dfg[loop_entry].unwrap_terminator() {
+            TerminatorInstruction::JmpIf { then_destination, else_destination, .. } => {
+                assert_eq!(*else_destination, loop_end);
+                *then_destination
+            }
+            other => panic!("Expected the loop header to branch, found {other:?}"),
+        };
+
+        match dfg[loop_body].unwrap_terminator() {
+            TerminatorInstruction::Jmp { destination, arguments } => {
+                assert_eq!(*destination, loop_entry);
+                assert_eq!(arguments.len(), 1);
+            }
+            other => panic!("Expected the loop body to jump back to its header, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn call_flattens_tuple_arguments_and_reshapes_results() {
+        // fn main(x: (Field, Field)) -> (Field, Field) {
+        //     foo(x)
+        // }
+        let x = ast::LocalId(0);
+        let tuple_type = ast::Type::Tuple(vec![ast::Type::Field, ast::Type::Field]);
+        let shared_context = empty_shared_context();
+        let mut context =
+            main_context(&vec![parameter(x, "x", tuple_type.clone())], &shared_context);
+
+        let argument = local_ident(x, "x", tuple_type.clone());
+        let call = function_call(1, vec![argument], tuple_type);
+        assert_eq!(context.codegen_expression(&call).count_leaves(), 2);
+
+        let function = &context.builder.current_function;
+        let instructions = function.dfg[function.entry_block()].instructions();
+        assert_eq!(instructions.len(), 1);
+        match &function.dfg[instructions[0]] {
+            Instruction::Call { func, arguments } => {
+                assert!(matches!(function.dfg[*func], IrValue::Function(_)));
+                assert_eq!(arguments.as_slice(), function.parameters());
+            }
+            other => panic!("Expected a call, found {other:?}"),
+        }
+        assert_eq!(function.dfg.instruction_results(instructions[0]).len(), 2);
+
+        // The callee was queued for codegen when it was first referenced
+        let (queued, _) = shared_context.pop_next_function_in_queue().unwrap();
+        assert_eq!(queued, ast::FuncId(1));
+
+        // A call without results returns the unit value
+        let call = function_call(1, Vec::new(), ast::Type::Unit);
+        assert_eq!(context.codegen_expression(&call).count_leaves(), 0);
+    }
+
+    /// `let x = (1, 2);`, which is mutable if `mutable` is set.
+    fn let_tuple(id: ast::LocalId, name: &str, mutable: bool) -> ast::Expression {
+        let expression = Box::new(ast::Expression::Tuple(vec![field(1), field(2)]));
+        ast::Expression::Let(ast::Let { id, mutable, name: name.into(), expression })
+    }
+
+    #[test]
+    fn immutable_let_binds_values_without_allocating() {
+        // let x = (1, 2);
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let x = ast::LocalId(0);
+        assert_eq!(context.codegen_expression(&let_tuple(x, "x", false)).count_leaves(), 0);
+
+        let function = &context.builder.current_function;
+        assert!(function.dfg[function.entry_block()].instructions().is_empty());
+
+        let fields = context.lookup(x).flatten();
+        assert_eq!(fields.len(), 2);
+        for (field, expected) in fields.into_iter().zip([1u128, 2]) {
+            match field {
+                Value::Normal(value) => {
+                    let constant = context.builder.current_function.dfg.get_numeric_constant(value);
+                    assert_eq!(constant, Some(expected.into()));
+                }
+                Value::Mutable(..) => panic!("Expected an immutable binding"),
+            }
+        }
+    }
+
+    #[test]
+    fn mutable_let_allocates_each_field() {
+        // let mut x = (1, 2);
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let x = ast::LocalId(0);
+        context.codegen_expression(&let_tuple(x, "x", true));
+
+        let function = &context.builder.current_function;
+        let instructions = function.dfg[function.entry_block()].instructions();
+        let allocations = instructions
+            .iter()
+            .filter(|id| matches!(function.dfg[**id], Instruction::Allocate))
+            .count();
+        assert_eq!(allocations, 2);
+        assert_eq!(instructions.len(), 4);
+
+        let fields = context.lookup(x).flatten();
+        assert!(fields.iter().all(|field| matches!(field, Value::Mutable(..))));
+    }
+
+    fn wrapping_call(name: &str, lhs: ast::Expression, rhs: ast::Expression) -> ast::Expression {
+        let func = builtin(name, vec![unsigned(8), unsigned(8)], unsigned(8));
+        call(func, vec![lhs, rhs], unsigned(8))
+    }
+
+    #[test]
+    fn wrapping_add_of_constants_wraps_around() {
+        // wrapping_add(255u8, 1u8)
+        let call = wrapping_call(
+            "wrapping_add",
+            literal(255u128, unsigned(8)),
+            literal(1u128, unsigned(8)),
+        );
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let result = context.codegen_non_tuple_expression(&call);
+
+        let function = &context.builder.current_function;
+        assert_eq!(function.dfg.get_numeric_constant(result), Some(FieldElement::zero()));
+        assert!(function.dfg[function.entry_block()].instructions().is_empty());
+    }
+
+    #[test]
+    fn wrapping_operations_are_not_checked() {
+        let (x, y) = (ast::LocalId(0), ast::LocalId(1));
+        let parameters = vec![parameter(x, "x", unsigned(8)), parameter(y, "y", unsigned(8))];
+        let body = ast::Expression::Block(vecmap(
+            ["wrapping_add", "wrapping_sub", "wrapping_mul"],
+            |name| {
+                let lhs = local_ident(x, "x", unsigned(8));
+                let rhs = local_ident(y, "y", unsigned(8));
+                ast::Expression::Semi(Box::new(wrapping_call(name, lhs, rhs)))
+            },
+        ));
+        let (ssa, _) = codegen_main(&parameters, &body);
+
+        // Each operation is truncated back to a u8 without constraining the untruncated result
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 6);
+        for instruction in instructions {
+            let instruction = &main.dfg[*instruction];
+            assert!(matches!(instruction, Instruction::Binary(_) | Instruction::Truncate { .. }));
+        }
+    }
+
+    #[test]
+    fn array_element_assignment_stores_the_updated_array() {
+        // fn main() -> [Field; 2] {
+        //     let mut a = [1, 2];
+        //     a[1] = 5;
+        //     a
+        // }
+        let array_type = ast::Type::Array(2, Box::new(ast::Type::Field));
+        let a = ast::LocalId(0);
+        let ident = mutable_ident(a, "a", array_type.clone());
+
+        let let_array = ast::Expression::Let(ast::Let {
+            id: a,
+            mutable: true,
+            name: "a".into(),
+            expression: Box::new(array_literal(vec![field(1), field(2)], ast::Type::Field)),
+        });
+        let assign = ast::Expression::Assign(ast::Assign {
+            lvalue: ast::LValue::Index {
+                array: Box::new(ast::LValue::Ident(ident.clone())),
+                index: Box::new(field(1)),
+                element_type: ast::Type::Field,
+                location: location(),
+            },
+            expression: Box::new(field(5)),
+        });
+        let body = ast::Expression::Block(vec![let_array, assign, ast::Expression::Ident(ident)]);
+        let ssa = generate_functions(vec![function(0, "main", Vec::new(), body, array_type)]);
+
+        // The new element is set within a copy of the array, which is stored back to `a`
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        let array_sets = instructions
+            .iter()
+            .filter(|instruction| matches!(main.dfg[**instruction], Instruction::ArraySet { .. }));
+        assert_eq!(array_sets.count(), 1);
+
+        let expected = vecmap([1u128, 5], InterpreterValue::numeric);
+        assert_eq!(interpret(main, Vec::new()), Ok(vec![InterpreterValue::Array(expected)]));
+    }
+
+    #[test]
+    fn tuple_assignment_swaps_fields() {
+        // fn main() -> (Field, Field) {
+        //     let mut t = (1, 2);
+        //     t = (t.1, t.0);
+        //     t
+        // }
+        let tuple_type = ast::Type::Tuple(vec![ast::Type::Field, ast::Type::Field]);
+        let t = ast::LocalId(0);
+        let ident = mutable_ident(t, "t", tuple_type.clone());
+        let extract = |index| {
+            let tuple = Box::new(ast::Expression::Ident(ident.clone()));
+            ast::Expression::ExtractTupleField(tuple, index)
+        };
+        let swap = ast::Expression::Assign(ast::Assign {
+            lvalue: ast::LValue::Ident(ident.clone()),
+            expression: Box::new(ast::Expression::Tuple(vec![extract(1), extract(0)])),
+        });
+
+        let body = ast::Expression::Block(vec![
+            let_tuple(t, "t", true),
+            swap,
+            ast::Expression::Ident(ident.clone()),
+        ]);
+        let ssa = generate_functions(vec![function(0, "main", Vec::new(), body, tuple_type)]);
+
+        let expected = vecmap([2u128, 1], InterpreterValue::numeric);
+        assert_eq!(interpret(ssa.main(), Vec::new()), Ok(expected));
+    }
+
+    fn if_expression(
+        condition: ast::Expression,
+        consequence: ast::Expression,
+        alternative: Option<ast::Expression>,
+        typ: ast::Type,
+    ) -> ast::Expression {
+        ast::Expression::If(ast::If {
+            condition: Box::new(condition),
+            consequence: Box::new(consequence),
+            alternative: alternative.map(Box::new),
+            typ,
+        })
+    }
+
+    #[test]
+    fn if_without_else_terminates_every_block() {
+        // fn main(c: bool) {
+        //     if c { assert(c); }
+        //     if c { if c { assert(c); } }
+        // }
+        let c = ast::LocalId(0);
+        let condition = || local_ident(c, "c", ast::Type::Bool);
+        let if_without_else =
+            |consequence| if_expression(condition(), consequence, None, ast::Type::Unit);
+        let constrain = || ast::Expression::Constrain(Box::new(condition()), location());
+
+        let body = ast::Expression::Block(vec![
+            if_without_else(constrain()),
+            if_without_else(if_without_else(constrain())),
+        ]);
+        let (ssa, _) = codegen_main(&vec![parameter(c, "c", ast::Type::Bool)], &body);
+
+        let dfg = &ssa.main().dfg;
+        assert_eq!(dfg.basic_blocks_iter().len(), 7);
+        for (block, data) in dfg.basic_blocks_iter() {
+            assert!(data.terminator().is_some(), "Block {block} has no terminator");
+        }
+    }
+
+    /// Codegens `fn main(c: bool, x: Field)` returning an if-else on `c` of the given type.
+    fn codegen_if_else(
+        typ: ast::Type,
+        branch: impl Fn(ast::Expression, ast::Expression) -> ast::Expression,
+    ) -> super::Ssa {
+        let (c, x) = (ast::LocalId(0), ast::LocalId(1));
+        let parameters =
+            vec![parameter(c, "c", ast::Type::Bool), parameter(x, "x", ast::Type::Field)];
+        let c = || local_ident(c, "c", ast::Type::Bool);
+        let x = || local_ident(x, "x", ast::Type::Field);
+
+        let body = if_expression(c(), branch(c(), x()), Some(branch(c(), x())), typ);
+        codegen_main(&parameters, &body).0
+    }
+
+    #[test]
+    fn if_statement_passes_no_block_arguments() {
+        // if c { assert(c); } else { assert(c); }
+        let ssa = codegen_if_else(ast::Type::Unit, |c, _| {
+            ast::Expression::Constrain(Box::new(c), location())
+        });
+
+        let main = ssa.main();
+        for block in main.reachable_blocks() {
+            assert!(main.dfg[block].parameters().is_empty());
+            match main.dfg[block].unwrap_terminator() {
+                TerminatorInstruction::Jmp { arguments, .. } => assert!(arguments.is_empty()),
+                TerminatorInstruction::Return { return_values } => {
+                    assert!(return_values.is_empty());
+                }
+                TerminatorInstruction::JmpIf { .. } => (),
+            }
+        }
+    }
+
+    #[test]
+    fn if_expression_passes_each_leaf_as_a_block_argument() {
+        // if c { (x, c) } else { (x, c) }
+        let typ = ast::Type::Tuple(vec![ast::Type::Field, ast::Type::Bool]);
+        let ssa = codegen_if_else(typ, |c, x| ast::Expression::Tuple(vec![x, c]));
+
+        let main = ssa.main();
+        let end_block = main
+            .reachable_blocks()
+            .into_iter()
+            .find(|block| {
+                matches!(main.dfg[*block].terminator(), Some(TerminatorInstruction::Return { .. }))
+            })
+            .unwrap();
+        let parameters = main.dfg[end_block].parameters();
+        assert_eq!(parameters.len(), 2);
+        assert_eq!(main.dfg.type_of_value(parameters[0]), Type::field());
+        assert_eq!(main.dfg.type_of_value(parameters[1]), Type::bool());
+
+        let arguments = vec![InterpreterValue::numeric(1), InterpreterValue::numeric(7)];
+        let expected = vec![InterpreterValue::numeric(7), InterpreterValue::numeric(1)];
+        assert_eq!(interpret(main, arguments), Ok(expected));
+    }
+
+    #[test]
+    fn integer_literal_outside_its_type_is_an_error() {
+        let (_, errors) = codegen_main(&Vec::new(), &literal(300u128, unsigned(8)));
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn asserted_comparisons_use_a_single_constraint() {
+        let (a, b) = (ast::LocalId(0), ast::LocalId(1));
+        let parameters =
+            vec![parameter(a, "a", ast::Type::Field), parameter(b, "b", ast::Type::Field)];
+        let a = || local_ident(a, "a", ast::Type::Field);
+        let b = || local_ident(b, "b", ast::Type::Field);
+        let constrain = |lhs, operator, rhs| {
+            ast::Expression::Constrain(Box::new(binary(lhs, operator, rhs)), location())
+        };
+        let numeric = InterpreterValue::numeric;
+
+        // assert(a != b) replaces the eq, not, and constrain with a single instruction
+        let (ssa, _) = codegen_main(&parameters, &constrain(a(), BinaryOpKind::NotEqual, b()));
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        let (v0, v1) = (main.parameters()[0], main.parameters()[1]);
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(main.dfg[instructions[0]], Instruction::ConstrainNotEqual(v0, v1));
+        assert_eq!(interpret(main, vec![numeric(1), numeric(2)]), Ok(vec![]));
+        assert_eq!(
+            interpret(main, vec![numeric(2), numeric(2)]),
+            Err(InterpreterError::ConstraintFailed)
+        );
+
+        // assert(a == b) replaces the eq and constrain with a single instruction
+        let (ssa, _) = codegen_main(&parameters, &constrain(a(), BinaryOpKind::Equal, b()));
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(main.dfg[instructions[0]], Instruction::ConstrainEqual(v0, v1));
+        assert_eq!(interpret(main, vec![numeric(2), numeric(2)]), Ok(vec![]));
+        assert_eq!(
+            interpret(main, vec![numeric(1), numeric(2)]),
+            Err(InterpreterError::ConstraintFailed)
+        );
+
+        // assert((a, b) == (b, a)) constrains each pair of fields
+        let lhs = ast::Expression::Tuple(vec![a(), b()]);
+        let rhs = ast::Expression::Tuple(vec![b(), a()]);
+        let (ssa, _) = codegen_main(&parameters, &constrain(lhs, BinaryOpKind::Equal, rhs));
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(main.dfg[instructions[0]], Instruction::ConstrainEqual(v0, v1));
+        assert_eq!(main.dfg[instructions[1]], Instruction::ConstrainEqual(v1, v0));
+    }
+
+    #[test]
+    fn constrain_records_the_location_of_the_assert() {
+        let a = ast::LocalId(0);
+        let condition = Box::new(local_ident(a, "a", ast::Type::Bool));
+        let body = ast::Expression::Constrain(condition, location());
+        let (ssa, _) = codegen_main(&vec![parameter(a, "a", ast::Type::Bool)], &body);
+
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 1);
+        assert_eq!(main.dfg[instructions[0]], Instruction::Constrain(main.parameters()[0]));
+        assert_eq!(main.dfg.get_location(instructions[0]), Some(location()));
+    }
+
+    /// Codegens a function returning `<operator> operand`, with a single parameter `x` of the
+    /// given type, along with the number of errors issued.
+    fn codegen_unary(
+        operator: UnaryOp,
+        operand: ast::Expression,
+        parameter_type: ast::Type,
+    ) -> (super::Ssa, usize) {
+        let parameters = vec![parameter(ast::LocalId(0), "x", parameter_type)];
+        let body = ast::Expression::Unary(ast::Unary { operator, rhs: Box::new(operand) });
+        let (ssa, errors) = codegen_main(&parameters, &body);
+        (ssa, errors.len())
+    }
+
+    fn x(typ: ast::Type) -> ast::Expression {
+        local_ident(ast::LocalId(0), "x", typ)
+    }
+
+    #[test]
+    fn negated_integers_wrap_to_their_twos_complement() {
+        let numeric = InterpreterValue::numeric;
+
+        // -(5 as i32) is folded to the bit pattern of -5
+        let (ssa, _) = codegen_unary(UnaryOp::Minus, literal(5u128, signed(32)), signed(32));
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+        assert_eq!(interpret(main, vec![numeric(0)]), Ok(vec![numeric((1 << 32) - 5)]));
+
+        let (ssa, _) = codegen_unary(UnaryOp::Minus, x(signed(32)), signed(32));
+        assert_eq!(interpret(ssa.main(), vec![numeric(5)]), Ok(vec![numeric((1 << 32) - 5)]));
+
+        // Unsigned integers wrap in the same way
+        let (ssa, _) = codegen_unary(UnaryOp::Minus, x(unsigned(8)), unsigned(8));
+        assert_eq!(interpret(ssa.main(), vec![numeric(5)]), Ok(vec![numeric(251)]));
+        assert_eq!(interpret(ssa.main(), vec![numeric(0)]), Ok(vec![numeric(0)]));
+
+        // Fields are negated modulo the field's prime
+        let (ssa, _) = codegen_unary(UnaryOp::Minus, x(ast::Type::Field), ast::Type::Field);
+        let minus_five = InterpreterValue::Numeric(-FieldElement::from(5u128));
+        assert_eq!(interpret(ssa.main(), vec![numeric(5)]), Ok(vec![minus_five]));
+    }
+
+    #[test]
+    fn not_flips_every_bit_of_its_operand() {
+        let numeric = InterpreterValue::numeric;
+
+        // !true == false, folded to a constant
+        let true_literal = ast::Expression::Literal(ast::Literal::Bool(true));
+        let (ssa, errors) = codegen_unary(UnaryOp::Not, true_literal, ast::Type::Bool);
+        assert_eq!(errors, 0);
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+        assert_eq!(interpret(main, vec![numeric(0)]), Ok(vec![numeric(0)]));
+
+        let (ssa, _) = codegen_unary(UnaryOp::Not, x(ast::Type::Bool), ast::Type::Bool);
+        assert_eq!(interpret(ssa.main(), vec![numeric(1)]), Ok(vec![numeric(0)]));
+        assert_eq!(interpret(ssa.main(), vec![numeric(0)]), Ok(vec![numeric(1)]));
+
+        // !0u8 == 255, flipping all eight bits rather than only the lowest
+        let (ssa, errors) = codegen_unary(UnaryOp::Not, literal(0u128, unsigned(8)), unsigned(8));
+        assert_eq!(errors, 0);
+        let main = ssa.main();
+        assert!(main.dfg[main.entry_block()].instructions().is_empty());
+        assert_eq!(interpret(main, vec![numeric(0)]), Ok(vec![numeric(255)]));
+
+        let (ssa, _) = codegen_unary(UnaryOp::Not, x(unsigned(8)), unsigned(8));
+        assert_eq!(interpret(ssa.main(), vec![numeric(0)]), Ok(vec![numeric(255)]));
+        assert_eq!(interpret(ssa.main(), vec![numeric(0xa5)]), Ok(vec![numeric(0x5a)]));
+
+        // Fields have no fixed bit size to flip
+        let (_, errors) = codegen_unary(UnaryOp::Not, x(ast::Type::Field), ast::Type::Field);
+        assert_eq!(errors, 1);
+    }
+
+    /// A call to the builtin of the given name, taking the given arguments of the given types.
+    fn builtin_call(
+        name: &str,
+        arguments: Vec<(ast::Expression, ast::Type)>,
+        return_type: ast::Type,
+    ) -> ast::Expression {
+        let (arguments, parameter_types): (Vec<_>, Vec<_>) = arguments.into_iter().unzip();
+        call(builtin(name, parameter_types, return_type.clone()), arguments, return_type)
+    }
+
+    #[test]
+    fn zeroed_creates_a_zero_for_each_leaf() {
+        // zeroed::<(u8, [Field; 2])>()
+        let array_type = ast::Type::Array(2, Box::new(ast::Type::Field));
+        let call =
+            builtin_call("zeroed", Vec::new(), ast::Type::Tuple(vec![unsigned(8), array_type]));
+
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let leaves = context.codegen_expression(&call).into_value_list(&mut context);
+        assert!(context.errors.is_empty());
+
+        let dfg = &context.builder.current_function.dfg;
+        assert!(dfg[context.builder.current_block()].instructions().is_empty());
+        assert_eq!(leaves.len(), 2);
+        assert_eq!(
+            dfg.get_numeric_constant_with_type(leaves[0]),
+            Some((FieldElement::zero(), Type::unsigned(8)))
+        );
+
+        let (elements, element_types) =
+            dfg.get_array_constant(leaves[1]).expect("Expected a constant array");
+        assert_eq!(*element_types, vec![Type::field()]);
+        assert_eq!(elements.len(), 2);
+        for element in elements {
+            assert_eq!(dfg.get_numeric_constant(element), Some(FieldElement::zero()));
+        }
+    }
+
+    #[test]
+    fn constant_field_round_trips_through_bytes() {
+        // bytes_to_field(field_to_bytes(x)), for a constant x
+        let bytes_type = ast::Type::Array(32, Box::new(unsigned(8)));
+        let x = FieldElement::zero() - FieldElement::one();
+        let to_bytes = builtin_call(
+            "field_to_bytes",
+            vec![(literal(x, ast::Type::Field), ast::Type::Field)],
+            bytes_type.clone(),
+        );
+        let round_trip =
+            builtin_call("bytes_to_field", vec![(to_bytes, bytes_type)], ast::Type::Field);
+
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+        let result = context.codegen_non_tuple_expression(&round_trip);
+        assert!(context.errors.is_empty());
+
+        // The decomposition, its recomposition constraint, and the range checks all fold away
+        let dfg = &context.builder.current_function.dfg;
+        assert!(dfg[context.builder.current_block()].instructions().is_empty());
+        assert_eq!(dfg.get_numeric_constant_with_type(result), Some((x, Type::field())));
+    }
+
+    /// Generates `main` with the given parameters, whose body calls the given builtin with each
+    /// of them as arguments.
+    fn builtin_program(
+        name: &str,
+        parameters: Vec<(&str, ast::Type)>,
+        return_type: ast::Type,
+    ) -> super::Ssa {
+        let parameters = vecmap(parameters.into_iter().enumerate(), |(i, (name, typ))| {
+            parameter(ast::LocalId(i as u32), name, typ)
+        });
+        let arguments = vecmap(&parameters, |(id, _, name, typ)| {
+            (local_ident(*id, name, typ.clone()), typ.clone())
+        });
+        let body = builtin_call(name, arguments, return_type.clone());
+        generate_functions(vec![function(0, "main", parameters, body, return_type)])
+    }
+
+    #[test]
+    fn option_is_some_reads_the_tag() {
+        // fn main(option: Option<Field>) -> bool {
+        //     option.is_some()
+        // }
+        let option_type = ast::Type::Tuple(vec![ast::Type::Bool, ast::Type::Field]);
+        let ssa = builtin_program("option_is_some", vec![("option", option_type)], ast::Type::Bool);
+
+        let main = ssa.main();
+        let entry = &main.dfg[main.entry_block()];
+        assert!(entry.instructions().is_empty());
+        match entry.unwrap_terminator() {
+            TerminatorInstruction::Return { return_values } => {
+                assert_eq!(return_values, &[main.parameters()[0]]);
+            }
+            other => panic!("Expected a return, found {other:?}"),
+        }
+    }
+
+    #[test]
+    fn option_unwrap_or_selects_the_payload_when_set() {
+        // fn main(option: Option<Field>, default: Field) -> Field {
+        //     option.unwrap_or(default)
+        // }
+        let option_type = ast::Type::Tuple(vec![ast::Type::Bool, ast::Type::Field]);
+        let parameters = vec![("option", option_type), ("default", ast::Type::Field)];
+        let ssa = builtin_program("option_unwrap_or", parameters, ast::Type::Field);
+
+        let main = ssa.main();
+        let some = vecmap([1u128, 7, 3], InterpreterValue::numeric);
+        assert_eq!(interpret(main, some), Ok(vec![InterpreterValue::numeric(7)]));
+        let none = vecmap([0u128, 0, 3], InterpreterValue::numeric);
+        assert_eq!(interpret(main, none), Ok(vec![InterpreterValue::numeric(3)]));
+    }
+
+    #[test]
+    fn field_modulus_builtins_fold_to_constants() {
+        let shared_context = empty_shared_context();
+        let mut context = main_context(&Vec::new(), &shared_context);
+
+        let call = builtin_call("modulus_num_bits", Vec::new(), ast::Type::Field);
+        let num_bits = context.codegen_non_tuple_expression(&call);
+
+        let bits_type = ast::Type::Array(0, Box::new(unsigned(1)));
+        let call = builtin_call("modulus_le_bits", Vec::new(), bits_type);
+        let bits = context.codegen_non_tuple_expression(&call);
+
+        let dfg = &context.builder.current_function.dfg;
+        assert!(dfg[context.builder.current_block()].instructions().is_empty());
+
+        let max_num_bits = FieldElement::max_num_bits() as u128;
+        assert_eq!(dfg.get_numeric_constant(num_bits), Some(FieldElement::from(max_num_bits)));
+
+        let (bits, element_types) =
+            dfg.get_array_constant(bits).expect("Expected a constant array");
+        assert_eq!(*element_types, vec![Type::bool()]);
+        assert_eq!(bits.len(), max_num_bits as usize);
+
+        // The modulus is odd, so its least significant bit is set
+        assert_eq!(dfg.get_numeric_constant(bits[0]), Some(FieldElement::one()));
+    }
+}
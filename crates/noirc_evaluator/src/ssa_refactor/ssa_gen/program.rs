@@ -1,10 +1,19 @@
-use std::{collections::BTreeMap, fmt::Display};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Display,
+};
 
-use iter_extended::btree_map;
+use iter_extended::{btree_map, vecmap};
 
-use crate::ssa_refactor::ir::{
-    function::{Function, FunctionId},
-    map::AtomicCounter,
+use crate::{
+    errors::{RuntimeError, RuntimeErrorKind},
+    ssa_refactor::ir::{
+        function::{Function, FunctionId},
+        instruction::Instruction,
+        map::AtomicCounter,
+        types::Type,
+        value::Value,
+    },
 };
 
 /// Contains the entire SSA representation of the program.
@@ -12,13 +21,19 @@ pub(crate) struct Ssa {
     pub(crate) functions: BTreeMap<FunctionId, Function>,
     pub(crate) main_id: FunctionId,
     pub(crate) next_id: AtomicCounter<Function>,
+    /// The types of main's parameters when this program was created. Each parameter of main
+    /// is assigned a witness in order, so no pass may remove or reorder them.
+    /// `Ssa::finalize` checks they are unchanged.
+    pub(crate) main_parameter_types: Vec<Type>,
 }
 
 impl Ssa {
     /// Create a new Ssa object from the given SSA functions.
     /// The first function in this vector is expected to be the main function.
     pub(crate) fn new(functions: Vec<Function>) -> Self {
-        let main_id = functions.first().expect("Expected at least 1 SSA function").id();
+        let main = functions.first().expect("Expected at least 1 SSA function");
+        let main_id = main.id();
+        let main_parameter_types = vecmap(main.parameters(), |p| main.dfg.type_of_value(*p));
         let mut max_id = main_id;
 
         let functions = btree_map(functions, |f| {
@@ -26,7 +41,8 @@ impl Ssa {
             (f.id(), f)
         });
 
-        Self { functions, main_id, next_id: AtomicCounter::starting_after(max_id) }
+        let next_id = AtomicCounter::starting_after(max_id);
+        Self { functions, main_id, next_id, main_parameter_types }
     }
 
     /// Returns the entry-point function of the program
@@ -38,6 +54,107 @@ impl Ssa {
     pub(crate) fn main_mut(&mut self) -> &mut Function {
         self.functions.get_mut(&self.main_id).expect("ICE: Ssa should have a main function")
     }
+
+    /// Changes the id of the function `old_id` to `new_id`, updating every call to it.
+    ///
+    /// If a function with `new_id` already exists, it must be equivalent to the function
+    /// `old_id`, which is then removed. This merges the two functions into one.
+    pub(crate) fn rename_function(&mut self, old_id: FunctionId, new_id: FunctionId) {
+        if old_id == new_id {
+            return;
+        }
+
+        if let Some(mut function) = self.functions.remove(&old_id) {
+            if !self.functions.contains_key(&new_id) {
+                function.set_id(new_id);
+                self.functions.insert(new_id, function);
+            }
+        }
+
+        for function in self.functions.values_mut() {
+            function.dfg.replace_function(old_id, new_id);
+        }
+
+        if self.main_id == old_id {
+            self.main_id = new_id;
+        }
+    }
+
+    /// Links the functions of `other` into this program, keeping the main function of this
+    /// program as the main function of the result.
+    ///
+    /// The functions of `other` are given fresh ids so they cannot collide with those of this
+    /// program. Calls between the programs are made to foreign functions, since neither program
+    /// knows the ids of the other, so each call to a foreign function whose name is defined by
+    /// exactly one function of the merged program becomes a call to that function. Functions of
+    /// the same name in one program are distinct instances of a generic function, but a name
+    /// defined by both programs is an error.
+    pub(crate) fn merge(mut self, mut other: Ssa) -> Result<Ssa, RuntimeError> {
+        let names: HashSet<_> = self.functions.values().map(Function::name).collect();
+        if let Some(duplicate) = other.functions.values().find(|f| names.contains(f.name())) {
+            let message = format!("Duplicate definitions of function {}", duplicate.name());
+            return Err(RuntimeErrorKind::Spanless(message).into());
+        }
+
+        // Renumber after the ids of both programs, so no fresh id is one `other` still uses
+        let max_id = self.functions.keys().chain(other.functions.keys()).max();
+        let next_id = AtomicCounter::starting_after(*max_id.expect("ICE: Ssa has no functions"));
+        for old_id in other.functions.keys().copied().collect::<Vec<_>>() {
+            other.rename_function(old_id, next_id.next());
+        }
+        self.functions.append(&mut other.functions);
+        self.next_id = next_id;
+
+        let mut definitions: HashMap<String, Vec<FunctionId>> = HashMap::new();
+        for (id, function) in &self.functions {
+            definitions.entry(function.name().to_owned()).or_default().push(*id);
+        }
+        let definitions: HashMap<_, _> = definitions
+            .into_iter()
+            .filter_map(|(name, ids)| (ids.len() == 1).then_some((name, ids[0])))
+            .collect();
+
+        for function in self.functions.values_mut() {
+            function.dfg.link_foreign_functions(&definitions);
+        }
+        Ok(self)
+    }
+
+    /// Changes the parameters of the function `id`, rewriting the arguments of every call to
+    /// it to match.
+    ///
+    /// Each entry of `new_parameters` is the index of one of the function's current parameters,
+    /// so parameters may be reordered, or removed by leaving out their index. Any parameter which
+    /// is removed must be unused. Only calls directly to the function are rewritten, so it should
+    /// not also be called through a function value passed elsewhere.
+    pub(crate) fn change_parameters(&mut self, id: FunctionId, new_parameters: &[usize]) {
+        let function = self.functions.get_mut(&id).expect("ICE: No function with the given id");
+        let parameters = vecmap(new_parameters, |index| function.parameters()[*index]);
+        let entry_block = function.entry_block();
+        function.dfg[entry_block].set_parameters(parameters);
+
+        for function in self.functions.values_mut() {
+            let dfg = &mut function.dfg;
+            let calls: HashSet<_> = dfg
+                .basic_blocks_iter()
+                .flat_map(|(_, block)| block.instructions().iter().copied())
+                .filter(|instruction| match &dfg[*instruction] {
+                    Instruction::Call { func, .. } | Instruction::Hint { func, .. } => {
+                        matches!(dfg[dfg.resolve(*func)], Value::Function(callee) if callee == id)
+                    }
+                    _ => false,
+                })
+                .collect();
+
+            for call in calls {
+                if let Instruction::Call { arguments, .. } | Instruction::Hint { arguments, .. } =
+                    &mut dfg[call]
+                {
+                    *arguments = vecmap(new_parameters, |index| arguments[*index]);
+                }
+            }
+        }
+    }
 }
 
 impl Display for Ssa {
@@ -48,3 +165,169 @@ impl Display for Ssa {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::ssa_refactor::{
+        ir::{
+            function::RuntimeType,
+            instruction::{BinaryOp, Instruction},
+            map::Id,
+            types::Type,
+            value::Value,
+        },
+        ssa_builder::FunctionBuilder,
+    };
+
+    #[test]
+    fn merge_identical_functions() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     v3 = call f1(v0)
+        //     v4 = call f2(v3)
+        //     return v4
+        // }
+        // fn double f1 {
+        //   b0(v0: Field):
+        //     v1 = add v0, v0
+        //     return v1
+        // }
+        // fn double f2 {
+        //   b0(v0: Field):
+        //     v1 = add v0, v0
+        //     return v1
+        // }
+        let main_id = Id::test_new(0);
+        let first_id = Id::test_new(1);
+        let second_id = Id::test_new(2);
+
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let first = builder.import_function(first_id);
+        let second = builder.import_function(second_id);
+        let v3 = builder.insert_call(first, vec![v0], vec![Type::field()])[0];
+        let v4 = builder.insert_call(second, vec![v3], vec![Type::field()])[0];
+        builder.terminate_with_return(vec![v4]);
+
+        for id in [first_id, second_id] {
+            builder.new_function("double".into(), id);
+            let v0 = builder.add_parameter(Type::field());
+            let v1 = builder.insert_binary(v0, BinaryOp::Add, v0);
+            builder.terminate_with_return(vec![v1]);
+        }
+
+        let mut ssa = builder.finish();
+        ssa.rename_function(second_id, first_id);
+
+        assert_eq!(ssa.functions.len(), 2);
+        assert!(!ssa.functions.contains_key(&second_id));
+
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 2);
+        for instruction in instructions {
+            match &main.dfg[*instruction] {
+                Instruction::Call { func, .. } => {
+                    assert!(matches!(main.dfg[*func], Value::Function(id) if id == first_id));
+                }
+                other => panic!("Expected only calls, found {other:?}"),
+            }
+        }
+    }
+
+    #[test]
+    fn remove_unused_parameter() {
+        // fn main f0 {
+        //   b0(v0: Field, v1: Field):
+        //     v3 = call f1(v0, v1)
+        //     return v3
+        // }
+        // fn first f1 {
+        //   b0(v0: Field, v1: Field):
+        //     return v0
+        // }
+        let main_id = Id::test_new(0);
+        let first_id = Id::test_new(1);
+
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.add_parameter(Type::field());
+        let first = builder.import_function(first_id);
+        let v3 = builder.insert_call(first, vec![v0, v1], vec![Type::field()])[0];
+        builder.terminate_with_return(vec![v3]);
+
+        builder.new_function("first".into(), first_id);
+        let first_v0 = builder.add_parameter(Type::field());
+        builder.add_parameter(Type::field());
+        builder.terminate_with_return(vec![first_v0]);
+
+        let mut ssa = builder.finish();
+        ssa.change_parameters(first_id, &[0]);
+
+        assert_eq!(ssa.functions[&first_id].parameters(), &[first_v0]);
+
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        assert_eq!(instructions.len(), 1);
+        match &main.dfg[instructions[0]] {
+            Instruction::Call { arguments, .. } => assert_eq!(arguments, &vec![v0]),
+            other => panic!("Expected a call, found {other:?}"),
+        }
+    }
+
+    /// Builds a program whose main function returns `foo(v0)`, calling `foo` by name.
+    fn program_calling_foo() -> Ssa {
+        let main_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("main".into(), main_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let foo = builder.import_foreign_function("foo");
+        let results = builder.insert_call(foo, vec![v0], vec![Type::field()]).to_vec();
+        builder.terminate_with_return(results);
+        builder.finish()
+    }
+
+    #[test]
+    fn merged_programs_call_each_other_by_name() {
+        // fn main f0 {
+        //   b0(v0: Field):
+        //     v2 = call foo(v0)
+        //     return v2
+        // }
+        //
+        // fn foo f0 {
+        //   b0(v0: Field):
+        //     v1 = add v0, v0
+        //     return v1
+        // }
+        let foo_id = Id::test_new(0);
+        let mut builder = FunctionBuilder::new("foo".into(), foo_id, RuntimeType::Acir);
+        let v0 = builder.add_parameter(Type::field());
+        let v1 = builder.insert_binary(v0, BinaryOp::Add, v0);
+        builder.terminate_with_return(vec![v1]);
+        let foo_program = builder.finish();
+
+        // foo is renumbered to f1 and the call to it is linked
+        let ssa = program_calling_foo().merge(foo_program).unwrap();
+        assert_eq!(ssa.functions.len(), 2);
+        assert_eq!(ssa.main().name(), "main");
+
+        let foo_id = Id::test_new(1);
+        assert_eq!(ssa.functions[&foo_id].name(), "foo");
+        assert_eq!(ssa.functions[&foo_id].id(), foo_id);
+
+        let main = ssa.main();
+        let instructions = main.dfg[main.entry_block()].instructions();
+        match &main.dfg[instructions[0]] {
+            Instruction::Call { func, .. } => {
+                assert_eq!(main.dfg[*func], Value::Function(foo_id));
+            }
+            other => panic!("Expected a call, found {other:?}"),
+        }
+        assert_eq!(ssa.next_id.next(), Id::test_new(2));
+    }
+
+    #[test]
+    fn merging_duplicate_definitions_fails() {
+        assert!(program_calling_foo().merge(program_calling_foo()).is_err());
+    }
+}
@@ -0,0 +1,170 @@
+//! Builders for the monomorphized AST, shared by the tests of SSA generation and by the tests
+//! of passes which start from a program rather than from hand-built SSA.
+use acvm::FieldElement;
+use fm::FileId;
+use noirc_abi::AbiDistinctness;
+use noirc_errors::{Location, Span};
+use noirc_frontend::{
+    monomorphization::ast::{self, Parameters, Program},
+    BinaryOpKind, Signedness,
+};
+
+use crate::{errors::RuntimeError, ssa_refactor::ir::function::RuntimeType};
+
+use super::{
+    context::{FunctionContext, SharedContext},
+    generate_ssa, Ssa,
+};
+
+/// The location given to every expression built here which needs one.
+pub(crate) fn location() -> Location {
+    Location::new(Span::new(0..0), FileId::dummy())
+}
+
+pub(crate) fn unsigned(bit_size: u32) -> ast::Type {
+    ast::Type::Integer(Signedness::Unsigned, bit_size)
+}
+
+pub(crate) fn signed(bit_size: u32) -> ast::Type {
+    ast::Type::Integer(Signedness::Signed, bit_size)
+}
+
+/// An integer literal of the given type.
+pub(crate) fn literal(value: impl Into<FieldElement>, typ: ast::Type) -> ast::Expression {
+    ast::Expression::Literal(ast::Literal::Integer(value.into(), typ))
+}
+
+/// A `Field` literal.
+pub(crate) fn field(value: u128) -> ast::Expression {
+    literal(value, ast::Type::Field)
+}
+
+pub(crate) fn array_literal(
+    contents: Vec<ast::Expression>,
+    element_type: ast::Type,
+) -> ast::Expression {
+    ast::Expression::Literal(ast::Literal::Array(ast::ArrayLiteral { contents, element_type }))
+}
+
+/// An immutable identifier referring to the given definition.
+pub(crate) fn ident(definition: ast::Definition, name: &str, typ: ast::Type) -> ast::Ident {
+    ast::Ident { location: None, definition, mutable: false, name: name.into(), typ }
+}
+
+/// An identifier referring to the given mutable local variable.
+pub(crate) fn mutable_ident(id: ast::LocalId, name: &str, typ: ast::Type) -> ast::Ident {
+    ast::Ident { mutable: true, ..ident(ast::Definition::Local(id), name, typ) }
+}
+
+/// An expression reading the given immutable local variable.
+pub(crate) fn local_ident(id: ast::LocalId, name: &str, typ: ast::Type) -> ast::Expression {
+    ast::Expression::Ident(ident(ast::Definition::Local(id), name, typ))
+}
+
+/// An expression referring to a function with the given definition and signature.
+pub(crate) fn function_ident(
+    definition: ast::Definition,
+    name: &str,
+    parameter_types: Vec<ast::Type>,
+    return_type: ast::Type,
+) -> ast::Expression {
+    let typ = ast::Type::Function(parameter_types, Box::new(return_type));
+    ast::Expression::Ident(ident(definition, name, typ))
+}
+
+/// An expression referring to the builtin function of the given name.
+pub(crate) fn builtin(
+    name: &str,
+    parameter_types: Vec<ast::Type>,
+    return_type: ast::Type,
+) -> ast::Expression {
+    function_ident(ast::Definition::Builtin(name.into()), name, parameter_types, return_type)
+}
+
+pub(crate) fn call(
+    func: ast::Expression,
+    arguments: Vec<ast::Expression>,
+    return_type: ast::Type,
+) -> ast::Expression {
+    let func = Box::new(func);
+    ast::Expression::Call(ast::Call { func, arguments, return_type, location: location() })
+}
+
+/// A call to the function with the given id. Codegen only uses the id of the function, so
+/// every such function is named `foo` and given no parameter types.
+pub(crate) fn function_call(
+    id: u32,
+    arguments: Vec<ast::Expression>,
+    return_type: ast::Type,
+) -> ast::Expression {
+    let definition = ast::Definition::Function(ast::FuncId(id));
+    let func = function_ident(definition, "foo", Vec::new(), return_type.clone());
+    call(func, arguments, return_type)
+}
+
+pub(crate) fn binary(
+    lhs: ast::Expression,
+    operator: BinaryOpKind,
+    rhs: ast::Expression,
+) -> ast::Expression {
+    let (lhs, rhs) = (Box::new(lhs), Box::new(rhs));
+    ast::Expression::Binary(ast::Binary { lhs, operator, rhs, location: location() })
+}
+
+/// An immutable parameter with the given id, name, and type.
+pub(crate) fn parameter(
+    id: ast::LocalId,
+    name: &str,
+    typ: ast::Type,
+) -> (ast::LocalId, bool, String, ast::Type) {
+    (id, false, name.into(), typ)
+}
+
+/// A constrained function with the given id.
+pub(crate) fn function(
+    id: u32,
+    name: &str,
+    parameters: Parameters,
+    body: ast::Expression,
+    return_type: ast::Type,
+) -> ast::Function {
+    let id = ast::FuncId(id);
+    ast::Function { id, name: name.into(), parameters, body, return_type, unconstrained: false }
+}
+
+/// Generates the SSA of a program made of the given functions, the first of which is main.
+/// Panics if codegen reports an error.
+pub(crate) fn generate_functions(functions: Vec<ast::Function>) -> Ssa {
+    let program = Program::new(functions, (Vec::new(), None), AbiDistinctness::Distinct);
+    generate_ssa(program).expect("Expected the program to generate without errors")
+}
+
+/// Creates a shared context for an empty program, for building a single function with
+/// a FunctionContext.
+pub(super) fn empty_shared_context() -> SharedContext {
+    let program = Program::new(Vec::new(), (Vec::new(), None), AbiDistinctness::Distinct);
+    let shared_context = SharedContext::new(program);
+    shared_context.get_or_queue_function(ast::FuncId(0));
+    shared_context
+}
+
+/// Creates a FunctionContext building an ACIR `main` with the given parameters.
+pub(super) fn main_context<'a>(
+    parameters: &Parameters,
+    shared_context: &'a SharedContext,
+) -> FunctionContext<'a> {
+    FunctionContext::new("main".into(), parameters, RuntimeType::Acir, shared_context)
+}
+
+/// Codegens an ACIR `main` with the given parameters and body, returning it along with any
+/// errors reported while generating it.
+pub(super) fn codegen_main(
+    parameters: &Parameters,
+    body: &ast::Expression,
+) -> (Ssa, Vec<RuntimeError>) {
+    let shared_context = empty_shared_context();
+    let mut context = main_context(parameters, &shared_context);
+    context.codegen_function_body(body);
+    let errors = std::mem::take(&mut context.errors);
+    (context.builder.finish(), errors)
+}
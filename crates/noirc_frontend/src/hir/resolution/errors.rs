@@ -42,6 +42,10 @@ pub enum ResolverError {
     InvalidArrayLengthExpr { span: Span },
     #[error("Integer too large to be evaluated in an array length context")]
     IntegerTooLarge { span: Span },
+    #[error("Division by zero in an array length context")]
+    ArrayLengthDivisionByZero { span: Span },
+    #[error("Array length evaluates to a negative number")]
+    NegativeArrayLength { span: Span },
     #[error("No global or generic type parameter found with the given name")]
     NoSuchNumericTypeVariable { path: crate::Path },
     #[error("Closures cannot capture mutable variables")]
@@ -212,6 +216,16 @@ impl From<ResolverError> for Diagnostic {
                 "Array-lengths may be a maximum size of usize::MAX, including intermediate calculations".into(),
                 span,
             ),
+            ResolverError::ArrayLengthDivisionByZero { span } => Diagnostic::simple_error(
+                "Division by zero in an array-length context".into(),
+                "The divisor of this operation evaluates to zero".into(),
+                span,
+            ),
+            ResolverError::NegativeArrayLength { span } => Diagnostic::simple_error(
+                "Array-length evaluates to a negative number".into(),
+                "Array-lengths and their intermediate calculations may not be negative".into(),
+                span,
+            ),
             ResolverError::NoSuchNumericTypeVariable { path } => Diagnostic::simple_error(
                 format!("Cannot find a global or generic type parameter named `{path}`"),
                 "Only globals or generic type parameters are allowed to be used as an array type's length".to_string(),
@@ -33,9 +33,9 @@ use crate::{
     Statement,
 };
 use crate::{
-    ArrayLiteral, ContractFunctionType, Generics, LValue, NoirStruct, Path, Pattern, Shared,
-    StructType, Type, TypeBinding, TypeVariable, UnresolvedGenerics, UnresolvedType,
-    UnresolvedTypeExpression, ERROR_IDENT,
+    ArrayLiteral, BinaryTypeOperator, ContractFunctionType, Generics, LValue, NoirStruct, Path,
+    Pattern, Shared, StructType, Type, TypeBinding, TypeVariable, UnresolvedGenerics,
+    UnresolvedType, UnresolvedTypeExpression, ERROR_IDENT,
 };
 use fm::FileId;
 use iter_extended::vecmap;
@@ -466,15 +466,28 @@ impl<'a> Resolver<'a> {
                 })
             }
             UnresolvedTypeExpression::Constant(int, _) => Type::Constant(int),
-            UnresolvedTypeExpression::BinaryOperation(lhs, op, rhs, _) => {
+            UnresolvedTypeExpression::BinaryOperation(lhs, op, rhs, span) => {
                 let (lhs_span, rhs_span) = (lhs.span(), rhs.span());
                 let lhs = self.convert_expression_type(*lhs);
                 let rhs = self.convert_expression_type(*rhs);
 
                 match (lhs, rhs) {
-                    (Type::Constant(lhs), Type::Constant(rhs)) => {
-                        Type::Constant(op.function()(lhs, rhs))
-                    }
+                    (Type::Constant(lhs), Type::Constant(rhs)) => match op.function()(lhs, rhs) {
+                        Some(result) => Type::Constant(result),
+                        None => {
+                            let error = match op {
+                                BinaryTypeOperator::Division | BinaryTypeOperator::Modulo => {
+                                    ResolverError::ArrayLengthDivisionByZero { span }
+                                }
+                                BinaryTypeOperator::Subtraction => {
+                                    ResolverError::NegativeArrayLength { span }
+                                }
+                                _ => ResolverError::IntegerTooLarge { span },
+                            };
+                            self.push_err(error);
+                            Type::Constant(0)
+                        }
+                    },
                     (lhs, _) => {
                         let span =
                             if !matches!(lhs, Type::Constant(_)) { lhs_span } else { rhs_span };
@@ -1522,4 +1535,31 @@ mod test {
             self.0.insert(name, func_id.into());
         }
     }
+
+    #[test]
+    fn resolve_nested_array_length_arithmetic() {
+        let src = r#"
+            fn main() {
+                let _a: [Field; (1 + 1) * 3 - 2] = [0; 4];
+            }
+        "#;
+
+        let errors = resolve_src_code(src, vec!["main"]);
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn resolve_invalid_array_length_arithmetic() {
+        let src = r#"
+            fn main() {
+                let _a: [Field; 3 / (1 - 1)] = [0; 1];
+                let _b: [Field; 1 - 2] = [0; 1];
+            }
+        "#;
+
+        let errors = resolve_src_code(src, vec!["main"]);
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(errors[0], ResolverError::ArrayLengthDivisionByZero { .. }));
+        assert!(matches!(errors[1], ResolverError::NegativeArrayLength { .. }));
+    }
 }
@@ -303,6 +303,17 @@ mod test {
         type_check_src_code(src, vec![String::from("main"), String::from("foo")]);
     }
 
+    #[test]
+    fn nested_array_length_arithmetic_is_evaluated() {
+        let src = r#"
+            fn main(x : Field) {
+                let _a: [Field; (1 + 1) * 3] = [x, x, x, x, x, x];
+            }
+        "#;
+
+        type_check_src_code(src, vec![String::from("main")]);
+    }
+
     // This is the same Stub that is in the resolver, maybe we can pull this out into a test module and re-use?
     struct TestPathResolver(HashMap<String, ModuleDefId>);
 
@@ -1385,14 +1385,15 @@ impl Type {
 }
 
 impl BinaryTypeOperator {
-    /// Return the actual rust numeric function associated with this operator
-    pub fn function(self) -> fn(u64, u64) -> u64 {
+    /// Return the actual rust numeric function associated with this operator.
+    /// The function returns None if the operation overflows, underflows, or divides by zero.
+    pub fn function(self) -> fn(u64, u64) -> Option<u64> {
         match self {
-            BinaryTypeOperator::Addition => |a, b| a.wrapping_add(b),
-            BinaryTypeOperator::Subtraction => |a, b| a.wrapping_sub(b),
-            BinaryTypeOperator::Multiplication => |a, b| a.wrapping_mul(b),
-            BinaryTypeOperator::Division => |a, b| a.wrapping_div(b),
-            BinaryTypeOperator::Modulo => |a, b| a.wrapping_rem(b), // % b,
+            BinaryTypeOperator::Addition => |a, b| a.checked_add(b),
+            BinaryTypeOperator::Subtraction => |a, b| a.checked_sub(b),
+            BinaryTypeOperator::Multiplication => |a, b| a.checked_mul(b),
+            BinaryTypeOperator::Division => |a, b| a.checked_div(b),
+            BinaryTypeOperator::Modulo => |a, b| a.checked_rem(b),
         }
     }
 }
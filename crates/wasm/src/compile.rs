@@ -103,7 +103,7 @@ pub fn compile(args: JsValue) -> JsValue {
     } else {
         let main = driver.main_function().expect("Could not find main function!");
         let compiled_program =
-            driver.compile_no_check(&options.compile_options, main).expect("Compilation failed");
+            driver.compile_no_check(&options.compile_options, main).expect("Compilation failed").0;
 
         <JsValue as JsValueSerdeExt>::from_serde(&compiled_program).unwrap()
     }